@@ -1,7 +1,16 @@
 use std::{
+    collections::HashMap,
     fmt::Display,
     hash::Hash,
-    ops::{Add, Div, Mul, Sub},
+    ops::{Add, Div, Mul, Range, Sub},
+    sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+#[cfg(feature = "bigint")]
+use crate::bigint::{self, BigInt};
+use crate::{
+    interpreter::Interpreter,
+    messages::{self, message},
 };
 
 pub const IDENT_MAP: phf::Map<&str, TokenType> = phf::phf_map! {
@@ -23,6 +32,8 @@ pub const IDENT_MAP: phf::Map<&str, TokenType> = phf::phf_map! {
     "const" => TokenType::CONST,
     "while" => TokenType::WHILE,
     "dump" => TokenType::DUMP,
+    "match" => TokenType::MATCH,
+    "assert" => TokenType::ASSERT,
 };
 
 #[derive(Debug, PartialEq)]
@@ -34,32 +45,113 @@ pub enum LoxError {
     },
     RuntimeException,
     ExitCode(i32),
-    ScanError(char),
+    ///everything the scanner rejected, first to last
+    ScanError(Vec<Diagnostic>),
     ParseError(ParserError),
+    InvalidConversion {
+        from: &'static str,
+        to: &'static str,
+    },
+    Io(IoError),
+    Config {
+        path: String,
+        line: usize,
+        message: String,
+    },
+    ///a session file that can't be saved or restored
+    Session {
+        path: String,
+        message: String,
+    },
+}
+
+///an io::Error that compares equal to another of the same kind, keeping
+///LoxError comparable
+#[derive(Debug)]
+pub struct IoError(pub std::io::Error);
+
+impl PartialEq for IoError {
+    fn eq(&self, other: &Self) -> bool {
+        return self.0.kind() == other.0.kind();
+    }
 }
 
 impl Display for LoxError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             LoxError::RuntimeException => {
-                write!(f, "Unhandled runtime exception")
+                write!(f, "{}", message("runtime-exception", &[]))
             }
             LoxError::ExitCode(c) => {
-                write!(f, "Lox exited with code {c}")
+                write!(f, "{}", message("exit-code", &[("code", c)]))
             }
-            LoxError::ScanError(c) => {
-                write!(f, "Error while reading char {c}")
+            LoxError::ScanError(errors) => {
+                let errors = errors.iter().map(Diagnostic::to_string);
+                write!(f, "{}", errors.collect::<Vec<String>>().join("\n"))
             }
             LoxError::ParseError(e) => {
-                write!(f, "Parser error: {e}")
+                write!(f, "{}", message("parser-error", &[("error", e)]))
             }
             LoxError::InvalidToken {
                 token_type,
                 line,
                 loc,
             } => {
-                write!(f, "Invalid token {token_type:?} at {line}:{loc}")
+                let token = format!("{token_type:?}");
+                let args: &[(&str, &dyn Display)] =
+                    &[("token", &token), ("line", line), ("location", loc)];
+                write!(f, "{}", message("invalid-token", args))
+            }
+            LoxError::InvalidConversion { from, to } => {
+                let args: &[(&str, &dyn Display)] = &[("from", from), ("to", to)];
+                write!(f, "{}", message("invalid-conversion", args))
+            }
+            LoxError::Io(e) => {
+                write!(f, "{}", message("io-error", &[("error", &e.0)]))
+            }
+            LoxError::Config {
+                path,
+                line,
+                message,
+            } => {
+                write!(f, "{path}:{line}: {message}")
             }
+            LoxError::Session {
+                path,
+                message: text,
+            } => {
+                let args: &[(&str, &dyn Display)] = &[("path", path), ("message", text)];
+                write!(f, "{}", message("session-error", args))
+            }
+        }
+    }
+}
+
+impl LoxError {
+    ///the stable code of the error, only errors in the script have one
+    pub fn code(&self) -> Option<&'static str> {
+        return match self {
+            LoxError::ParseError(e) => e.code(),
+            LoxError::ScanError(errors) => errors.first().and_then(|error| error.code),
+            _ => None,
+        };
+    }
+
+    ///where a runtime error was raised, see `ParserError::location`
+    pub fn location(&self) -> Option<(usize, usize, Range<usize>)> {
+        return match self {
+            LoxError::ParseError(e) => e.location(),
+            _ => None,
+        };
+    }
+}
+
+impl std::error::Error for LoxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoxError::ParseError(e) => Some(e),
+            LoxError::Io(e) => Some(&e.0),
+            _ => None,
         }
     }
 }
@@ -70,7 +162,52 @@ impl From<ParserError> for LoxError {
     }
 }
 
-#[derive(Debug, Clone)]
+impl From<std::io::Error> for LoxError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(IoError(value))
+    }
+}
+
+///a problem found in the source, reported without aborting the scan
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub line: usize,
+    ///1-based, in chars, 0 when unknown
+    pub column: usize,
+    ///byte range of the source the problem covers
+    pub span: Range<usize>,
+    ///the stable code of the problem, like `E2001`
+    pub code: Option<&'static str>,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", Position(self.line, self.column), self.message)
+    }
+}
+
+///`line 3, column 5`, the column is left out when it's unknown
+pub struct Position(pub usize, pub usize);
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Position(line, 0) => write!(f, "line {}", line),
+            Position(line, column) => write!(f, "line {}, column {}", line, column),
+        }
+    }
+}
+
+///where the resolver found a name bound, `depth` counts the scopes between
+///the use and the binding and `slot` is the binding's place in its scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Local {
+    pub depth: usize,
+    pub slot: usize,
+}
+
+#[derive(Debug, Default)]
 pub enum Expression {
     Binary {
         left: Box<Expression>,
@@ -81,148 +218,830 @@ pub enum Expression {
         operator: Token,
         right: Box<Expression>,
     },
-    Grouping(Box<Expression>),
-    Literal(TokenLiteral),
-    Variable(Token),
+    ///an operator that only evaluates `right` when it needs to, so far
+    ///only `??`
+    Logical {
+        left: Box<Expression>,
+        operator: Token,
+        right: Box<Expression>,
+    },
+    ///the range includes the parentheses
+    Grouping(Box<Expression>, Range<usize>),
+    Literal(TokenLiteral, Range<usize>),
+    ///`local` is set by the resolver for names bound inside the program
+    Variable {
+        name: Token,
+        local: Option<Local>,
+    },
+    Assign {
+        name: Token,
+        value: Box<Expression>,
+        local: Option<Local>,
+    },
+    Match {
+        keyword: Token,
+        subject: Box<Expression>,
+        arms: Vec<MatchArm>,
+        span: Range<usize>,
+    },
+    ListLiteral {
+        bracket: Token,
+        elements: Vec<Expression>,
+        span: Range<usize>,
+    },
+    MapLiteral {
+        brace: Token,
+        entries: Vec<(Expression, Expression)>,
+        span: Range<usize>,
+    },
+    ///`(a, b)`, a single element needs a trailing comma
+    TupleLiteral {
+        paren: Token,
+        elements: Vec<Expression>,
+        span: Range<usize>,
+    },
+    Index {
+        object: Box<Expression>,
+        ///the closing bracket, errors are reported at it
+        bracket: Token,
+        index: Box<Expression>,
+    },
+    IndexAssign {
+        object: Box<Expression>,
+        bracket: Token,
+        index: Box<Expression>,
+        value: Box<Expression>,
+    },
+    #[default]
     Empty,
 }
 
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: Expression,
+}
+
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Literal(TokenLiteral),
+    Type(LiteralType),
+    Binding(Token),
+    Wildcard,
+}
+
+impl Pattern {
+    ///whether this pattern matches any value
+    pub fn is_catch_all(&self) -> bool {
+        return matches!(self, Pattern::Binding(_) | Pattern::Wildcard);
+    }
+}
+
+///which values conditions treat as false
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Truthiness {
+    ///nil, false, 0 and 0.0
+    #[default]
+    Rlox,
+    ///only nil and false, as in the book
+    Strict,
+}
+
+impl Truthiness {
+    pub fn from_name(name: &str) -> Result<Truthiness, String> {
+        return match name {
+            "rlox" => Ok(Truthiness::Rlox),
+            "strict" => Ok(Truthiness::Strict),
+            other => Err(format!("Unknown truthiness '{}'", other)),
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LiteralType {
+    Number,
+    Integer,
+    Float,
+    String,
+    Boolean,
+    Nil,
+    List,
+    Map,
+    Tuple,
+}
+
+impl LiteralType {
+    pub fn from_name(name: &str) -> Option<LiteralType> {
+        match name {
+            "Number" => Some(LiteralType::Number),
+            "Integer" => Some(LiteralType::Integer),
+            "Float" => Some(LiteralType::Float),
+            "String" => Some(LiteralType::String),
+            "Boolean" => Some(LiteralType::Boolean),
+            "Nil" => Some(LiteralType::Nil),
+            "List" => Some(LiteralType::List),
+            "Map" => Some(LiteralType::Map),
+            "Tuple" => Some(LiteralType::Tuple),
+            _ => None,
+        }
+    }
+
+    ///the name `from_name` takes
+    pub fn name(&self) -> &'static str {
+        return match self {
+            LiteralType::Number => "Number",
+            LiteralType::Integer => "Integer",
+            LiteralType::Float => "Float",
+            LiteralType::String => "String",
+            LiteralType::Boolean => "Boolean",
+            LiteralType::Nil => "Nil",
+            LiteralType::List => "List",
+            LiteralType::Map => "Map",
+            LiteralType::Tuple => "Tuple",
+        };
+    }
+
+    pub fn matches(&self, literal: &TokenLiteral) -> bool {
+        match self {
+            LiteralType::Number => literal.is_number(),
+            LiteralType::Integer => {
+                literal.is_number() && !matches!(literal, TokenLiteral::Float(_))
+            }
+            LiteralType::Float => matches!(literal, TokenLiteral::Float(_)),
+            LiteralType::String => matches!(literal, TokenLiteral::String(_)),
+            LiteralType::Boolean => matches!(literal, TokenLiteral::Boolean(_)),
+            LiteralType::Nil => matches!(literal, TokenLiteral::Empty),
+            LiteralType::List => matches!(literal, TokenLiteral::List(_)),
+            LiteralType::Map => matches!(literal, TokenLiteral::Map(_)),
+            LiteralType::Tuple => matches!(literal, TokenLiteral::Tuple(_)),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum ParserError {
     UnsupportedAction,
+    ///an operator applied to operands it doesn't support, the interpreter
+    ///turns it into a `RuntimeError` at the operator
+    TypeMismatch {
+        operator: &'static str,
+        left: &'static str,
+        right: &'static str,
+    },
+    ///an integer operation past isize, only without the bigint feature
+    IntegerOverflow {
+        operator: &'static str,
+    },
+    ///a runtime error at the token that caused it
+    RuntimeError {
+        message: String,
+        ///the stable code of the error it was raised as
+        code: Option<&'static str>,
+        line: usize,
+        column: usize,
+        span: Range<usize>,
+        token: String,
+    },
     Generic(String),
+    LimitExceeded {
+        limit: &'static str,
+        max: usize,
+    },
+    ///the interpreter's step budget ran out, the script stops
+    FuelExhausted,
+    UndefinedVariable {
+        name: String,
+        ///the closest name that is defined, when one is close enough
+        suggestion: Option<String>,
+        line: usize,
+        column: usize,
+        span: Range<usize>,
+    },
+    IndexOutOfBounds {
+        index: isize,
+        len: usize,
+        line: usize,
+        column: usize,
+        span: Range<usize>,
+    },
+    NotIndexable {
+        type_name: &'static str,
+        line: usize,
+        column: usize,
+        span: Range<usize>,
+    },
+    IndexType {
+        type_name: &'static str,
+        line: usize,
+        column: usize,
+        span: Range<usize>,
+    },
+    MissingKey {
+        key: String,
+        line: usize,
+        column: usize,
+        span: Range<usize>,
+    },
+    UnhashableKey {
+        type_name: &'static str,
+        line: usize,
+        column: usize,
+        span: Range<usize>,
+    },
+    NotIterable {
+        type_name: &'static str,
+        line: usize,
+        column: usize,
+        span: Range<usize>,
+    },
+    DestructureType {
+        kind: &'static str,
+        type_name: &'static str,
+        line: usize,
+        column: usize,
+        span: Range<usize>,
+    },
+    DestructureArity {
+        kind: &'static str,
+        expected: usize,
+        len: usize,
+        line: usize,
+        column: usize,
+        span: Range<usize>,
+    },
+    AssertionFailed {
+        ///the asserted expression as written
+        expression: String,
+        message: Option<String>,
+        line: usize,
+        column: usize,
+        span: Range<usize>,
+    },
 }
 
 impl Display for ParserError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ParserError::UnsupportedAction => write!(f, "Unsupported Action"),
-            ParserError::Generic(s) => write!(f, "Generic Error({})", s),
+            ParserError::UnsupportedAction => write!(f, "{}", message("unsupported-action", &[])),
+            ParserError::TypeMismatch {
+                operator,
+                left,
+                right,
+            } => {
+                let args: &[(&str, &dyn Display)] =
+                    &[("operator", operator), ("left", left), ("right", right)];
+                write!(f, "{}", message("type-mismatch", args))
+            }
+            ParserError::IntegerOverflow { operator } => {
+                write!(
+                    f,
+                    "{}",
+                    message("integer-overflow", &[("operator", operator)])
+                )
+            }
+            ParserError::RuntimeError {
+                message,
+                line,
+                column,
+                ..
+            } => self.write_located(f, *line, *column, message),
+            ParserError::Generic(s) => {
+                write!(f, "{}", message("generic-error", &[("message", s)]))
+            }
+            ParserError::FuelExhausted => write!(f, "{}", message("fuel-exhausted", &[])),
+            ParserError::LimitExceeded { limit, max } => {
+                let args: &[(&str, &dyn Display)] = &[("limit", limit), ("max", max)];
+                write!(f, "{}", message("limit-exceeded", args))
+            }
+            ParserError::UndefinedVariable {
+                name,
+                suggestion,
+                line,
+                column,
+                ..
+            } => {
+                let text = match suggestion {
+                    Some(suggestion) => message(
+                        "undefined-variable-suggestion",
+                        &[("name", name), ("suggestion", suggestion)],
+                    ),
+                    None => message("undefined-variable", &[("name", name)]),
+                };
+                self.write_located(f, *line, *column, &text)
+            }
+            ParserError::IndexOutOfBounds {
+                index,
+                len,
+                line,
+                column,
+                ..
+            } => {
+                let args: &[(&str, &dyn Display)] = &[("index", index), ("len", len)];
+                self.write_located(f, *line, *column, &message("index-out-of-bounds", args))
+            }
+            ParserError::NotIndexable {
+                type_name,
+                line,
+                column,
+                ..
+            } => {
+                let text = message("not-indexable", &[("type", type_name)]);
+                self.write_located(f, *line, *column, &text)
+            }
+            ParserError::IndexType {
+                type_name,
+                line,
+                column,
+                ..
+            } => {
+                let text = message("index-type", &[("type", type_name)]);
+                self.write_located(f, *line, *column, &text)
+            }
+            ParserError::MissingKey {
+                key, line, column, ..
+            } => self.write_located(f, *line, *column, &message("missing-key", &[("key", key)])),
+            ParserError::UnhashableKey {
+                type_name,
+                line,
+                column,
+                ..
+            } => {
+                let text = message("unhashable-key", &[("type", type_name)]);
+                self.write_located(f, *line, *column, &text)
+            }
+            ParserError::NotIterable {
+                type_name,
+                line,
+                column,
+                ..
+            } => {
+                let text = message("not-iterable", &[("type", type_name)]);
+                self.write_located(f, *line, *column, &text)
+            }
+            ParserError::DestructureType {
+                kind,
+                type_name,
+                line,
+                column,
+                ..
+            } => {
+                let args: &[(&str, &dyn Display)] = &[("kind", kind), ("type", type_name)];
+                self.write_located(f, *line, *column, &message("destructure-type", args))
+            }
+            ParserError::DestructureArity {
+                kind,
+                expected,
+                len,
+                line,
+                column,
+                ..
+            } => {
+                let args: &[(&str, &dyn Display)] =
+                    &[("kind", kind), ("expected", expected), ("len", len)];
+                self.write_located(f, *line, *column, &message("destructure-arity", args))
+            }
+            ParserError::AssertionFailed {
+                expression,
+                message: None,
+                line,
+                column,
+                ..
+            } => {
+                let text = message("assertion-failed", &[("expression", expression)]);
+                self.write_located(f, *line, *column, &text)
+            }
+            ParserError::AssertionFailed {
+                expression,
+                message: Some(reason),
+                line,
+                column,
+                ..
+            } => {
+                let args: &[(&str, &dyn Display)] =
+                    &[("expression", expression), ("message", reason)];
+                let text = message("assertion-failed-with-message", args);
+                self.write_located(f, *line, *column, &text)
+            }
         }
     }
 }
 
+impl std::error::Error for ParserError {}
+
+impl ParserError {
+    ///the stable code of the error, `None` for generic errors that were
+    ///reported with their own code when they were raised
+    pub fn code(&self) -> Option<&'static str> {
+        let key = match self {
+            ParserError::RuntimeError { code, .. } => return *code,
+            ParserError::Generic(_) => return None,
+            ParserError::UnsupportedAction => "unsupported-action",
+            ParserError::TypeMismatch { .. } => "type-mismatch",
+            ParserError::IntegerOverflow { .. } => "integer-overflow",
+            ParserError::LimitExceeded { .. } => "limit-exceeded",
+            ParserError::FuelExhausted => "fuel-exhausted",
+            ParserError::UndefinedVariable { .. } => "undefined-variable",
+            ParserError::IndexOutOfBounds { .. } => "index-out-of-bounds",
+            ParserError::NotIndexable { .. } => "not-indexable",
+            ParserError::IndexType { .. } => "index-type",
+            ParserError::MissingKey { .. } => "missing-key",
+            ParserError::UnhashableKey { .. } => "unhashable-key",
+            ParserError::NotIterable { .. } => "not-iterable",
+            ParserError::DestructureType { .. } => "destructure-type",
+            ParserError::DestructureArity { .. } => "destructure-arity",
+            ParserError::AssertionFailed { .. } => "assertion-failed",
+        };
+        return messages::code(key);
+    }
+
+    ///the line, column and byte range of the token the error was raised
+    ///at, `None` for errors that aren't tied to one
+    pub fn location(&self) -> Option<(usize, usize, Range<usize>)> {
+        return match self {
+            ParserError::RuntimeError {
+                line, column, span, ..
+            }
+            | ParserError::UndefinedVariable {
+                line, column, span, ..
+            }
+            | ParserError::IndexOutOfBounds {
+                line, column, span, ..
+            }
+            | ParserError::NotIndexable {
+                line, column, span, ..
+            }
+            | ParserError::IndexType {
+                line, column, span, ..
+            }
+            | ParserError::MissingKey {
+                line, column, span, ..
+            }
+            | ParserError::UnhashableKey {
+                line, column, span, ..
+            }
+            | ParserError::NotIterable {
+                line, column, span, ..
+            }
+            | ParserError::DestructureType {
+                line, column, span, ..
+            }
+            | ParserError::DestructureArity {
+                line, column, span, ..
+            }
+            | ParserError::AssertionFailed {
+                line, column, span, ..
+            } => Some((*line, *column, span.clone())),
+            ParserError::UnsupportedAction
+            | ParserError::TypeMismatch { .. }
+            | ParserError::IntegerOverflow { .. }
+            | ParserError::Generic(_)
+            | ParserError::LimitExceeded { .. }
+            | ParserError::FuelExhausted => None,
+        };
+    }
+
+    ///`text` after the position and code of the error
+    fn write_located(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        line: usize,
+        column: usize,
+        text: &str,
+    ) -> std::fmt::Result {
+        let text = match self.code() {
+            Some(code) => message("runtime-error-at", &[("code", &code), ("message", &text)]),
+            None => text.to_string(),
+        };
+        write!(f, "[{}] {}", Position(line, column), text)
+    }
+
+    ///the error for an operator that doesn't apply to `left` and `right`
+    pub fn type_mismatch(
+        operator: &'static str,
+        left: &TokenLiteral,
+        right: &TokenLiteral,
+    ) -> ParserError {
+        return ParserError::TypeMismatch {
+            operator,
+            left: left.type_name(),
+            right: right.type_name(),
+        };
+    }
+
+    ///gives a type mismatch or overflow the line of the operator that
+    ///raised it
+    pub fn at(self, operator: &Token) -> ParserError {
+        return match self {
+            ParserError::TypeMismatch { .. } | ParserError::IntegerOverflow { .. } => {
+                ParserError::runtime(operator, self.code(), self.to_string())
+            }
+            e => e,
+        };
+    }
+
+    pub fn runtime(token: &Token, code: Option<&'static str>, message: String) -> ParserError {
+        return ParserError::RuntimeError {
+            message,
+            code,
+            line: token.line,
+            column: token.column,
+            span: token.span(),
+            token: token.lexeme.clone(),
+        };
+    }
+}
+
 impl Expression {
+    ///evaluates the expression without any interpreter state
     pub fn evaluate(&self) -> Result<TokenLiteral, ParserError> {
-        return match self {
-            crate::ast::Expression::Binary {
-                left,
-                operator,
-                right,
-            } => {
-                let left = left.evaluate()?;
-                let right = right.evaluate()?;
-                match operator.token_type {
-                    TokenType::MINUS => {
-                        if !self.check_number_operand(operator, &right) {
-                            return Err(ParserError::UnsupportedAction);
-                        }
-                        return left.sub(right);
-                    }
-                    TokenType::PLUS => {
-                        return left.add(right);
-                    }
-                    TokenType::SLASH => {
-                        if !self.check_number_operand(operator, &right) {
-                            return Err(ParserError::UnsupportedAction);
-                        }
-                        return left / right;
-                    }
-                    TokenType::STAR => {
-                        if !self.check_number_operand(operator, &right) {
-                            return Err(ParserError::UnsupportedAction);
-                        }
-                        return left * right;
-                    }
-                    TokenType::EXPONENT => {
-                        if !self.check_number_operand(operator, &right) {
-                            return Err(ParserError::UnsupportedAction);
-                        }
-                        return left.pow(right);
-                    }
-                    TokenType::GREATER => {
-                        if !self.check_number_operand(operator, &right) {
-                            return Err(ParserError::UnsupportedAction);
-                        }
-                        if let TokenLiteral::Integer(left) = left {
-                            if let TokenLiteral::Integer(right) = right {
-                                return Ok(TokenLiteral::Boolean(left > right));
-                            }
-                        }
-                        return Ok(TokenLiteral::Empty);
-                    }
-                    TokenType::GREATER_EQUAL => {
-                        if !self.check_number_operand(operator, &right) {
-                            return Err(ParserError::UnsupportedAction);
-                        }
-                        if let TokenLiteral::Integer(left) = left {
-                            if let TokenLiteral::Integer(right) = right {
-                                return Ok(TokenLiteral::Boolean(left >= right));
-                            }
-                        }
-                        return Ok(TokenLiteral::Empty);
-                    }
-                    TokenType::LESS => {
-                        if !self.check_number_operand(operator, &right) {
-                            return Err(ParserError::UnsupportedAction);
-                        }
-                        if let TokenLiteral::Integer(left) = left {
-                            if let TokenLiteral::Integer(right) = right {
-                                return Ok(TokenLiteral::Boolean(left < right));
-                            }
-                        }
-                        return Ok(TokenLiteral::Empty);
-                    }
-                    TokenType::LESS_EQUAL => {
-                        if !self.check_number_operand(operator, &right) {
-                            return Err(ParserError::UnsupportedAction);
-                        }
-                        if let TokenLiteral::Integer(left) = left {
-                            if let TokenLiteral::Integer(right) = right {
-                                return Ok(TokenLiteral::Boolean(left <= right));
-                            }
-                        }
-                        return Ok(TokenLiteral::Empty);
-                    }
-                    TokenType::BANG_EQUAL => {
-                        return Ok(TokenLiteral::Boolean(!left.is_equal(right)));
-                    }
-                    TokenType::EQUAL_EQUAL => {
-                        return Ok(TokenLiteral::Boolean(left.is_equal(right)));
-                    }
-                    _ => todo!(),
+        return Interpreter::new().evaluate(self);
+    }
+
+    ///line of a token in the expression, literals don't keep one
+    pub fn line(&self) -> Option<usize> {
+        // the leftmost token with a line, or the operator or bracket right
+        // after the leftmost operand when that has none
+        let mut expr = self;
+        let mut fallback = None;
+        loop {
+            let line = match expr {
+                Expression::Binary { left, operator, .. }
+                | Expression::Logical { left, operator, .. } => {
+                    fallback = Some(operator.line);
+                    expr = left;
+                    continue;
                 }
-            }
-            crate::ast::Expression::Unary { operator, right } => {
-                let right = right.evaluate()?;
-                match operator.token_type {
-                    TokenType::MINUS => {
-                        if !self.check_number_operand(operator, &right) {
-                            return Err(ParserError::UnsupportedAction);
-                        }
-                        if let TokenLiteral::Integer(n) = right {
-                            return Ok(TokenLiteral::Integer(-n));
-                        }
-                        todo!()
-                    }
-                    TokenType::BANG => {
-                        return Ok(TokenLiteral::Boolean(!right.is_truthy()));
-                    }
-                    _ => todo!(),
+                Expression::Index {
+                    object, bracket, ..
                 }
+                | Expression::IndexAssign {
+                    object, bracket, ..
+                } => {
+                    fallback = Some(bracket.line);
+                    expr = object;
+                    continue;
+                }
+                Expression::Grouping(inner, _) => {
+                    expr = inner;
+                    continue;
+                }
+                Expression::Unary { operator, .. } => Some(operator.line),
+                Expression::Variable { name, .. } | Expression::Assign { name, .. } => {
+                    Some(name.line)
+                }
+                Expression::Match { keyword, .. } => Some(keyword.line),
+                Expression::ListLiteral { bracket, .. } => Some(bracket.line),
+                Expression::MapLiteral { brace, .. } => Some(brace.line),
+                Expression::TupleLiteral { paren, .. } => Some(paren.line),
+                Expression::Literal(..) | Expression::Empty => None,
+            };
+            return line.or(fallback);
+        }
+    }
+
+    ///byte range of the expression in the parsed source, empty for
+    ///`Expression::Empty`
+    pub fn span(&self) -> Range<usize> {
+        return self.start()..self.end();
+    }
+
+    ///where the span starts, following the left operands of a chain in a
+    ///loop since a long one nests as deep as it has operators
+    fn start(&self) -> usize {
+        let mut expr = self;
+        loop {
+            expr = match expr {
+                Expression::Binary { left, .. }
+                | Expression::Logical { left, .. }
+                | Expression::Index { object: left, .. }
+                | Expression::IndexAssign { object: left, .. } => left,
+                Expression::Unary { operator, .. } => return operator.offset,
+                Expression::Grouping(_, span)
+                | Expression::Literal(_, span)
+                | Expression::Match { span, .. }
+                | Expression::ListLiteral { span, .. }
+                | Expression::MapLiteral { span, .. }
+                | Expression::TupleLiteral { span, .. } => return span.start,
+                Expression::Variable { name, .. } | Expression::Assign { name, .. } => {
+                    return name.offset
+                }
+                Expression::Empty => return 0,
+            };
+        }
+    }
+
+    ///where the span ends, the right hand side counterpart of `start`
+    fn end(&self) -> usize {
+        let mut expr = self;
+        loop {
+            expr = match expr {
+                Expression::Binary { right, .. }
+                | Expression::Logical { right, .. }
+                | Expression::Unary { right, .. }
+                | Expression::Assign { value: right, .. }
+                | Expression::IndexAssign { value: right, .. } => right,
+                Expression::Index { bracket, .. } => return bracket.span().end,
+                Expression::Grouping(_, span)
+                | Expression::Literal(_, span)
+                | Expression::Match { span, .. }
+                | Expression::ListLiteral { span, .. }
+                | Expression::MapLiteral { span, .. }
+                | Expression::TupleLiteral { span, .. } => return span.end,
+                Expression::Variable { name, .. } => return name.span().end,
+                Expression::Empty => return 0,
+            };
+        }
+    }
+
+    ///the subexpressions in source order, a match arm's body after its
+    ///subject and a map's values after their keys
+    fn operands(&self) -> Vec<&Expression> {
+        return match self {
+            Expression::Binary { left, right, .. } | Expression::Logical { left, right, .. } => {
+                vec![left, right]
+            }
+            Expression::Unary { right: inner, .. }
+            | Expression::Grouping(inner, _)
+            | Expression::Assign { value: inner, .. } => vec![inner],
+            Expression::Match { subject, arms, .. } => {
+                let mut operands = vec![subject.as_ref()];
+                operands.extend(arms.iter().map(|arm| &arm.body));
+                operands
             }
-            Expression::Grouping(sub_expr) => sub_expr.evaluate(),
-            Expression::Literal(lit) => Ok(lit.clone()),
-            Expression::Empty => Ok(TokenLiteral::Empty),
-            Expression::Variable(token) => Ok(token.literal.clone()),
+            Expression::ListLiteral { elements, .. }
+            | Expression::TupleLiteral { elements, .. } => elements.iter().collect(),
+            Expression::MapLiteral { entries, .. } => entries
+                .iter()
+                .flat_map(|(key, value)| [key, value])
+                .collect(),
+            Expression::Index { object, index, .. } => vec![object, index],
+            Expression::IndexAssign {
+                object,
+                index,
+                value,
+                ..
+            } => vec![object, index, value],
+            Expression::Literal(_, _) | Expression::Variable { .. } | Expression::Empty => vec![],
+        };
+    }
 
+    ///a copy of this node over `operands`, which are in the order
+    ///`operands` returns them
+    fn with_operands(&self, operands: Vec<Expression>) -> Expression {
+        let mut operands = operands.into_iter();
+        let mut next = || operands.next().unwrap_or_default();
+        return match self {
+            Expression::Binary { operator, .. } => Expression::Binary {
+                left: Box::new(next()),
+                operator: operator.clone(),
+                right: Box::new(next()),
+            },
+            Expression::Logical { operator, .. } => Expression::Logical {
+                left: Box::new(next()),
+                operator: operator.clone(),
+                right: Box::new(next()),
+            },
+            Expression::Unary { operator, .. } => Expression::Unary {
+                operator: operator.clone(),
+                right: Box::new(next()),
+            },
+            Expression::Grouping(_, span) => Expression::Grouping(Box::new(next()), span.clone()),
+            Expression::Assign { name, local, .. } => Expression::Assign {
+                name: name.clone(),
+                value: Box::new(next()),
+                local: *local,
+            },
+            Expression::Match {
+                keyword,
+                arms,
+                span,
+                ..
+            } => Expression::Match {
+                keyword: keyword.clone(),
+                subject: Box::new(next()),
+                arms: arms
+                    .iter()
+                    .map(|arm| MatchArm {
+                        pattern: arm.pattern.clone(),
+                        body: next(),
+                    })
+                    .collect(),
+                span: span.clone(),
+            },
+            Expression::ListLiteral { bracket, span, .. } => Expression::ListLiteral {
+                bracket: bracket.clone(),
+                elements: operands.collect(),
+                span: span.clone(),
+            },
+            Expression::TupleLiteral { paren, span, .. } => Expression::TupleLiteral {
+                paren: paren.clone(),
+                elements: operands.collect(),
+                span: span.clone(),
+            },
+            Expression::MapLiteral {
+                brace,
+                entries,
+                span,
+            } => Expression::MapLiteral {
+                brace: brace.clone(),
+                entries: entries.iter().map(|_| (next(), next())).collect(),
+                span: span.clone(),
+            },
+            Expression::Index { bracket, .. } => Expression::Index {
+                object: Box::new(next()),
+                bracket: bracket.clone(),
+                index: Box::new(next()),
+            },
+            Expression::IndexAssign { bracket, .. } => Expression::IndexAssign {
+                object: Box::new(next()),
+                bracket: bracket.clone(),
+                index: Box::new(next()),
+                value: Box::new(next()),
+            },
+            Expression::Literal(literal, span) => {
+                Expression::Literal(literal.clone(), span.clone())
+            }
+            Expression::Variable { name, local } => Expression::Variable {
+                name: name.clone(),
+                local: *local,
+            },
+            Expression::Empty => Expression::Empty,
         };
     }
 
-    fn check_number_operand(&self, _operator: &Token, operand: &TokenLiteral) -> bool {
-        match operand {
-            TokenLiteral::Integer(_) | TokenLiteral::Float(_) => true,
-            _ => false,
+    ///moves the subexpressions out onto `out`, leaving `Empty` behind
+    fn take_operands(&mut self, out: &mut Vec<Expression>) {
+        match self {
+            Expression::Binary { left, right, .. } | Expression::Logical { left, right, .. } => {
+                out.push(std::mem::take(&mut **left));
+                out.push(std::mem::take(&mut **right));
+            }
+            Expression::Unary { right: inner, .. }
+            | Expression::Grouping(inner, _)
+            | Expression::Assign { value: inner, .. } => {
+                out.push(std::mem::take(&mut **inner));
+            }
+            Expression::Match { subject, arms, .. } => {
+                out.push(std::mem::take(&mut **subject));
+                out.extend(arms.drain(..).map(|arm| arm.body));
+            }
+            Expression::ListLiteral { elements, .. }
+            | Expression::TupleLiteral { elements, .. } => out.append(elements),
+            Expression::MapLiteral { entries, .. } => {
+                for (key, value) in entries.drain(..) {
+                    out.push(key);
+                    out.push(value);
+                }
+            }
+            Expression::Index { object, index, .. } => {
+                out.push(std::mem::take(&mut **object));
+                out.push(std::mem::take(&mut **index));
+            }
+            Expression::IndexAssign {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                out.push(std::mem::take(&mut **object));
+                out.push(std::mem::take(&mut **index));
+                out.push(std::mem::take(&mut **value));
+            }
+            Expression::Literal(_, _) | Expression::Variable { .. } | Expression::Empty => {}
+        }
+    }
+}
+
+///copies the tree with a stack of its own, the derived clone would
+///recurse once per operator of a chain
+impl Clone for Expression {
+    fn clone(&self) -> Expression {
+        // each node is visited twice, its copy is built the second time
+        // from the copies of its operands on top of `copies`
+        let mut tasks = vec![(self, false)];
+        let mut copies = vec![];
+        while let Some((expr, operands_copied)) = tasks.pop() {
+            let operands = expr.operands();
+            if operands_copied {
+                let operands = copies.split_off(copies.len() - operands.len());
+                copies.push(expr.with_operands(operands));
+                continue;
+            }
+            tasks.push((expr, true));
+            tasks.extend(operands.into_iter().rev().map(|operand| (operand, false)));
+        }
+        return copies.pop().unwrap_or_default();
+    }
+}
+
+///takes the tree apart with a stack of its own, the derived drop would
+///recurse once per operator of a chain
+impl Drop for Expression {
+    fn drop(&mut self) {
+        let mut operands = vec![];
+        self.take_operands(&mut operands);
+        while let Some(mut expr) = operands.pop() {
+            expr.take_operands(&mut operands);
         }
     }
 }
@@ -233,6 +1052,57 @@ pub struct Token {
     pub lexeme: String,
     pub literal: TokenLiteral,
     pub line: usize,
+    ///1-based column of the lexeme's first char, 0 for tokens that weren't
+    ///scanned
+    pub column: usize,
+    ///byte offset of the lexeme in the scanned source
+    pub offset: usize,
+}
+
+impl Token {
+    pub fn identifier(name: &str, line: usize) -> Token {
+        return Token {
+            token_type: TokenType::IDENTIFIER,
+            lexeme: name.to_string(),
+            literal: TokenLiteral::String(name.to_string()),
+            line,
+            column: 0,
+            offset: 0,
+        };
+    }
+
+    ///comments, kept in the token stream for tools like the formatter
+    ///but skipped by the parser
+    pub fn is_trivia(&self) -> bool {
+        return matches!(
+            self.token_type,
+            TokenType::COMMENT | TokenType::BLOCK_COMMENT
+        );
+    }
+
+    ///the end of a source, `offset` is its length
+    pub fn eof(line: usize, column: usize, offset: usize) -> Token {
+        return Token {
+            token_type: TokenType::EOF,
+            lexeme: String::new(),
+            literal: TokenLiteral::Empty,
+            line,
+            column,
+            offset,
+        };
+    }
+
+    ///byte range of the lexeme in the scanned source
+    pub fn span(&self) -> Range<usize> {
+        return self.offset..self.offset + self.lexeme.len();
+    }
+}
+
+///the end of an empty source
+impl Default for Token {
+    fn default() -> Self {
+        return Token::eof(1, 1, 0);
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -242,44 +1112,311 @@ pub enum TokenLiteral {
     Float(f64),
     String(String),
     Boolean(bool),
+    List(SharedList),
+    Map(SharedMap),
+    ///immutable, two tuples with equal elements are equal
+    Tuple(Arc<[TokenLiteral]>),
+    ///an integer outside isize, arithmetic overflowing isize promotes to it
+    #[cfg(feature = "bigint")]
+    BigInt(BigInt),
 }
 
-impl Display for TokenLiteral {
+///a list value, assigning it to another variable doesn't copy it, it's
+///behind a lock so compiled programs stay shareable between threads
+#[derive(Clone, Default)]
+pub struct SharedList(Arc<RwLock<Vec<TokenLiteral>>>);
+
+impl SharedList {
+    pub fn new(elements: Vec<TokenLiteral>) -> SharedList {
+        return SharedList(Arc::new(RwLock::new(elements)));
+    }
+
+    ///the same for every alias of one list
+    pub fn id(&self) -> usize {
+        return Arc::as_ptr(&self.0) as *const () as usize;
+    }
+
+    pub fn borrow(&self) -> RwLockReadGuard<'_, Vec<TokenLiteral>> {
+        return self.0.read().unwrap_or_else(PoisonError::into_inner);
+    }
+
+    pub fn borrow_mut(&self) -> RwLockWriteGuard<'_, Vec<TokenLiteral>> {
+        return self.0.write().unwrap_or_else(PoisonError::into_inner);
+    }
+}
+
+///written like `Display` so a list that contains itself ends
+impl std::fmt::Debug for SharedList {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let val = match self {
-            TokenLiteral::Empty => String::new(),
-            TokenLiteral::Integer(i) => i.to_string(),
-            TokenLiteral::Float(f) => f.to_string(),
-            TokenLiteral::String(s) => s.clone(),
-            TokenLiteral::Boolean(b) => b.to_string(),
+        write!(f, "SharedList({})", TokenLiteral::List(self.clone()))
+    }
+}
+
+///lists are equal only to themselves
+impl PartialEq for SharedList {
+    fn eq(&self, other: &Self) -> bool {
+        return Arc::ptr_eq(&self.0, &other.0);
+    }
+}
+
+///the values that can key a map, floats and lists can't
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Nil,
+    Boolean(bool),
+    Integer(isize),
+    String(String),
+    #[cfg(feature = "bigint")]
+    BigInt(BigInt),
+}
+
+impl MapKey {
+    pub fn from_literal(literal: &TokenLiteral) -> Option<MapKey> {
+        return match literal {
+            TokenLiteral::Empty => Some(MapKey::Nil),
+            TokenLiteral::Boolean(b) => Some(MapKey::Boolean(*b)),
+            TokenLiteral::Integer(i) => Some(MapKey::Integer(*i)),
+            TokenLiteral::String(s) => Some(MapKey::String(s.clone())),
+            #[cfg(feature = "bigint")]
+            TokenLiteral::BigInt(n) => Some(MapKey::BigInt(n.clone())),
+            _ => None,
         };
+    }
 
-        write!(f, "{}", val)
+    pub fn to_literal(&self) -> TokenLiteral {
+        return match self {
+            MapKey::Nil => TokenLiteral::Empty,
+            MapKey::Boolean(b) => TokenLiteral::Boolean(*b),
+            MapKey::Integer(i) => TokenLiteral::Integer(*i),
+            MapKey::String(s) => TokenLiteral::String(s.clone()),
+            #[cfg(feature = "bigint")]
+            MapKey::BigInt(n) => TokenLiteral::BigInt(n.clone()),
+        };
+    }
+}
+
+///written the way it appears in a map literal, strings are quoted
+impl Display for MapKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", nested_display(&self.to_literal(), &mut vec![]))
+    }
+}
+
+///a map's entries, iterated in the order keys were first inserted,
+///overwriting a key keeps its place
+#[derive(Debug, Clone, Default)]
+pub struct MapEntries {
+    positions: HashMap<MapKey, usize>,
+    entries: Vec<(MapKey, TokenLiteral)>,
+}
+
+impl MapEntries {
+    pub fn get(&self, key: &MapKey) -> Option<&TokenLiteral> {
+        return self.positions.get(key).map(|i| &self.entries[*i].1);
+    }
+
+    pub fn insert(&mut self, key: MapKey, value: TokenLiteral) {
+        match self.positions.get(&key) {
+            Some(i) => self.entries[*i].1 = value,
+            None => {
+                self.positions.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        return self.entries.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.entries.is_empty();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(MapKey, TokenLiteral)> {
+        return self.entries.iter();
+    }
+}
+
+///a map value, shared on assignment like `SharedList`
+#[derive(Clone, Default)]
+pub struct SharedMap(Arc<RwLock<MapEntries>>);
+
+impl SharedMap {
+    pub fn new(entries: MapEntries) -> SharedMap {
+        return SharedMap(Arc::new(RwLock::new(entries)));
+    }
+
+    ///the same for every alias of one map
+    pub fn id(&self) -> usize {
+        return Arc::as_ptr(&self.0) as *const () as usize;
+    }
+
+    pub fn borrow(&self) -> RwLockReadGuard<'_, MapEntries> {
+        return self.0.read().unwrap_or_else(PoisonError::into_inner);
+    }
+
+    pub fn borrow_mut(&self) -> RwLockWriteGuard<'_, MapEntries> {
+        return self.0.write().unwrap_or_else(PoisonError::into_inner);
+    }
+}
+
+///written like `Display`, see `SharedList`
+impl std::fmt::Debug for SharedMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SharedMap({})", TokenLiteral::Map(self.clone()))
+    }
+}
+
+///maps are equal only to themselves
+impl PartialEq for SharedMap {
+    fn eq(&self, other: &Self) -> bool {
+        return Arc::ptr_eq(&self.0, &other.0);
+    }
+}
+
+///how a value is written inside a list or map, strings are quoted
+fn nested_display(value: &TokenLiteral, open: &mut Vec<usize>) -> String {
+    return match value {
+        TokenLiteral::Empty => "nil".to_string(),
+        TokenLiteral::String(s) => format!("{:?}", s),
+        value => display(value, open),
+    };
+}
+
+///`value` the way `Display` writes it, `open` holds the lists and maps
+///being written, one that contains itself is written `[...]` or `{...}`
+///where it comes back
+fn display(value: &TokenLiteral, open: &mut Vec<usize>) -> String {
+    return match value {
+        TokenLiteral::Empty => String::new(),
+        TokenLiteral::Integer(i) => i.to_string(),
+        #[cfg(feature = "bigint")]
+        TokenLiteral::BigInt(n) => n.to_string(),
+        TokenLiteral::Float(f) => f.to_string(),
+        TokenLiteral::String(s) => s.clone(),
+        TokenLiteral::Boolean(b) => b.to_string(),
+        TokenLiteral::List(list) => {
+            if open.contains(&list.id()) {
+                return "[...]".to_string();
+            }
+            open.push(list.id());
+            let elements = list
+                .borrow()
+                .iter()
+                .map(|element| nested_display(element, open))
+                .collect::<Vec<String>>();
+            open.pop();
+            format!("[{}]", elements.join(", "))
+        }
+        TokenLiteral::Map(map) => {
+            if open.contains(&map.id()) {
+                return "{...}".to_string();
+            }
+            open.push(map.id());
+            let entries = map
+                .borrow()
+                .iter()
+                .map(|(key, value)| format!("{}: {}", key, nested_display(value, open)))
+                .collect::<Vec<String>>();
+            open.pop();
+            format!("{{{}}}", entries.join(", "))
+        }
+        TokenLiteral::Tuple(elements) if elements.len() == 1 => {
+            format!("({},)", nested_display(&elements[0], open))
+        }
+        TokenLiteral::Tuple(elements) => {
+            let elements = elements
+                .iter()
+                .map(|element| nested_display(element, open))
+                .collect::<Vec<String>>();
+            format!("({})", elements.join(", "))
+        }
+    };
+}
+
+impl Display for TokenLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", display(self, &mut vec![]))
     }
 }
 
 impl TokenLiteral {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            TokenLiteral::Empty => "nil",
+            TokenLiteral::Integer(_) => "integer",
+            #[cfg(feature = "bigint")]
+            TokenLiteral::BigInt(_) => "integer",
+            TokenLiteral::Float(_) => "float",
+            TokenLiteral::String(_) => "string",
+            TokenLiteral::Boolean(_) => "boolean",
+            TokenLiteral::List(_) => "list",
+            TokenLiteral::Map(_) => "map",
+            TokenLiteral::Tuple(_) => "tuple",
+        }
+    }
+
+    pub fn is_number(&self) -> bool {
+        return match self {
+            TokenLiteral::Integer(_) | TokenLiteral::Float(_) => true,
+            #[cfg(feature = "bigint")]
+            TokenLiteral::BigInt(_) => true,
+            _ => false,
+        };
+    }
+
+    pub fn list(elements: Vec<TokenLiteral>) -> TokenLiteral {
+        return TokenLiteral::List(SharedList::new(elements));
+    }
+
     pub fn pow(&self, rhs: TokenLiteral) -> Result<TokenLiteral, ParserError> {
+        #[cfg(feature = "bigint")]
+        if let Some(result) = bigint::binary("**", self, &rhs) {
+            return result;
+        }
+        let mismatch = ParserError::type_mismatch("**", self, &rhs);
         match self {
+            // a negative exponent has no integer result
             TokenLiteral::Integer(i) => match rhs {
-                TokenLiteral::Integer(ii) => Ok(TokenLiteral::Integer(i.pow(ii as u32))),
-                _ => Ok(TokenLiteral::Empty),
+                TokenLiteral::Integer(ii) if ii < 0 => {
+                    Ok(TokenLiteral::Float((*i as f64).powi(ii as i32)))
+                }
+                TokenLiteral::Integer(ii) => integer_result(
+                    "**",
+                    *i,
+                    ii,
+                    u32::try_from(ii).ok().and_then(|ii| i.checked_pow(ii)),
+                ),
+                TokenLiteral::Float(ff) => Ok(TokenLiteral::Float((*i as f64).powf(ff))),
+                _ => Err(mismatch),
             },
             TokenLiteral::Float(f) => match rhs {
                 TokenLiteral::Float(ff) => Ok(TokenLiteral::Float(f.powf(ff))),
                 TokenLiteral::Integer(fi) => Ok(TokenLiteral::Float(f.powi(fi as i32))),
-                _ => Ok(TokenLiteral::Empty),
+                _ => Err(mismatch),
             },
-            _ => Ok(TokenLiteral::Empty),
+            _ => Err(mismatch),
         }
     }
 
-    pub fn is_truthy(&self) -> bool {
+    pub fn is_truthy(&self, truthiness: Truthiness) -> bool {
         match self {
             TokenLiteral::Empty => false,
+            TokenLiteral::Integer(_) | TokenLiteral::Float(_)
+                if truthiness == Truthiness::Strict =>
+            {
+                true
+            }
             TokenLiteral::Integer(n) => *n != 0,
             TokenLiteral::Float(n) => *n != 0.0,
-            TokenLiteral::String(_) => true,
+            // never zero, small values are plain integers
+            #[cfg(feature = "bigint")]
+            TokenLiteral::BigInt(_) => true,
+            TokenLiteral::String(_)
+            | TokenLiteral::List(_)
+            | TokenLiteral::Map(_)
+            | TokenLiteral::Tuple(_) => true,
             TokenLiteral::Boolean(b) => *b,
         }
     }
@@ -298,6 +1435,16 @@ impl TokenLiteral {
                 return match rhs {
                     TokenLiteral::Float(right) => return right == *left,
                     TokenLiteral::Integer(right) => return *left == right as f64,
+                    #[cfg(feature = "bigint")]
+                    TokenLiteral::BigInt(right) => return *left == bigint::to_f64(&right),
+                    _ => false,
+                };
+            }
+            #[cfg(feature = "bigint")]
+            TokenLiteral::BigInt(left) => {
+                return match rhs {
+                    TokenLiteral::BigInt(right) => return *left == right,
+                    TokenLiteral::Float(right) => return bigint::to_f64(left) == right,
                     _ => false,
                 };
             }
@@ -313,30 +1460,369 @@ impl TokenLiteral {
                 }
                 return false;
             }
+            TokenLiteral::List(left) => {
+                if let TokenLiteral::List(right) = rhs {
+                    return *left == right;
+                }
+                return false;
+            }
+            TokenLiteral::Map(left) => {
+                if let TokenLiteral::Map(right) = rhs {
+                    return *left == right;
+                }
+                return false;
+            }
+            TokenLiteral::Tuple(left) => {
+                if let TokenLiteral::Tuple(right) = rhs {
+                    return left.len() == right.len()
+                        && left
+                            .iter()
+                            .zip(right.iter())
+                            .all(|(l, r)| l.is_equal(r.clone()));
+                }
+                return false;
+            }
+        }
+    }
+}
+
+impl From<isize> for TokenLiteral {
+    fn from(value: isize) -> Self {
+        return TokenLiteral::Integer(value);
+    }
+}
+
+///an `i64` only fits an integer where isize is as wide, past that it's
+///kept exactly with the bigint feature and doesn't convert without it
+impl TryFrom<i64> for TokenLiteral {
+    type Error = LoxError;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        return match isize::try_from(value) {
+            Ok(i) => Ok(TokenLiteral::Integer(i)),
+            #[cfg(feature = "bigint")]
+            Err(_) => Ok(TokenLiteral::BigInt(BigInt::from(value))),
+            #[cfg(not(feature = "bigint"))]
+            Err(_) => Err(LoxError::InvalidConversion {
+                from: "i64",
+                to: "integer",
+            }),
+        };
+    }
+}
+
+impl From<i32> for TokenLiteral {
+    fn from(value: i32) -> Self {
+        return TokenLiteral::Integer(value as isize);
+    }
+}
+
+impl From<f64> for TokenLiteral {
+    fn from(value: f64) -> Self {
+        return TokenLiteral::Float(value);
+    }
+}
+
+impl From<bool> for TokenLiteral {
+    fn from(value: bool) -> Self {
+        return TokenLiteral::Boolean(value);
+    }
+}
+
+impl From<&str> for TokenLiteral {
+    fn from(value: &str) -> Self {
+        return TokenLiteral::String(value.to_string());
+    }
+}
+
+impl From<String> for TokenLiteral {
+    fn from(value: String) -> Self {
+        return TokenLiteral::String(value);
+    }
+}
+
+impl<T: Into<TokenLiteral>> From<Option<T>> for TokenLiteral {
+    fn from(value: Option<T>) -> Self {
+        return match value {
+            Some(value) => value.into(),
+            None => TokenLiteral::Empty,
+        };
+    }
+}
+
+impl<T: Into<TokenLiteral>> From<Vec<T>> for TokenLiteral {
+    fn from(value: Vec<T>) -> Self {
+        return TokenLiteral::list(value.into_iter().map(Into::into).collect());
+    }
+}
+
+///a map with string keys, entries are inserted in key order since a
+///`HashMap` has none of its own
+impl<K: Into<String>, V: Into<TokenLiteral>> From<HashMap<K, V>> for TokenLiteral {
+    fn from(value: HashMap<K, V>) -> Self {
+        let mut pairs = value
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect::<Vec<(String, TokenLiteral)>>();
+        pairs.sort_by(|(left, _), (right, _)| left.cmp(right));
+        let mut entries = MapEntries::default();
+        for (key, value) in pairs {
+            entries.insert(MapKey::String(key), value);
+        }
+        return TokenLiteral::Map(SharedMap::new(entries));
+    }
+}
+
+impl TryFrom<TokenLiteral> for isize {
+    type Error = LoxError;
+
+    fn try_from(value: TokenLiteral) -> Result<Self, Self::Error> {
+        match value {
+            TokenLiteral::Integer(i) => Ok(i),
+            other => Err(LoxError::InvalidConversion {
+                from: other.type_name(),
+                to: "isize",
+            }),
+        }
+    }
+}
+
+impl TryFrom<TokenLiteral> for i64 {
+    type Error = LoxError;
+
+    fn try_from(value: TokenLiteral) -> Result<Self, Self::Error> {
+        match value {
+            TokenLiteral::Integer(i) => Ok(i as i64),
+            other => Err(LoxError::InvalidConversion {
+                from: other.type_name(),
+                to: "i64",
+            }),
+        }
+    }
+}
+
+impl TryFrom<TokenLiteral> for f64 {
+    type Error = LoxError;
+
+    fn try_from(value: TokenLiteral) -> Result<Self, Self::Error> {
+        match value {
+            TokenLiteral::Float(f) => Ok(f),
+            TokenLiteral::Integer(i) => Ok(i as f64),
+            #[cfg(feature = "bigint")]
+            TokenLiteral::BigInt(n) => Ok(bigint::to_f64(&n)),
+            other => Err(LoxError::InvalidConversion {
+                from: other.type_name(),
+                to: "f64",
+            }),
+        }
+    }
+}
+
+impl TryFrom<TokenLiteral> for bool {
+    type Error = LoxError;
+
+    fn try_from(value: TokenLiteral) -> Result<Self, Self::Error> {
+        match value {
+            TokenLiteral::Boolean(b) => Ok(b),
+            other => Err(LoxError::InvalidConversion {
+                from: other.type_name(),
+                to: "bool",
+            }),
+        }
+    }
+}
+
+impl TryFrom<TokenLiteral> for String {
+    type Error = LoxError;
+
+    fn try_from(value: TokenLiteral) -> Result<Self, Self::Error> {
+        match value {
+            TokenLiteral::String(s) => Ok(s),
+            other => Err(LoxError::InvalidConversion {
+                from: other.type_name(),
+                to: "String",
+            }),
+        }
+    }
+}
+
+///lists and tuples, each element converted
+impl<T: TryFrom<TokenLiteral, Error = LoxError>> TryFrom<TokenLiteral> for Vec<T> {
+    type Error = LoxError;
+
+    fn try_from(value: TokenLiteral) -> Result<Self, Self::Error> {
+        let elements = match value {
+            TokenLiteral::List(list) => list.borrow().clone(),
+            TokenLiteral::Tuple(elements) => elements.to_vec(),
+            other => {
+                return Err(LoxError::InvalidConversion {
+                    from: other.type_name(),
+                    to: "Vec",
+                })
+            }
+        };
+        return elements.into_iter().map(T::try_from).collect();
+    }
+}
+
+///maps whose keys are all strings, each value converted
+impl<T: TryFrom<TokenLiteral, Error = LoxError>> TryFrom<TokenLiteral> for HashMap<String, T> {
+    type Error = LoxError;
+
+    fn try_from(value: TokenLiteral) -> Result<Self, Self::Error> {
+        let TokenLiteral::Map(map) = value else {
+            return Err(LoxError::InvalidConversion {
+                from: value.type_name(),
+                to: "HashMap",
+            });
+        };
+        let entries = map.borrow().iter().cloned().collect::<Vec<_>>();
+        let mut converted = HashMap::new();
+        for (key, value) in entries {
+            let MapKey::String(key) = key else {
+                return Err(LoxError::InvalidConversion {
+                    from: key.to_literal().type_name(),
+                    to: "String",
+                });
+            };
+            converted.insert(key, T::try_from(value)?);
         }
+        return Ok(converted);
     }
 }
 
+#[cfg(feature = "json")]
+impl From<TokenLiteral> for serde_json::Value {
+    fn from(value: TokenLiteral) -> Self {
+        return to_json(&value, &mut vec![]);
+    }
+}
+
+///`value` as JSON, `open` holds the lists and maps being converted, one
+///that contains itself is the string `[...]` or `{...}` where it comes back
+#[cfg(feature = "json")]
+fn to_json(value: &TokenLiteral, open: &mut Vec<usize>) -> serde_json::Value {
+    return match value {
+        TokenLiteral::Empty => serde_json::Value::Null,
+        TokenLiteral::Integer(i) => serde_json::Value::from(*i),
+        // JSON numbers are doubles to most readers, a string keeps
+        // every digit
+        #[cfg(feature = "bigint")]
+        TokenLiteral::BigInt(n) => serde_json::Value::String(n.to_string()),
+        TokenLiteral::Float(f) => serde_json::Value::from(*f),
+        TokenLiteral::String(s) => serde_json::Value::String(s.clone()),
+        TokenLiteral::Boolean(b) => serde_json::Value::Bool(*b),
+        TokenLiteral::List(list) => {
+            if open.contains(&list.id()) {
+                return serde_json::Value::String("[...]".to_string());
+            }
+            open.push(list.id());
+            let elements = list
+                .borrow()
+                .iter()
+                .map(|element| to_json(element, open))
+                .collect();
+            open.pop();
+            serde_json::Value::Array(elements)
+        }
+        TokenLiteral::Tuple(elements) => serde_json::Value::Array(
+            elements
+                .iter()
+                .map(|element| to_json(element, open))
+                .collect(),
+        ),
+        // JSON keys are strings, other keys are written as they display
+        TokenLiteral::Map(map) => {
+            if open.contains(&map.id()) {
+                return serde_json::Value::String("{...}".to_string());
+            }
+            open.push(map.id());
+            let entries = map
+                .borrow()
+                .iter()
+                .map(|(key, value)| (key.to_literal().to_string(), to_json(value, open)))
+                .collect();
+            open.pop();
+            serde_json::Value::Object(entries)
+        }
+    };
+}
+
+#[cfg(feature = "json")]
+impl TryFrom<serde_json::Value> for TokenLiteral {
+    type Error = LoxError;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        match value {
+            serde_json::Value::Null => Ok(TokenLiteral::Empty),
+            serde_json::Value::Bool(b) => Ok(TokenLiteral::Boolean(b)),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => TokenLiteral::try_from(i),
+                None => Ok(TokenLiteral::Float(n.as_f64().unwrap_or(f64::NAN))),
+            },
+            serde_json::Value::String(s) => Ok(TokenLiteral::String(s)),
+            serde_json::Value::Array(values) => Ok(TokenLiteral::list(
+                values
+                    .into_iter()
+                    .map(TokenLiteral::try_from)
+                    .collect::<Result<Vec<TokenLiteral>, LoxError>>()?,
+            )),
+            serde_json::Value::Object(values) => {
+                let mut entries = MapEntries::default();
+                for (key, value) in values {
+                    entries.insert(MapKey::String(key), TokenLiteral::try_from(value)?);
+                }
+                Ok(TokenLiteral::Map(SharedMap::new(entries)))
+            }
+        }
+    }
+}
+
+///the `checked` result of an integer operation, when it overflowed the
+///exact value with the bigint feature and an error without it
+#[cfg_attr(not(feature = "bigint"), allow(unused_variables))]
+fn integer_result(
+    operator: &'static str,
+    lhs: isize,
+    rhs: isize,
+    checked: Option<isize>,
+) -> Result<TokenLiteral, ParserError> {
+    if let Some(result) = checked {
+        return Ok(TokenLiteral::Integer(result));
+    }
+    #[cfg(feature = "bigint")]
+    return bigint::apply(operator, &BigInt::from(lhs), &BigInt::from(rhs));
+    #[cfg(not(feature = "bigint"))]
+    return Err(ParserError::IntegerOverflow { operator });
+}
+
 impl Mul for TokenLiteral {
     type Output = Result<TokenLiteral, ParserError>;
 
     fn mul(self, rhs: Self) -> Self::Output {
+        #[cfg(feature = "bigint")]
+        if let Some(result) = bigint::binary("*", &self, &rhs) {
+            return result;
+        }
+        let mismatch = ParserError::type_mismatch("*", &self, &rhs);
         match self {
             TokenLiteral::Float(lhs) => {
                 return match rhs {
                     TokenLiteral::Float(rhs) => Ok(TokenLiteral::Float(lhs * rhs)),
                     TokenLiteral::Integer(rhs) => Ok(TokenLiteral::Float(lhs * rhs as f64)),
-                    _ => todo!(),
+                    _ => Err(mismatch),
                 };
             }
             TokenLiteral::Integer(lhs) => {
                 return match rhs {
                     TokenLiteral::Float(rhs) => Ok(TokenLiteral::Float((lhs as f64) * rhs)),
-                    TokenLiteral::Integer(rhs) => Ok(TokenLiteral::Integer(lhs * rhs)),
-                    _ => todo!(),
+                    TokenLiteral::Integer(rhs) => {
+                        integer_result("*", lhs, rhs, lhs.checked_mul(rhs))
+                    }
+                    _ => Err(mismatch),
                 };
             }
-            _ => todo!(),
+            _ => Err(mismatch),
         }
     }
 }
@@ -345,22 +1831,27 @@ impl Div for TokenLiteral {
     type Output = Result<TokenLiteral, ParserError>;
 
     fn div(self, rhs: Self) -> Self::Output {
+        #[cfg(feature = "bigint")]
+        if let Some(result) = bigint::binary("/", &self, &rhs) {
+            return result;
+        }
+        let mismatch = ParserError::type_mismatch("/", &self, &rhs);
         match self {
             TokenLiteral::Float(lhs) => {
                 return match rhs {
                     TokenLiteral::Float(rhs) => Ok(TokenLiteral::Float(lhs / rhs)),
                     TokenLiteral::Integer(rhs) => Ok(TokenLiteral::Float(lhs / rhs as f64)),
-                    _ => todo!(),
+                    _ => Err(mismatch),
                 };
             }
             TokenLiteral::Integer(lhs) => {
                 return match rhs {
                     TokenLiteral::Float(rhs) => Ok(TokenLiteral::Float((lhs as f64) / rhs)),
                     TokenLiteral::Integer(rhs) => Ok(TokenLiteral::Float(lhs as f64 / rhs as f64)),
-                    _ => todo!(),
+                    _ => Err(mismatch),
                 };
             }
-            _ => todo!(),
+            _ => Err(mismatch),
         }
     }
 }
@@ -369,22 +1860,29 @@ impl Sub for TokenLiteral {
     type Output = Result<TokenLiteral, ParserError>;
 
     fn sub(self, rhs: Self) -> Self::Output {
+        #[cfg(feature = "bigint")]
+        if let Some(result) = bigint::binary("-", &self, &rhs) {
+            return result;
+        }
+        let mismatch = ParserError::type_mismatch("-", &self, &rhs);
         match self {
             TokenLiteral::Float(lhs) => {
                 return match rhs {
                     TokenLiteral::Float(rhs) => Ok(TokenLiteral::Float(lhs - rhs)),
                     TokenLiteral::Integer(rhs) => Ok(TokenLiteral::Float(lhs - rhs as f64)),
-                    _ => todo!(),
+                    _ => Err(mismatch),
                 };
             }
             TokenLiteral::Integer(lhs) => {
                 return match rhs {
                     TokenLiteral::Float(rhs) => Ok(TokenLiteral::Float((lhs as f64) - rhs)),
-                    TokenLiteral::Integer(rhs) => Ok(TokenLiteral::Integer(lhs - rhs)),
-                    _ => todo!(),
+                    TokenLiteral::Integer(rhs) => {
+                        integer_result("-", lhs, rhs, lhs.checked_sub(rhs))
+                    }
+                    _ => Err(mismatch),
                 };
             }
-            _ => todo!(),
+            _ => Err(mismatch),
         }
     }
 }
@@ -393,6 +1891,11 @@ impl Add for TokenLiteral {
     type Output = Result<TokenLiteral, ParserError>;
 
     fn add(self, rhs: Self) -> Self::Output {
+        #[cfg(feature = "bigint")]
+        if let Some(result) = bigint::binary("+", &self, &rhs) {
+            return result;
+        }
+        let mismatch = ParserError::type_mismatch("+", &self, &rhs);
         match self {
             TokenLiteral::Float(lhs) => {
                 match rhs {
@@ -401,17 +1904,19 @@ impl Add for TokenLiteral {
                     TokenLiteral::String(rhs) => {
                         return Ok(TokenLiteral::String(format!("{}{}", lhs, rhs)))
                     }
-                    _ => todo!(),
+                    _ => {}
                 };
             }
             TokenLiteral::Integer(lhs) => {
                 match rhs {
                     TokenLiteral::Float(rhs) => return Ok(TokenLiteral::Float((lhs as f64) + rhs)),
-                    TokenLiteral::Integer(rhs) => return Ok(TokenLiteral::Integer(lhs + rhs)),
+                    TokenLiteral::Integer(rhs) => {
+                        return integer_result("+", lhs, rhs, lhs.checked_add(rhs))
+                    }
                     TokenLiteral::String(rhs) => {
                         return Ok(TokenLiteral::String(format!("{}{}", lhs, rhs)))
                     }
-                    _ => todo!(),
+                    _ => {}
                 };
             }
             TokenLiteral::String(lhs) => {
@@ -427,7 +1932,7 @@ impl Add for TokenLiteral {
             }
             _ => {}
         };
-        return Err(ParserError::UnsupportedAction);
+        return Err(mismatch);
     }
 }
 
@@ -438,6 +1943,9 @@ pub enum TokenType {
     RIGHT_PAREN,
     LEFT_BRACE,
     RIGHT_BRACE,
+    LEFT_BRACKET,
+    RIGHT_BRACKET,
+    COLON,
     COMMA,
     DOT,
     MINUS,
@@ -456,6 +1964,12 @@ pub enum TokenType {
     GREATER_EQUAL,
     LESS,
     LESS_EQUAL,
+    FAT_ARROW,
+    QUESTION_QUESTION,
+    PLUS_EQUAL,
+    MINUS_EQUAL,
+    STAR_EQUAL,
+    SLASH_EQUAL,
 
     // Literals.
     IDENTIFIER,
@@ -486,4 +2000,6 @@ pub enum TokenType {
     COMMENT,
     BLOCK_COMMENT,
     DUMP,
+    MATCH,
+    ASSERT,
 }
@@ -0,0 +1,120 @@
+use std::cmp::Ordering;
+
+pub use num_bigint::BigInt;
+use num_traits::{Num, ToPrimitive};
+
+use crate::ast::{ParserError, TokenLiteral};
+
+///`n` as a plain integer when it fits, so every `TokenLiteral::BigInt` is
+///outside isize
+pub fn normalize(n: BigInt) -> TokenLiteral {
+    return match isize::try_from(&n) {
+        Ok(n) => TokenLiteral::Integer(n),
+        Err(_) => TokenLiteral::BigInt(n),
+    };
+}
+
+///a number literal too large for isize
+pub fn parse(digits: &str, radix: u32) -> Option<TokenLiteral> {
+    return BigInt::from_str_radix(digits, radix).ok().map(normalize);
+}
+
+///the closest float, infinite when `n` is past f64
+pub fn to_f64(n: &BigInt) -> f64 {
+    return n.to_f64().unwrap_or(f64::NAN);
+}
+
+///`+`, `-`, `*` and `**` on integers of any size
+pub fn apply(
+    operator: &'static str,
+    left: &BigInt,
+    right: &BigInt,
+) -> Result<TokenLiteral, ParserError> {
+    let result = match operator {
+        "+" => left + right,
+        "-" => left - right,
+        "*" => left * right,
+        _ => match u32::try_from(right) {
+            Ok(exponent) => left.pow(exponent),
+            Err(_) => return Err(ParserError::IntegerOverflow { operator }),
+        },
+    };
+    return Ok(normalize(result));
+}
+
+fn integer(literal: &TokenLiteral) -> Option<BigInt> {
+    return match literal {
+        TokenLiteral::Integer(n) => Some(BigInt::from(*n)),
+        TokenLiteral::BigInt(n) => Some(n.clone()),
+        _ => None,
+    };
+}
+
+///an arithmetic operator with a big integer on either side, `None` when
+///neither operand is one
+pub fn binary(
+    operator: &'static str,
+    left: &TokenLiteral,
+    right: &TokenLiteral,
+) -> Option<Result<TokenLiteral, ParserError>> {
+    if !matches!(left, TokenLiteral::BigInt(_)) && !matches!(right, TokenLiteral::BigInt(_)) {
+        return None;
+    }
+    if let (Some(left), Some(right)) = (integer(left), integer(right)) {
+        if operator == "/" {
+            return Some(Ok(TokenLiteral::Float(to_f64(&left) / to_f64(&right))));
+        }
+        // a negative exponent has no integer result
+        if operator == "**" && right < BigInt::ZERO {
+            let exponent = right.to_i32().unwrap_or(i32::MIN);
+            return Some(Ok(TokenLiteral::Float(to_f64(&left).powi(exponent))));
+        }
+        return Some(apply(operator, &left, &right));
+    }
+    let result = match (operator, left, right) {
+        ("+", TokenLiteral::String(s), n) => TokenLiteral::String(format!("{}{}", s, n)),
+        ("+", n, TokenLiteral::String(s)) => TokenLiteral::String(format!("{}{}", n, s)),
+        (_, TokenLiteral::Float(_), _) | (_, _, TokenLiteral::Float(_)) => {
+            let (Some(left), Some(right)) = (as_f64(left), as_f64(right)) else {
+                return Some(Err(ParserError::type_mismatch(operator, left, right)));
+            };
+            TokenLiteral::Float(match operator {
+                "+" => left + right,
+                "-" => left - right,
+                "*" => left * right,
+                "/" => left / right,
+                _ => left.powf(right),
+            })
+        }
+        _ => return Some(Err(ParserError::type_mismatch(operator, left, right))),
+    };
+    return Some(Ok(result));
+}
+
+fn as_f64(literal: &TokenLiteral) -> Option<f64> {
+    return match literal {
+        TokenLiteral::Float(n) => Some(*n),
+        TokenLiteral::BigInt(n) => Some(to_f64(n)),
+        _ => None,
+    };
+}
+
+///orders a big integer against another number, `None` when neither
+///operand is a big integer or the other one isn't a number
+pub fn compare(left: &TokenLiteral, right: &TokenLiteral) -> Option<Option<Ordering>> {
+    if !matches!(left, TokenLiteral::BigInt(_)) && !matches!(right, TokenLiteral::BigInt(_)) {
+        return None;
+    }
+    if let (Some(left), Some(right)) = (integer(left), integer(right)) {
+        return Some(Some(left.cmp(&right)));
+    }
+    return match (left, right) {
+        (TokenLiteral::BigInt(left), TokenLiteral::Float(right)) => {
+            Some(to_f64(left).partial_cmp(right))
+        }
+        (TokenLiteral::Float(left), TokenLiteral::BigInt(right)) => {
+            Some(left.partial_cmp(&to_f64(right)))
+        }
+        _ => None,
+    };
+}
@@ -0,0 +1,560 @@
+use std::path::PathBuf;
+
+use crate::{
+    ast::Truthiness,
+    color::ColorChoice,
+    config::{ConfigOverrides, Dialect, Extension, Extensions, WarningLevel, EXTENSIONS},
+    formatter::QuoteStyle,
+    mutate::Mutant,
+    repl::OutputMode,
+    xref::Query,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Run {
+        path: String,
+        ///run each statement as soon as it's parsed, the script is still
+        ///scanned as a whole first
+        stream: bool,
+        ///set by `rlox mutate` for the child it runs a mutant in, the
+        ///`--mutant` flag isn't documented
+        mutant: Option<Mutant>,
+    },
+    Repl {
+        output: OutputMode,
+        ///a session file saved with `:save` to start from
+        restore: Option<String>,
+    },
+    #[cfg(feature = "json")]
+    Kernel,
+    Check {
+        paths: Vec<String>,
+    },
+    Fmt {
+        paths: Vec<String>,
+        check: bool,
+        ///how many files may be unformatted before --check fails
+        max_unformatted: usize,
+        ///read the files to format from stdin, one per line
+        changed_only: bool,
+    },
+    Lint {
+        paths: Vec<String>,
+        ///how many findings are allowed before the command fails
+        max_warnings: usize,
+        changed_only: bool,
+    },
+    Ast {
+        path: String,
+    },
+    Tokens {
+        path: String,
+    },
+    Test {
+        dir: String,
+    },
+    Bench {
+        path: String,
+        iterations: usize,
+    },
+    Fix {
+        paths: Vec<String>,
+        ///write the fixed scripts instead of printing diffs
+        apply: bool,
+    },
+    Hash {
+        paths: Vec<String>,
+        ///number variables by first use instead of hashing their names
+        ignore_names: bool,
+        ///print the canonical form that gets hashed
+        canonical: bool,
+    },
+    Xref {
+        path: String,
+        query: Query,
+        json: bool,
+    },
+    Mutate {
+        dir: String,
+        ///percentage of mutants that must be caught
+        min_score: usize,
+    },
+    Messages,
+    Help {
+        topic: Option<String>,
+    },
+}
+
+pub const USAGE: &str = "Usage: rlox <command> [args]
+       rlox [file.lox]    shorthand for `rlox run file.lox`
+       rlox -             shorthand for `rlox repl`
+
+Commands:
+    run [--stream] <file>             run a script
+    repl [--output plain|json|quiet] [--restore <session>]
+                                      start an interactive session
+    kernel                            serve notebook style requests on stdin
+    check <files...>                  scan and parse without running
+    fmt [--check] <files...>          format scripts in place
+    lint <files...>                   report likely mistakes
+    fix [--apply] <files...>          upgrade scripts written for an older dialect
+    ast <file>                        print the parsed syntax tree
+    tokens <file>                     print the scanned tokens
+    test [dir]                        run every .lox file in dir (default: tests)
+    bench [--iterations N] <file>     time repeated runs of a script
+    mutate [--min-score N] [dir]      check that the tests in dir catch operator mutants
+    hash [--ignore-names] <files...>  print a hash that survives reformatting
+    xref <file> --find-def|--find-refs <name> [--json]
+                                      find where a variable is defined or used
+    messages                          print the error and warning texts by code
+    help [command]                    show help for a command
+
+Options (any command):
+    --config <file>                   use this config instead of the closest rlox.toml
+    --no-config                       ignore rlox.toml files
+    --dialect <strict|extended>       extended allows implicit globals
+    --strict-truthiness               only nil and false are falsey, not 0 and 0.0
+    --warnings <allow|warn|deny>      how parser warnings are reported
+    -I, --include <dir>               search dir for scripts, may be repeated
+    --quotes <preserve|double|single> string quotes used by fmt
+    --step-limit <n>                  statements and expressions a script may run
+    --messages <file>                 replace error and warning texts, see `rlox messages`
+    --color <auto|always|never>       color errors and warnings, auto only when
+                                      stderr is a terminal
+    --extensions <all|none|a,b,...>   language extensions scripts may use: integers,
+                                      exponent, single-quotes, dump, match, lists,
+                                      maps, compound-assignment, for-in, assert,
+                                      tuples, nil-coalescing, radix-literals";
+
+impl Command {
+    ///the script or directory a command works on, where config lookup starts
+    pub fn target(&self) -> Option<&str> {
+        return match self {
+            Command::Run { path, .. }
+            | Command::Ast { path }
+            | Command::Tokens { path }
+            | Command::Bench { path, .. }
+            | Command::Xref { path, .. } => Some(path),
+            Command::Check { paths }
+            | Command::Fmt { paths, .. }
+            | Command::Lint { paths, .. }
+            | Command::Fix { paths, .. }
+            | Command::Hash { paths, .. } => paths.first().map(String::as_str),
+            Command::Test { dir } | Command::Mutate { dir, .. } => Some(dir),
+            Command::Repl { .. } | Command::Messages | Command::Help { .. } => None,
+            #[cfg(feature = "json")]
+            Command::Kernel => None,
+        };
+    }
+}
+
+///removes the config flags shared by every command, returning the
+///remaining arguments and the settings they override
+pub fn split_config_flags(args: &[String]) -> Result<(Vec<String>, ConfigOverrides), String> {
+    let mut overrides = ConfigOverrides::default();
+    let mut rest = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let takes_value = matches!(
+            flag,
+            "--config"
+                | "--dialect"
+                | "--warnings"
+                | "-I"
+                | "--include"
+                | "--quotes"
+                | "--step-limit"
+                | "--messages"
+                | "--extensions"
+                | "--color"
+        );
+        if !takes_value {
+            if flag == "--no-config" {
+                overrides.no_config = true;
+            } else if flag == "--strict-truthiness" {
+                overrides.truthiness = Some(Truthiness::Strict);
+            } else {
+                rest.push(arg.clone());
+            }
+            continue;
+        }
+        let value = match inline.or_else(|| args.next().cloned()) {
+            Some(value) => value,
+            None => return Err(format!("{} expects a value", flag)),
+        };
+        match flag {
+            "--config" => overrides.config = Some(PathBuf::from(value)),
+            "--dialect" => overrides.dialect = Some(Dialect::from_name(&value)?),
+            "--warnings" => overrides.warnings = Some(WarningLevel::from_name(&value)?),
+            "--quotes" => overrides.quotes = Some(QuoteStyle::from_name(&value)?),
+            "--step-limit" => {
+                overrides.step_limit = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("--step-limit expects a number, found '{}'", value))?,
+                )
+            }
+            "--color" => overrides.color = ColorChoice::from_name(&value)?,
+            "--messages" => overrides.messages = Some(PathBuf::from(value)),
+            "--extensions" => {
+                let names = match value.as_str() {
+                    "all" => EXTENSIONS.iter().map(Extension::name).collect(),
+                    "none" => vec![],
+                    names => names.split(',').map(str::trim).collect(),
+                };
+                overrides.extensions = Some(Extensions::only(&names)?);
+            }
+            _ => overrides.include_paths.push(PathBuf::from(value)),
+        }
+    }
+    return Ok((rest, overrides));
+}
+
+///parses command line arguments, not including the program name
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let (name, rest) = match args.split_first() {
+        Some((name, rest)) => (name.as_str(), rest),
+        None => return Err("Missing command".to_string()),
+    };
+
+    if rest.iter().any(|arg| arg == "--help" || arg == "-h") {
+        return Ok(Command::Help {
+            topic: Some(name.to_string()),
+        });
+    }
+
+    return match name {
+        "-h" | "--help" | "help" => Ok(Command::Help {
+            topic: rest.first().cloned(),
+        }),
+        "-" | "repl" => {
+            let mut output = OutputMode::default();
+            let mut restore = None;
+            let mut rest = rest.iter();
+            while let Some(arg) = rest.next() {
+                let (flag, value) = match arg.split_once('=') {
+                    Some((flag, value)) => (flag, Some(value.to_string())),
+                    None => (arg.as_str(), None),
+                };
+                if flag != "--output" && flag != "--restore" {
+                    return Err(format!("Unexpected arguments for {}", name));
+                }
+                let value = value
+                    .or_else(|| rest.next().cloned())
+                    .ok_or(format!("{} expects a value", flag))?;
+                if flag == "--output" {
+                    output = OutputMode::from_name(&value)?;
+                } else {
+                    restore = Some(value);
+                }
+            }
+            if restore.is_some() && !cfg!(feature = "json") {
+                return Err("rlox was built without the json feature".to_string());
+            }
+            Ok(Command::Repl { output, restore })
+        }
+        #[cfg(feature = "json")]
+        "kernel" if rest.is_empty() => Ok(Command::Kernel),
+        #[cfg(feature = "json")]
+        "kernel" => Err("kernel takes no arguments".to_string()),
+        #[cfg(not(feature = "json"))]
+        "kernel" => Err("rlox was built without the json feature".to_string()),
+        "messages" if rest.is_empty() => Ok(Command::Messages),
+        "messages" => Err("messages takes no arguments".to_string()),
+        "run" => {
+            let mut stream = false;
+            let mut mutant = None;
+            let mut positional = vec![];
+            let mut rest = rest.iter();
+            while let Some(arg) = rest.next() {
+                if arg == "--stream" {
+                    stream = true;
+                } else if arg == "--mutant" {
+                    let name = rest.next().ok_or(format!("{} expects a mutant", arg))?;
+                    mutant =
+                        Some(Mutant::from_name(name).ok_or(format!("Unknown mutant '{}'", name))?);
+                } else {
+                    positional.push(arg.clone());
+                }
+            }
+            Ok(Command::Run {
+                path: single_path(name, &positional)?,
+                stream,
+                mutant,
+            })
+        }
+        "check" => Ok(Command::Check {
+            paths: paths(name, rest)?,
+        }),
+        "fmt" => {
+            let files = FileArgs::parse(name, rest, "--max-unformatted", &["--check"])?;
+            Ok(Command::Fmt {
+                paths: files.paths,
+                check: files.switches[0],
+                max_unformatted: files.threshold,
+                changed_only: files.changed_only,
+            })
+        }
+        "lint" => {
+            let files = FileArgs::parse(name, rest, "--max-warnings", &[])?;
+            Ok(Command::Lint {
+                paths: files.paths,
+                max_warnings: files.threshold,
+                changed_only: files.changed_only,
+            })
+        }
+        "ast" => Ok(Command::Ast {
+            path: single_path(name, rest)?,
+        }),
+        "tokens" => Ok(Command::Tokens {
+            path: single_path(name, rest)?,
+        }),
+        "test" => match rest {
+            [] => Ok(Command::Test {
+                dir: "tests".to_string(),
+            }),
+            [dir] => Ok(Command::Test { dir: dir.clone() }),
+            _ => Err("test takes at most one directory".to_string()),
+        },
+        "bench" => {
+            let mut iterations = 10;
+            let mut positional = vec![];
+            let mut rest = rest.iter();
+            while let Some(arg) = rest.next() {
+                if arg == "--iterations" || arg == "-n" {
+                    iterations = rest
+                        .next()
+                        .and_then(|n| n.parse().ok())
+                        .filter(|n| *n > 0)
+                        .ok_or(format!("{} expects a positive number", arg))?;
+                } else {
+                    positional.push(arg.clone());
+                }
+            }
+            Ok(Command::Bench {
+                path: single_path(name, &positional)?,
+                iterations,
+            })
+        }
+        "fix" => {
+            let positional = rest
+                .iter()
+                .filter(|arg| *arg != "--apply")
+                .cloned()
+                .collect::<Vec<String>>();
+            if let Some(arg) = positional.iter().find(|arg| arg.starts_with("--")) {
+                return Err(format!("Unknown option '{}' for fix", arg));
+            }
+            Ok(Command::Fix {
+                paths: paths(name, &positional)?,
+                apply: positional.len() < rest.len(),
+            })
+        }
+        "hash" => {
+            let switch = |flag: &str| rest.iter().any(|arg| arg == flag);
+            let positional = rest
+                .iter()
+                .filter(|arg| *arg != "--ignore-names" && *arg != "--canonical")
+                .cloned()
+                .collect::<Vec<String>>();
+            if let Some(arg) = positional.iter().find(|arg| arg.starts_with("--")) {
+                return Err(format!("Unknown option '{}' for hash", arg));
+            }
+            Ok(Command::Hash {
+                paths: paths(name, &positional)?,
+                ignore_names: switch("--ignore-names"),
+                canonical: switch("--canonical"),
+            })
+        }
+        "xref" => {
+            let mut query = None;
+            let mut json = false;
+            let mut positional = vec![];
+            let mut rest = rest.iter();
+            while let Some(arg) = rest.next() {
+                let make = match arg.as_str() {
+                    "--find-def" => Query::Definition,
+                    "--find-refs" => Query::References,
+                    "--json" => {
+                        json = true;
+                        continue;
+                    }
+                    _ => {
+                        positional.push(arg.clone());
+                        continue;
+                    }
+                };
+                if query.is_some() {
+                    return Err("xref takes a single --find-def or --find-refs".to_string());
+                }
+                let name = rest.next().ok_or(format!("{} expects a name", arg))?;
+                query = Some(make(name.clone()));
+            }
+            if json && !cfg!(feature = "json") {
+                return Err("rlox was built without the json feature".to_string());
+            }
+            Ok(Command::Xref {
+                path: single_path(name, &positional)?,
+                query: query.ok_or("xref expects --find-def or --find-refs".to_string())?,
+                json,
+            })
+        }
+        "mutate" => {
+            let mut min_score = 100;
+            let mut positional = vec![];
+            let mut rest = rest.iter();
+            while let Some(arg) = rest.next() {
+                if arg == "--min-score" {
+                    min_score = rest
+                        .next()
+                        .and_then(|n| n.parse().ok())
+                        .filter(|n| *n <= 100)
+                        .ok_or(format!("{} expects a percentage", arg))?;
+                } else {
+                    positional.push(arg.clone());
+                }
+            }
+            match positional.as_slice() {
+                [] => Ok(Command::Mutate {
+                    dir: "tests".to_string(),
+                    min_score,
+                }),
+                [dir] => Ok(Command::Mutate {
+                    dir: dir.clone(),
+                    min_score,
+                }),
+                _ => Err("mutate takes at most one directory".to_string()),
+            }
+        }
+        path if !path.starts_with('-') && rest.is_empty() => Ok(Command::Run {
+            path: path.to_string(),
+            stream: false,
+            mutant: None,
+        }),
+        other => Err(format!("Unknown command '{}'", other)),
+    };
+}
+
+pub fn help(topic: Option<&str>) -> String {
+    let text = match topic {
+        Some("run") => {
+            "Usage: rlox run [--stream] <file>\n\nScans, parses and runs a script. With --stream each statement runs as\nsoon as it's parsed instead of after the whole script parsed, output\nstarts earlier and parsed statements aren't kept. Nothing runs after a\nsyntax error, but the rest of the script is still checked. The whole\nscript is still read and scanned before the first statement runs, so\nits tokens are all held at once and a scan error anywhere in it stops\nthe script before anything runs."
+        }
+        Some("repl") => {
+            "Usage: rlox repl [--output plain|json|quiet] [--restore <session>]\n\nStarts an interactive session. Lines without a trailing ';' are\nevaluated as expressions and their value is printed.\n\n--output json writes one JSON object per line for every result, print\nand diagnostic, with a \"type\" of \"result\", \"print\" or \"diagnostic\".\n--output quiet doesn't echo values.\n\n`:save <file>` writes every global variable to a JSON session file and\n--restore <file> starts a new session with them defined again."
+        }
+        Some("kernel") => {
+            "Usage: rlox kernel\n\nServes a minimal Jupyter style protocol over stdio, one JSON message\nper line. Requests carry a \"msg_type\" of \"kernel_info_request\",\n\"execute_request\" (with {\"code\": ...} as \"content\") or\n\"shutdown_request\". Every request is answered with \"status\" busy/idle\nmessages around its replies: \"stream\" for prints and warnings,\n\"execute_result\" for the value of a trailing expression, \"error\" and\n\"execute_reply\". Replies point at the request through \"parent_id\"."
+        }
+        Some("check") => {
+            "Usage: rlox check <files...>\n\nScans and parses scripts without running them, exits with 65 on errors."
+        }
+        Some("fmt") => {
+            "Usage: rlox fmt [--check] [--max-unformatted N] [--changed-only] <files...>\n\nFormats scripts in place. With --check nothing is written, a diff is\nprinted for every unformatted file and the command exits with 1 when\nmore than N files (default: 0) are unformatted. With --changed-only the\nfiles are read from stdin, one per line, and files that aren't .lox or\nno longer exist are skipped."
+        }
+        Some("lint") => {
+            "Usage: rlox lint [--max-warnings N] [--changed-only] <files...>\n\nReports parser warnings, unused and redeclared variables grouped by\nfile and rule. Exits with 1 when there are more than N findings\n(default: 0) and 65 when a file doesn't parse. --changed-only reads the\nfiles from stdin like fmt does."
+        }
+        Some("ast") => "Usage: rlox ast <file>\n\nPrints the parsed syntax tree of a script.",
+        Some("tokens") => "Usage: rlox tokens <file>\n\nPrints the tokens scanned from a script.",
+        Some("test") => {
+            "Usage: rlox test [dir]\n\nRuns every .lox file in dir (default: tests), a file fails when it\nhas syntax or runtime errors."
+        }
+        Some("bench") => {
+            "Usage: rlox bench [--iterations N] <file>\n\nRuns a script N times (default: 10) and reports timings."
+        }
+        Some("mutate") => {
+            "Usage: rlox mutate [--min-score N] [dir]\n\nRuns every .lox file in dir (default: tests) once normally and once per\noperator mutant, like `<` behaving as `<=`. A mutant is caught when any\nscript's output or exit status changes. Exits with 1 when fewer than N\npercent (default: 100) of the mutants are caught."
+        }
+        Some("fix") => {
+            return format!(
+                "Usage: rlox fix [--apply] <files...>\n\nRewrites scripts written for an older dialect and re-emits them through\nthe formatter. Without --apply nothing is written, a diff is printed for\nevery file that needs fixing and the command exits with 1 when there is\none. Scripts that don't parse are left alone.\n\nFixes:\n{}",
+                crate::fix::FIXES
+                    .iter()
+                    .map(|fix| format!("    {:<20}{}", fix.name, fix.description))
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            );
+        }
+        Some("hash") => {
+            "Usage: rlox hash [--ignore-names] [--canonical] <files...>\n\nPrints a hash of each script's syntax tree followed by its path.\nComments, whitespace, quote style, redundant parentheses and `^` vs `**`\ndon't change the hash. With --ignore-names variables are numbered in\norder of first use, so renaming them doesn't either. --canonical prints\nthe canonical form that gets hashed instead."
+        }
+        Some("xref") => {
+            "Usage: rlox xref <file> --find-def <name> [--json]\n       rlox xref <file> --find-refs <name> [--json]\n\nPrints where a variable is declared, or every place it's read or\nassigned, one `file:line` per site. Names bound by match arms are\nseparate from globals with the same name and only cover their arm.\n--json prints a single object with a \"sites\" array for editors. Exits\nwith 1 when nothing is found."
+        }
+        Some("messages") => {
+            "Usage: rlox messages\n\nPrints every error and warning text as `code = \"text\"` lines. Saved to\na file and edited, for example translated, it can be given to\n--messages or the `messages` key in rlox.toml to replace the built in\ntexts. `{name}` placeholders are filled in with details of the error,\ncodes left out of the file keep their built in text."
+        }
+        _ => USAGE,
+    };
+    return text.to_string();
+}
+
+///file list arguments shared by fmt and lint
+struct FileArgs {
+    paths: Vec<String>,
+    threshold: usize,
+    changed_only: bool,
+    ///whether each of the command's own switches was given
+    switches: Vec<bool>,
+}
+
+impl FileArgs {
+    fn parse(
+        name: &str,
+        rest: &[String],
+        threshold_flag: &str,
+        switches: &[&str],
+    ) -> Result<FileArgs, String> {
+        let mut files = FileArgs {
+            paths: vec![],
+            threshold: 0,
+            changed_only: false,
+            switches: vec![false; switches.len()],
+        };
+        let mut rest = rest.iter();
+        while let Some(arg) = rest.next() {
+            if arg == threshold_flag {
+                files.threshold = rest
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or(format!("{} expects a number", arg))?;
+            } else if arg == "--changed-only" {
+                files.changed_only = true;
+            } else if let Some(i) = switches.iter().position(|switch| switch == arg) {
+                files.switches[i] = true;
+            } else if arg.starts_with("--") {
+                return Err(format!("Unknown option '{}' for {}", arg, name));
+            } else {
+                files.paths.push(arg.clone());
+            }
+        }
+        if files.changed_only && !files.paths.is_empty() {
+            return Err("--changed-only reads files from stdin, don't pass any".to_string());
+        }
+        if !files.changed_only && files.paths.is_empty() {
+            return Err(format!("{} expects at least one file", name));
+        }
+        return Ok(files);
+    }
+}
+
+fn single_path(name: &str, rest: &[String]) -> Result<String, String> {
+    match rest {
+        [path] => Ok(path.clone()),
+        [] => Err(format!("{} expects a file", name)),
+        _ => Err(format!("{} expects exactly one file", name)),
+    }
+}
+
+fn paths(name: &str, rest: &[String]) -> Result<Vec<String>, String> {
+    if rest.is_empty() {
+        return Err(format!("{} expects at least one file", name));
+    }
+    return Ok(rest.to_vec());
+}
@@ -0,0 +1,71 @@
+use std::{
+    io::IsTerminal,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+///when errors and warnings written to stderr are colored
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ColorChoice {
+    ///only when stderr is a terminal
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn from_name(name: &str) -> Result<ColorChoice, String> {
+        return match name {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            other => Err(format!("Unknown color choice '{}'", other)),
+        };
+    }
+
+    pub fn enabled(&self) -> bool {
+        return match self {
+            ColorChoice::Auto => std::io::stderr().is_terminal(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        };
+    }
+}
+
+///what a piece of a diagnostic is, which decides its color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Style {
+    Error,
+    Warning,
+    ///the line numbers and margin around quoted source
+    Note,
+}
+
+impl Style {
+    ///`text` wrapped in this style's escape codes, unchanged when `enabled`
+    ///is false
+    pub fn apply(&self, text: &str, enabled: bool) -> String {
+        if !enabled || text.is_empty() {
+            return text.to_string();
+        }
+        let code = match self {
+            Style::Error => "1;31",
+            Style::Warning => "1;33",
+            Style::Note => "36",
+        };
+        return format!("\x1b[{}m{}\x1b[0m", code, text);
+    }
+}
+
+///off until `install` is called, so library users and tests get plain text
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+///decides once, at startup, whether diagnostics are colored
+pub fn install(choice: ColorChoice) {
+    ENABLED.store(choice.enabled(), Ordering::Relaxed);
+}
+
+///`text` in `style` when the installed choice enables color
+pub fn paint(style: Style, text: &str) -> String {
+    return style.apply(text, ENABLED.load(Ordering::Relaxed));
+}
@@ -0,0 +1,436 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    ast::{LoxError, Truthiness},
+    color::ColorChoice,
+    formatter::{FormatOptions, QuoteStyle},
+    interpreter::{AssignPolicy, Interpreter},
+    parser::Parser,
+};
+
+pub const CONFIG_FILE: &str = "rlox.toml";
+
+///project settings from `rlox.toml`, with command line flags merged on top
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoxConfig {
+    pub dialect: Dialect,
+    pub warnings: WarningLevel,
+    ///directories searched for scripts that aren't found relative to the
+    ///working directory
+    pub include_paths: Vec<PathBuf>,
+    pub format: FormatOptions,
+    ///statements and expressions a script may run, `None` means unlimited
+    pub step_limit: Option<usize>,
+    ///a message catalog replacing the built in error and warning texts
+    pub messages: Option<PathBuf>,
+    pub extensions: Extensions,
+    pub truthiness: Truthiness,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Dialect {
+    ///plain Lox, assigning to an undeclared name is an error
+    #[default]
+    Strict,
+    ///assigning to an undeclared name declares a global
+    Extended,
+}
+
+///a language feature that isn't in the book's Lox
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Extension {
+    ///integer numbers, without them every number is a float
+    Integers,
+    ///`**` and `^`
+    Exponent,
+    SingleQuotes,
+    Dump,
+    Match,
+    Lists,
+    Maps,
+    ///`+=`, `-=`, `*=` and `/=`
+    CompoundAssignment,
+    ForIn,
+    Assert,
+    Tuples,
+    NilCoalescing,
+    RadixLiterals,
+}
+
+pub const EXTENSIONS: &[Extension] = &[
+    Extension::Integers,
+    Extension::Exponent,
+    Extension::SingleQuotes,
+    Extension::Dump,
+    Extension::Match,
+    Extension::Lists,
+    Extension::Maps,
+    Extension::CompoundAssignment,
+    Extension::ForIn,
+    Extension::Assert,
+    Extension::Tuples,
+    Extension::NilCoalescing,
+    Extension::RadixLiterals,
+];
+
+///which extensions a script may use, all of them by default
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Extensions {
+    pub disabled: Vec<Extension>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum WarningLevel {
+    Allow,
+    #[default]
+    Warn,
+    ///warnings are reported as errors
+    Deny,
+}
+
+///settings given on the command line, `None` keeps the config file's value
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigOverrides {
+    pub config: Option<PathBuf>,
+    pub no_config: bool,
+    pub dialect: Option<Dialect>,
+    pub warnings: Option<WarningLevel>,
+    pub include_paths: Vec<PathBuf>,
+    pub quotes: Option<QuoteStyle>,
+    pub step_limit: Option<usize>,
+    pub messages: Option<PathBuf>,
+    pub extensions: Option<Extensions>,
+    pub truthiness: Option<Truthiness>,
+    ///only used by the command line, diagnostics aren't colored otherwise
+    pub color: ColorChoice,
+}
+
+impl LoxConfig {
+    ///finds the closest `rlox.toml` in `start` or any of its parents
+    pub fn find(start: &Path) -> Option<PathBuf> {
+        let start = start.canonicalize().ok()?;
+        let dir = if start.is_dir() {
+            start.as_path()
+        } else {
+            start.parent()?
+        };
+        return dir
+            .ancestors()
+            .map(|dir| dir.join(CONFIG_FILE))
+            .find(|path| path.is_file());
+    }
+
+    ///loads the config for `start` (a script or directory), the default
+    ///config when there is none
+    pub fn discover(start: &Path) -> Result<LoxConfig, LoxError> {
+        return match LoxConfig::find(start) {
+            Some(path) => LoxConfig::load(&path),
+            None => Ok(LoxConfig::default()),
+        };
+    }
+
+    ///reads a config file, include paths and the message catalog are
+    ///relative to the file
+    pub fn load(path: &Path) -> Result<LoxConfig, LoxError> {
+        let source = std::fs::read_to_string(path)?;
+        let mut config = LoxConfig::parse(&source).map_err(|(line, message)| LoxError::Config {
+            path: path.display().to_string(),
+            line,
+            message,
+        })?;
+        if let Some(dir) = path.parent() {
+            for include in config.include_paths.iter_mut() {
+                *include = dir.join(&include);
+            }
+            if let Some(messages) = config.messages.as_mut() {
+                *messages = dir.join(&messages);
+            }
+        }
+        return Ok(config);
+    }
+
+    ///parses the subset of TOML rlox.toml uses: top level keys and a
+    ///`[format]` table holding strings, integers and arrays of strings
+    pub fn parse(source: &str) -> Result<LoxConfig, (usize, String)> {
+        let mut config = LoxConfig::default();
+        let mut table = String::new();
+        for (i, line) in source.lines().enumerate() {
+            let line_no = i + 1;
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[') {
+                table = name
+                    .strip_suffix(']')
+                    .ok_or((line_no, "Expected ']' after table name".to_string()))?
+                    .trim()
+                    .to_string();
+                if table != "format" {
+                    return Err((line_no, format!("Unknown table '{}'", table)));
+                }
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or((line_no, "Expected 'key = value'".to_string()))?;
+            let value = Value::parse(value.trim()).map_err(|msg| (line_no, msg))?;
+            config
+                .set(&table, key.trim(), value)
+                .map_err(|msg| (line_no, msg))?;
+        }
+        return Ok(config);
+    }
+
+    fn set(&mut self, table: &str, key: &str, value: Value) -> Result<(), String> {
+        match (table, key) {
+            ("", "dialect") => self.dialect = Dialect::from_name(&value.string()?)?,
+            ("", "warnings") => self.warnings = WarningLevel::from_name(&value.string()?)?,
+            ("", "include_paths") => {
+                self.include_paths = value.strings()?.into_iter().map(PathBuf::from).collect()
+            }
+            ("", "step_limit") => self.step_limit = Some(value.integer()?),
+            ("", "messages") => self.messages = Some(PathBuf::from(value.string()?)),
+            ("", "extensions") => {
+                let names = value.strings()?;
+                self.extensions =
+                    Extensions::only(&names.iter().map(String::as_str).collect::<Vec<&str>>())?
+            }
+            ("", "truthiness") => self.truthiness = Truthiness::from_name(&value.string()?)?,
+            ("format", "quotes") => self.format.quotes = QuoteStyle::from_name(&value.string()?)?,
+            ("", key) => return Err(format!("Unknown key '{}'", key)),
+            (table, key) => return Err(format!("Unknown key '{}.{}'", table, key)),
+        }
+        return Ok(());
+    }
+
+    ///applies command line flags on top of the file's settings
+    pub fn merge(&mut self, overrides: &ConfigOverrides) {
+        if let Some(dialect) = overrides.dialect {
+            self.dialect = dialect;
+        }
+        if let Some(warnings) = overrides.warnings {
+            self.warnings = warnings;
+        }
+        if let Some(quotes) = overrides.quotes {
+            self.format.quotes = quotes;
+        }
+        if let Some(step_limit) = overrides.step_limit {
+            self.step_limit = Some(step_limit);
+        }
+        if let Some(messages) = &overrides.messages {
+            self.messages = Some(messages.clone());
+        }
+        if let Some(extensions) = &overrides.extensions {
+            self.extensions = extensions.clone();
+        }
+        if let Some(truthiness) = overrides.truthiness {
+            self.truthiness = truthiness;
+        }
+        // flags are searched before the config file's include paths
+        let mut include_paths = overrides.include_paths.clone();
+        include_paths.append(&mut self.include_paths);
+        self.include_paths = include_paths;
+    }
+
+    ///`path` when it exists, otherwise the first match in the include paths
+    pub fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let direct = PathBuf::from(path);
+        if direct.exists() {
+            return Some(direct);
+        }
+        if direct.is_absolute() {
+            return None;
+        }
+        return self
+            .include_paths
+            .iter()
+            .map(|dir| dir.join(path))
+            .find(|path| path.exists());
+    }
+
+    pub fn parser(&self) -> Parser<'static> {
+        let mut parser = Parser::new();
+        parser.warning_level = self.warnings;
+        parser.extensions = self.extensions.clone();
+        return parser;
+    }
+
+    pub fn interpreter(&self) -> Interpreter {
+        let policy = match self.dialect {
+            Dialect::Strict => AssignPolicy::Strict,
+            Dialect::Extended => AssignPolicy::ImplicitGlobal,
+        };
+        let interpreter = Interpreter::new()
+            .with_assign_policy(policy)
+            .with_truthiness(self.truthiness);
+        return match self.step_limit {
+            Some(step_limit) => interpreter.with_fuel(step_limit),
+            None => interpreter,
+        };
+    }
+}
+
+impl Dialect {
+    pub fn from_name(name: &str) -> Result<Dialect, String> {
+        return match name {
+            "strict" => Ok(Dialect::Strict),
+            "extended" => Ok(Dialect::Extended),
+            other => Err(format!("Unknown dialect '{}'", other)),
+        };
+    }
+}
+
+impl Extension {
+    pub fn name(&self) -> &'static str {
+        return match self {
+            Extension::Integers => "integers",
+            Extension::Exponent => "exponent",
+            Extension::SingleQuotes => "single-quotes",
+            Extension::Dump => "dump",
+            Extension::Match => "match",
+            Extension::Lists => "lists",
+            Extension::Maps => "maps",
+            Extension::CompoundAssignment => "compound-assignment",
+            Extension::ForIn => "for-in",
+            Extension::Assert => "assert",
+            Extension::Tuples => "tuples",
+            Extension::NilCoalescing => "nil-coalescing",
+            Extension::RadixLiterals => "radix-literals",
+        };
+    }
+
+    pub fn from_name(name: &str) -> Result<Extension, String> {
+        return EXTENSIONS
+            .iter()
+            .find(|extension| extension.name() == name)
+            .copied()
+            .ok_or(format!("Unknown extension '{}'", name));
+    }
+}
+
+impl Extensions {
+    ///enables the named extensions and disables the rest
+    pub fn only(names: &[&str]) -> Result<Extensions, String> {
+        let enabled = names
+            .iter()
+            .map(|name| Extension::from_name(name))
+            .collect::<Result<Vec<Extension>, String>>()?;
+        return Ok(Extensions {
+            disabled: EXTENSIONS
+                .iter()
+                .filter(|extension| !enabled.contains(extension))
+                .copied()
+                .collect(),
+        });
+    }
+
+    pub fn enabled(&self, extension: Extension) -> bool {
+        return !self.disabled.contains(&extension);
+    }
+}
+
+impl WarningLevel {
+    pub fn from_name(name: &str) -> Result<WarningLevel, String> {
+        return match name {
+            "allow" => Ok(WarningLevel::Allow),
+            "warn" => Ok(WarningLevel::Warn),
+            "deny" => Ok(WarningLevel::Deny),
+            other => Err(format!("Unknown warning level '{}'", other)),
+        };
+    }
+}
+
+enum Value {
+    String(String),
+    Integer(usize),
+    Array(Vec<String>),
+}
+
+impl Value {
+    fn parse(value: &str) -> Result<Value, String> {
+        if let Some(items) = value.strip_prefix('[') {
+            let items = items
+                .strip_suffix(']')
+                .ok_or("Arrays must be closed on the same line")?;
+            return items
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(parse_string)
+                .collect::<Result<Vec<String>, String>>()
+                .map(Value::Array);
+        }
+        if value.starts_with('"') {
+            return parse_string(value).map(Value::String);
+        }
+        return value
+            .replace('_', "")
+            .parse()
+            .map(Value::Integer)
+            .map_err(|_| format!("Invalid value '{}'", value));
+    }
+
+    fn string(self) -> Result<String, String> {
+        match self {
+            Value::String(s) => Ok(s),
+            _ => Err("Expected a string".to_string()),
+        }
+    }
+
+    fn integer(self) -> Result<usize, String> {
+        match self {
+            Value::Integer(n) => Ok(n),
+            _ => Err("Expected a number".to_string()),
+        }
+    }
+
+    fn strings(self) -> Result<Vec<String>, String> {
+        match self {
+            Value::Array(items) => Ok(items),
+            _ => Err("Expected an array of strings".to_string()),
+        }
+    }
+}
+
+pub fn parse_string(value: &str) -> Result<String, String> {
+    let body = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or(format!("Expected a quoted string, found '{}'", value))?;
+    let mut out = String::new();
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            other => return Err(format!("Unsupported escape '\\{}'", other.unwrap_or(' '))),
+        }
+    }
+    return Ok(out);
+}
+
+///drops a `#` comment, ignoring any inside a string
+pub fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '\\' if in_string => {
+                escaped = !escaped;
+                continue;
+            }
+            '"' if !escaped => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+        escaped = false;
+    }
+    return line;
+}
@@ -0,0 +1,199 @@
+#[cfg(not(feature = "minimal"))]
+use std::{backtrace::Backtrace, panic::PanicHookInfo};
+use std::{
+    cell::{Cell, RefCell},
+    path::PathBuf,
+};
+
+pub const ISSUES_URL: &str = "https://github.com/RoeeJ/rlox/issues";
+
+///lines of source shown on each side of the crash site
+const CHUNK_RADIUS: usize = 3;
+
+///what the interpreter was working on, kept up to date so a panic hook can
+///describe where rlox crashed without touching interpreter state
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CrashContext {
+    pub script: Script,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Script {
+    #[default]
+    Unknown,
+    File(PathBuf),
+    ///source that didn't come from a file, like a REPL line
+    Input(String),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Position {
+    pub phase: Phase,
+    pub line: usize,
+    ///index of the last token the parser consumed
+    pub token: Option<usize>,
+    ///index of the top level statement being executed, statements are the
+    ///only AST nodes with a stable position
+    pub statement: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Phase {
+    #[default]
+    Idle,
+    Parsing,
+    Running,
+}
+
+thread_local! {
+    static SCRIPT: RefCell<Script> = const { RefCell::new(Script::Unknown) };
+    static POSITION: Cell<Position> = Cell::new(Position::default());
+}
+
+pub fn set_script(script: Script) {
+    SCRIPT.with(|current| *current.borrow_mut() = script);
+    POSITION.set(Position::default());
+}
+
+pub fn at_token(index: usize, line: usize) {
+    POSITION.set(Position {
+        phase: Phase::Parsing,
+        line,
+        token: Some(index),
+        statement: None,
+    });
+}
+
+pub fn at_statement(index: usize, line: Option<usize>) {
+    let position = POSITION.get();
+    POSITION.set(Position {
+        phase: Phase::Running,
+        line: line.unwrap_or(position.line),
+        token: position.token,
+        statement: Some(index),
+    });
+}
+
+pub fn context() -> CrashContext {
+    return CrashContext {
+        script: SCRIPT.with(|script| script.borrow().clone()),
+        position: POSITION.get(),
+    };
+}
+
+///installs a panic hook that keeps the default panic message and then
+///writes a crash report to the temp directory
+#[cfg(not(feature = "minimal"))]
+pub fn install() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        let report = render_report(
+            &context(),
+            &panic_message(info),
+            &Backtrace::force_capture().to_string(),
+        );
+        let path = std::env::temp_dir().join(format!("rlox-crash-{}.txt", std::process::id()));
+        match std::fs::write(&path, report) {
+            Ok(()) => eprintln!(
+                "\nrlox crashed, this is a bug in rlox and not in your script.\n\
+                 A crash report was written to {}\n\
+                 Please open an issue at {} and attach it.\n\
+                 Nothing has been sent anywhere.",
+                path.display(),
+                ISSUES_URL
+            ),
+            Err(e) => eprintln!(
+                "\nrlox crashed and the crash report couldn't be written: {}",
+                e
+            ),
+        }
+    }));
+}
+
+///minimal builds can't write files, the default panic hook stays
+#[cfg(feature = "minimal")]
+pub fn install() {}
+
+#[cfg(not(feature = "minimal"))]
+fn panic_message(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    return match info.location() {
+        Some(location) => format!("{} at {}", message, location),
+        None => message,
+    };
+}
+
+pub fn render_report(context: &CrashContext, message: &str, backtrace: &str) -> String {
+    let position = context.position;
+    let mut out = format!(
+        "rlox {} crash report\n\npanic: {}\nphase: {:?}\n",
+        env!("CARGO_PKG_VERSION"),
+        message,
+        position.phase
+    );
+    let source = match &context.script {
+        Script::Unknown => None,
+        Script::File(path) => {
+            out.push_str(&format!("script: {}\n", path.display()));
+            read_script(path)
+        }
+        Script::Input(input) => {
+            out.push_str("script: <input>\n");
+            Some(input.clone())
+        }
+    };
+    if let Some(token) = position.token {
+        out.push_str(&format!("token index: {}\n", token));
+    }
+    if let Some(statement) = position.statement {
+        out.push_str(&format!("statement index: {}\n", statement));
+    }
+    if position.line > 0 {
+        out.push_str(&format!("line: {}\n", position.line));
+    }
+    if let Some(source) = source {
+        out.push_str("\nsource:\n");
+        out.push_str(&source_chunk(&source, position.line));
+    }
+    out.push_str("\nbacktrace:\n");
+    out.push_str(backtrace);
+    out.push('\n');
+    return out;
+}
+
+#[cfg(not(feature = "minimal"))]
+fn read_script(path: &std::path::Path) -> Option<String> {
+    return std::fs::read_to_string(path).ok();
+}
+
+#[cfg(feature = "minimal")]
+fn read_script(_path: &std::path::Path) -> Option<String> {
+    return None;
+}
+
+///the lines around `line` with the crash line marked, the start of the
+///source when the line isn't known
+fn source_chunk(source: &str, line: usize) -> String {
+    let first = line.saturating_sub(CHUNK_RADIUS).max(1);
+    let last = line.max(1) + CHUNK_RADIUS;
+    let mut out = String::new();
+    for (i, text) in source.lines().enumerate() {
+        let number = i + 1;
+        if number < first {
+            continue;
+        }
+        if number > last {
+            break;
+        }
+        let marker = if number == line { ">" } else { " " };
+        out.push_str(&format!("{} {:>4} | {}\n", marker, number, text));
+    }
+    return out;
+}
@@ -0,0 +1,111 @@
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit {
+    Keep,
+    Delete,
+    Insert,
+}
+
+///renders a unified diff of two texts line by line, empty when they match
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+    // lines keep their '\n' so a missing newline at the end is a change
+    let old_lines = old.split_inclusive('\n').collect::<Vec<&str>>();
+    let new_lines = new.split_inclusive('\n').collect::<Vec<&str>>();
+    let edits = diff_lines(&old_lines, &new_lines);
+
+    let mut out = format!("--- {}\n+++ {}\n", old_name, new_name);
+    let mut i = 0;
+    while i < edits.len() {
+        // skip to the next change, keeping CONTEXT lines before it
+        let change = match edits[i..]
+            .iter()
+            .position(|(edit, _, _)| *edit != Edit::Keep)
+        {
+            Some(offset) => i + offset,
+            None => break,
+        };
+        let start = change.saturating_sub(CONTEXT).max(i);
+        // a hunk ends once more than 2 * CONTEXT unchanged lines follow
+        let mut end = change;
+        let mut unchanged = 0;
+        while end < edits.len() && unchanged <= CONTEXT * 2 {
+            if edits[end].0 == Edit::Keep {
+                unchanged += 1;
+            } else {
+                unchanged = 0;
+            }
+            end += 1;
+        }
+        end -= unchanged.saturating_sub(CONTEXT);
+
+        let hunk = &edits[start..end];
+        let old_count = hunk.iter().filter(|e| e.0 != Edit::Insert).count();
+        let new_count = hunk.iter().filter(|e| e.0 != Edit::Delete).count();
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(hunk[0].1, old_count),
+            range(hunk[0].2, new_count)
+        ));
+        for (edit, old_i, new_i) in hunk {
+            let (prefix, line) = match edit {
+                Edit::Keep => (' ', old_lines[*old_i]),
+                Edit::Delete => ('-', old_lines[*old_i]),
+                Edit::Insert => ('+', new_lines[*new_i]),
+            };
+            out.push(prefix);
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+        i = end;
+    }
+    return out;
+}
+
+///hunk header range, 1-based, pointing before the hunk when it's empty
+fn range(start: usize, count: usize) -> String {
+    if count == 0 {
+        return format!("{},0", start);
+    }
+    if count == 1 {
+        return format!("{}", start + 1);
+    }
+    return format!("{},{}", start + 1, count);
+}
+
+///longest common subsequence edit script, each edit carries the line index
+///it is at in both texts
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<(Edit, usize, usize)> {
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut edits = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            edits.push((Edit::Keep, i, j));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            edits.push((Edit::Delete, i, j));
+            i += 1;
+        } else {
+            edits.push((Edit::Insert, i, j));
+            j += 1;
+        }
+    }
+    return edits;
+}
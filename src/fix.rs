@@ -0,0 +1,68 @@
+use crate::{
+    ast::{Diagnostic, Token, TokenType},
+    config::{LoxConfig, WarningLevel},
+    formatter::{format_tokens, keep_shebang},
+    scanner::scan_to_vec,
+};
+
+///a mechanical rewrite from an old dialect to the current one
+pub struct Fix {
+    pub name: &'static str,
+    pub description: &'static str,
+    ///rewrites `tokens` in place and returns how many places changed
+    apply: fn(&mut [Token]) -> usize,
+}
+
+pub const FIXES: &[Fix] = &[Fix {
+    name: "caret-exponent",
+    description: "writes the `^` exponent operator as `**`",
+    apply: caret_exponent,
+}];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fixed {
+    pub source: String,
+    ///fixes that changed something and how many places each changed
+    pub applied: Vec<(&'static str, usize)>,
+}
+
+///applies every fix to a script that parses, the result is re-emitted
+///through the formatter, sources nothing applies to are returned as is
+pub fn fix_source(source: &str, config: &LoxConfig) -> Result<Fixed, Vec<Diagnostic>> {
+    let mut parser = config.parser();
+    parser.warning_level = WarningLevel::Allow;
+    let diagnostics = parser.syntax_errors(source.to_string());
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+
+    let (mut tokens, _) = scan_to_vec(source);
+    let mut applied = vec![];
+    for fix in FIXES {
+        let count = (fix.apply)(&mut tokens);
+        if count > 0 {
+            applied.push((fix.name, count));
+        }
+    }
+    if applied.is_empty() {
+        return Ok(Fixed {
+            source: source.to_string(),
+            applied,
+        });
+    }
+    return Ok(Fixed {
+        source: keep_shebang(source, &tokens, format_tokens(&tokens, &config.format)),
+        applied,
+    });
+}
+
+fn caret_exponent(tokens: &mut [Token]) -> usize {
+    let mut count = 0;
+    for token in tokens {
+        if token.token_type == TokenType::EXPONENT && token.lexeme == "^" {
+            token.lexeme = "**".to_string();
+            count += 1;
+        }
+    }
+    return count;
+}
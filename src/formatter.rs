@@ -0,0 +1,216 @@
+use crate::{
+    ast::{Diagnostic, Token, TokenType},
+    scanner::{scan_to_vec, shebang},
+};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FormatOptions {
+    pub quotes: QuoteStyle,
+}
+
+///which quote character string literals are written with
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum QuoteStyle {
+    #[default]
+    Preserve,
+    Double,
+    Single,
+}
+
+impl QuoteStyle {
+    pub fn from_name(name: &str) -> Result<QuoteStyle, String> {
+        return match name {
+            "preserve" => Ok(QuoteStyle::Preserve),
+            "double" => Ok(QuoteStyle::Double),
+            "single" => Ok(QuoteStyle::Single),
+            other => Err(format!("Unknown quote style '{}'", other)),
+        };
+    }
+}
+
+///re-emits `source` with normalized spacing, one statement per line,
+///comments and single blank lines between statements are kept
+pub fn format_source(source: &str, options: &FormatOptions) -> Result<String, Vec<Diagnostic>> {
+    let (tokens, diagnostics) = scan_to_vec(source);
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+    return Ok(keep_shebang(
+        source,
+        &tokens,
+        format_tokens(&tokens, options),
+    ));
+}
+
+///puts the shebang `source` starts with, if any, back in front of the
+///formatted tokens
+pub fn keep_shebang(source: &str, tokens: &[Token], formatted: String) -> String {
+    let Some(line) = shebang(source) else {
+        return formatted;
+    };
+    if formatted.is_empty() {
+        return format!("{}\n", line);
+    }
+    // a blank line after the shebang is kept like one between statements
+    let blank = tokens.first().is_some_and(|token| token.line > 2);
+    return format!("{}\n{}{}", line, if blank { "\n" } else { "" }, formatted);
+}
+
+///emits already scanned tokens like `format_source`, used by rewrites that
+///work on tokens
+pub fn format_tokens(tokens: &[Token], options: &FormatOptions) -> String {
+    let mut out = String::new();
+    let mut line = String::new();
+    let mut depth = 0usize;
+    let mut prev: Option<&Token> = None;
+    let mut prev_unary = false;
+
+    for token in tokens {
+        if token.token_type == TokenType::EOF {
+            break;
+        }
+        let starts_line = line.is_empty();
+        if starts_line {
+            if let Some(prev) = prev {
+                if token.line > prev.line + 1 && !out.ends_with("\n\n") && !out.is_empty() {
+                    out.push('\n');
+                }
+            }
+        }
+
+        if matches!(
+            token.token_type,
+            TokenType::RIGHT_PAREN | TokenType::RIGHT_BRACE
+        ) {
+            depth = depth.saturating_sub(1);
+        }
+
+        match token.token_type {
+            TokenType::COMMENT | TokenType::BLOCK_COMMENT => {
+                let trailing = prev.is_some_and(|prev| prev.line == token.line);
+                if trailing && !starts_line {
+                    line.push(' ');
+                    line.push_str(&token.lexeme);
+                } else if trailing && out.ends_with('\n') {
+                    // the statement was already flushed, reopen its line
+                    out.pop();
+                    line.push_str(out.split_off(out.rfind('\n').map_or(0, |i| i + 1)).as_str());
+                    line.push(' ');
+                    line.push_str(&token.lexeme);
+                } else {
+                    if !starts_line {
+                        flush(&mut out, &mut line);
+                    }
+                    line.push_str(&token.lexeme);
+                }
+                flush(&mut out, &mut line);
+                prev = Some(token);
+                continue;
+            }
+            _ => {}
+        }
+
+        if !starts_line && needs_space(prev, token, prev_unary) {
+            line.push(' ');
+        }
+        if token.token_type == TokenType::STRING {
+            line.push_str(&requote(&token.lexeme, options.quotes));
+        } else {
+            line.push_str(&token.lexeme);
+        }
+
+        prev_unary = matches!(token.token_type, TokenType::MINUS | TokenType::BANG)
+            && !prev.is_some_and(ends_operand);
+        if matches!(
+            token.token_type,
+            TokenType::LEFT_PAREN | TokenType::LEFT_BRACE
+        ) {
+            depth += 1;
+        }
+        if token.token_type == TokenType::SEMICOLON && depth == 0 {
+            flush(&mut out, &mut line);
+        }
+        prev = Some(token);
+    }
+    if !line.is_empty() {
+        flush(&mut out, &mut line);
+    }
+    return out;
+}
+
+///swaps a string literal's quotes when it doesn't contain the new quote
+fn requote(lexeme: &str, style: QuoteStyle) -> String {
+    let quote = match style {
+        QuoteStyle::Preserve => return lexeme.to_string(),
+        QuoteStyle::Double => '"',
+        QuoteStyle::Single => '\'',
+    };
+    let body = &lexeme[1..lexeme.len() - 1];
+    if body.contains(quote) {
+        return lexeme.to_string();
+    }
+    return format!("{quote}{body}{quote}");
+}
+
+fn flush(out: &mut String, line: &mut String) {
+    out.push_str(line.trim_end());
+    out.push('\n');
+    line.clear();
+}
+
+///whether a token can end an operand, making a following '-' binary
+fn ends_operand(token: &Token) -> bool {
+    return matches!(
+        token.token_type,
+        TokenType::IDENTIFIER
+            | TokenType::NUMBER
+            | TokenType::STRING
+            | TokenType::TRUE
+            | TokenType::FALSE
+            | TokenType::NIL
+            | TokenType::THIS
+            | TokenType::RIGHT_PAREN
+            | TokenType::RIGHT_BRACE
+            | TokenType::RIGHT_BRACKET
+    );
+}
+
+fn needs_space(prev: Option<&Token>, token: &Token, prev_unary: bool) -> bool {
+    let prev = match prev {
+        Some(prev) => prev,
+        None => return false,
+    };
+    if prev_unary {
+        return false;
+    }
+    if matches!(
+        token.token_type,
+        TokenType::SEMICOLON
+            | TokenType::COMMA
+            | TokenType::COLON
+            | TokenType::RIGHT_PAREN
+            | TokenType::RIGHT_BRACKET
+            | TokenType::DOT
+    ) {
+        return false;
+    }
+    if matches!(
+        prev.token_type,
+        TokenType::LEFT_PAREN | TokenType::LEFT_BRACKET | TokenType::DOT
+    ) {
+        return false;
+    }
+    // indexing, `xs[0]`, but not a list after the `in` of a for loop
+    let loop_in = prev.token_type == TokenType::IDENTIFIER && prev.lexeme == "in";
+    if token.token_type == TokenType::LEFT_BRACKET && ends_operand(prev) && !loop_in {
+        return false;
+    }
+    // an empty map, `{}`
+    if token.token_type == TokenType::RIGHT_BRACE && prev.token_type == TokenType::LEFT_BRACE {
+        return false;
+    }
+    if token.token_type == TokenType::LEFT_PAREN && prev.token_type == TokenType::IDENTIFIER {
+        return false;
+    }
+    return true;
+}
@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::{Expression, Pattern, Token, TokenLiteral, TokenType},
+    stmt::Statement,
+};
+
+///bumped whenever the canonical form changes so old hashes don't match,
+///everything in it is spelled out here rather than borrowed from `Debug`
+const CANONICAL_VERSION: &str = "rlox-ast-2";
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+///a stable hash of what the program does, ignoring comments, whitespace,
+///quotes, redundant parentheses and `^` vs `**`, with `ignore_names`
+///variables are numbered in order of first use
+pub fn hash_program(statements: &[Statement], ignore_names: bool) -> u64 {
+    return fnv1a(canonical_form(statements, ignore_names).as_bytes());
+}
+
+///one s-expression per statement, the input to `hash_program`
+pub fn canonical_form(statements: &[Statement], ignore_names: bool) -> String {
+    let mut canonical = Canonical {
+        ignore_names,
+        names: HashMap::new(),
+        out: format!("{}\n", CANONICAL_VERSION),
+    };
+    for statement in statements {
+        canonical.statement(statement);
+        canonical.out.push('\n');
+    }
+    return canonical.out;
+}
+
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = FNV_OFFSET;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    return hash;
+}
+
+struct Canonical {
+    ignore_names: bool,
+    names: HashMap<String, usize>,
+    out: String,
+}
+
+impl Canonical {
+    ///`^` and `**` are the same operator
+    fn operator(&mut self, operator: &Token) {
+        let text = match operator.token_type {
+            TokenType::PLUS => "+",
+            TokenType::MINUS => "-",
+            TokenType::STAR => "*",
+            TokenType::SLASH => "/",
+            TokenType::EXPONENT => "**",
+            TokenType::BANG => "!",
+            TokenType::BANG_EQUAL => "!=",
+            TokenType::EQUAL_EQUAL => "==",
+            TokenType::GREATER => ">",
+            TokenType::GREATER_EQUAL => ">=",
+            TokenType::LESS => "<",
+            TokenType::LESS_EQUAL => "<=",
+            TokenType::QUESTION_QUESTION => "??",
+            _ => &operator.lexeme,
+        };
+        self.out.push_str(text);
+    }
+
+    ///integers are written the same however they're stored, floats by
+    ///their bits and strings with their length so nothing in them can
+    ///close the form early
+    fn literal(&mut self, literal: &TokenLiteral) {
+        let text = match literal {
+            TokenLiteral::Empty => "nil".to_string(),
+            TokenLiteral::Boolean(b) => b.to_string(),
+            TokenLiteral::Integer(i) => format!("int:{}", i),
+            #[cfg(feature = "bigint")]
+            TokenLiteral::BigInt(n) => format!("int:{}", n),
+            TokenLiteral::Float(f) => format!("float:{:016x}", f.to_bits()),
+            TokenLiteral::String(s) => format!("str:{}:{}", s.len(), s),
+            // the parser only makes scalars, a collection is written as
+            // it prints
+            value @ (TokenLiteral::List(_) | TokenLiteral::Map(_) | TokenLiteral::Tuple(_)) => {
+                let text = value.to_string();
+                format!("value:{}:{}", text.len(), text)
+            }
+        };
+        self.out.push_str(&text);
+    }
+
+    fn name(&mut self, name: &Token) {
+        if !self.ignore_names {
+            self.out.push_str(&name.lexeme);
+            return;
+        }
+        let next = self.names.len();
+        let index = *self.names.entry(name.lexeme.clone()).or_insert(next);
+        self.out.push_str(&format!("${}", index));
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Expression(expr, _) => {
+                self.out.push_str("(expr ");
+                self.expression(expr);
+            }
+            Statement::Print(expr, _) => {
+                self.out.push_str("(print ");
+                self.expression(expr);
+            }
+            Statement::Var(name, initializer, _) => {
+                self.out.push_str("(var ");
+                self.name(name);
+                if let Some(initializer) = initializer {
+                    self.out.push(' ');
+                    self.expression(initializer);
+                }
+            }
+            Statement::Destructure {
+                kind,
+                names,
+                initializer,
+                ..
+            } => {
+                self.out.push_str(&format!("(var-{} (", kind.name()));
+                for (i, name) in names.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push(' ');
+                    }
+                    self.name(name);
+                }
+                self.out.push_str(") ");
+                self.expression(initializer);
+            }
+            Statement::Dump(_) => self.out.push_str("(dump"),
+            Statement::ForIn {
+                name,
+                iterable,
+                body,
+                ..
+            } => {
+                self.out.push_str("(for ");
+                self.name(name);
+                self.out.push(' ');
+                self.expression(iterable);
+                self.out.push(' ');
+                self.statement(body);
+            }
+            Statement::Assert {
+                condition, message, ..
+            } => {
+                self.out.push_str("(assert ");
+                self.expression(condition);
+                if let Some(message) = message {
+                    self.out.push(' ');
+                    self.expression(message);
+                }
+            }
+        }
+        self.out.push(')');
+    }
+
+    ///writes `expr` with a stack of its own, a long chain like `a + b + c`
+    ///nests as deep as it has operators
+    fn expression(&mut self, expr: &Expression) {
+        let mut tasks = vec![Task::Write(expr)];
+        while let Some(task) = tasks.pop() {
+            let expr = match task {
+                Task::Write(expr) => expr,
+                Task::Text(text) => {
+                    self.out.push_str(text);
+                    continue;
+                }
+                Task::Pattern(pattern) => {
+                    self.pattern(pattern);
+                    continue;
+                }
+            };
+            // the parts of `expr` are pushed last to first
+            match expr {
+                Expression::Binary {
+                    left,
+                    operator,
+                    right,
+                }
+                | Expression::Logical {
+                    left,
+                    operator,
+                    right,
+                } => {
+                    self.out.push('(');
+                    self.operator(operator);
+                    self.out.push(' ');
+                    tasks.extend([
+                        Task::Text(")"),
+                        Task::Write(right),
+                        Task::Text(" "),
+                        Task::Write(left),
+                    ]);
+                }
+                Expression::Unary { operator, right } => {
+                    self.out.push('(');
+                    self.operator(operator);
+                    self.out.push(' ');
+                    tasks.extend([Task::Text(")"), Task::Write(right)]);
+                }
+                Expression::Grouping(inner, _) => tasks.push(Task::Write(inner)),
+                Expression::Literal(literal, _) => self.literal(literal),
+                Expression::Variable { name, .. } => self.name(name),
+                Expression::Assign { name, value, .. } => {
+                    self.out.push_str("(assign ");
+                    self.name(name);
+                    self.out.push(' ');
+                    tasks.extend([Task::Text(")"), Task::Write(value)]);
+                }
+                Expression::Match { subject, arms, .. } => {
+                    self.out.push_str("(match ");
+                    tasks.push(Task::Text(")"));
+                    for arm in arms.iter().rev() {
+                        tasks.extend([Task::Text(")"), Task::Write(&arm.body), Task::Text(" ")]);
+                        tasks.extend([Task::Pattern(&arm.pattern), Task::Text(" (arm ")]);
+                    }
+                    tasks.push(Task::Write(subject));
+                }
+                Expression::ListLiteral { elements, .. } => {
+                    self.out.push_str("(list");
+                    self.elements(elements, &mut tasks);
+                }
+                Expression::TupleLiteral { elements, .. } => {
+                    self.out.push_str("(tuple");
+                    self.elements(elements, &mut tasks);
+                }
+                Expression::MapLiteral { entries, .. } => {
+                    self.out.push_str("(map");
+                    tasks.push(Task::Text(")"));
+                    for (key, value) in entries.iter().rev() {
+                        tasks.extend([
+                            Task::Text(")"),
+                            Task::Write(value),
+                            Task::Text(" "),
+                            Task::Write(key),
+                            Task::Text(" ("),
+                        ]);
+                    }
+                }
+                Expression::Index { object, index, .. } => {
+                    self.out.push_str("(index ");
+                    tasks.extend([
+                        Task::Text(")"),
+                        Task::Write(index),
+                        Task::Text(" "),
+                        Task::Write(object),
+                    ]);
+                }
+                Expression::IndexAssign {
+                    object,
+                    index,
+                    value,
+                    ..
+                } => {
+                    self.out.push_str("(index-assign ");
+                    tasks.extend([
+                        Task::Text(")"),
+                        Task::Write(value),
+                        Task::Text(" "),
+                        Task::Write(index),
+                        Task::Text(" "),
+                        Task::Write(object),
+                    ]);
+                }
+                Expression::Empty => self.out.push_str("(empty)"),
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Literal(literal) => self.literal(literal),
+            Pattern::Type(literal_type) => self.out.push_str(literal_type.name()),
+            Pattern::Binding(name) => self.name(name),
+            Pattern::Wildcard => self.out.push('_'),
+        }
+    }
+
+    ///` element` for each of `elements` and the closing paren
+    fn elements<'a>(&mut self, elements: &'a [Expression], tasks: &mut Vec<Task<'a>>) {
+        tasks.push(Task::Text(")"));
+        for element in elements.iter().rev() {
+            tasks.extend([Task::Write(element), Task::Text(" ")]);
+        }
+    }
+}
+
+enum Task<'a> {
+    Write(&'a Expression),
+    Text(&'a str),
+    Pattern(&'a Pattern),
+}
@@ -1,11 +1,357 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    io::{BufRead, Write},
+    ops::Range,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+#[cfg(feature = "bigint")]
+use crate::bigint;
 use crate::{
-    ast::{Expression, LoxError, Token, TokenLiteral},
-    stmt::Statement,
+    ast::{
+        Diagnostic, Expression, Local, LoxError, MapEntries, MapKey, MatchArm, ParserError,
+        Pattern, SharedList, SharedMap, Token, TokenLiteral, TokenType, Truthiness,
+    },
+    color::{paint, Style},
+    crash,
+    messages::{self, message},
+    mutate::Mutant,
+    parser::Parser,
+    sink::{Severity, SharedSink},
+    snippet::{self, SourceText},
+    stmt::{DestructureKind, Statement},
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Interpreter {
-    pub variables: Vec<Variable>,
+    ///every global declaration in order, kept in step with `global_slots`
+    pub(crate) variables: Vec<Variable>,
+    ///where in `variables` the latest declaration of each global is
+    global_slots: HashMap<String, usize>,
+    pub scopes: Vec<Vec<Variable>>,
+    pub limits: Limits,
+    pub assign_policy: AssignPolicy,
+    pub truthiness: Truthiness,
+    ///statements and expressions left to run, `None` means unlimited
+    pub fuel: Option<usize>,
+    ///the memory usage when it was last measured plus what was allocated
+    ///since, only tracked with `Limits::max_memory`
+    allocated: usize,
+    ///set once any statement fails at runtime
+    pub had_error: bool,
+    pub mutant: Option<Mutant>,
+    pub output: Output,
+    pub input: Input,
+    ///the script being run, runtime errors quote the failing statement
+    ///from it
+    pub source: Option<SourceText>,
+    ///where runtime errors are reported unless the output is captured
+    pub sink: SharedSink,
+}
+
+///where print statements and runtime errors go
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Output {
+    ///printed to stdout and stderr as they happen
+    #[default]
+    Stdio,
+    ///kept in order until the embedder takes them with `take_output`
+    Captured(Vec<OutputEvent>),
+    ///prints go to the writer, errors to stderr
+    Writer(SharedWriter),
+}
+
+///where an embedder wants prints to go, clones write to the same place
+#[derive(Clone)]
+pub struct SharedWriter(Arc<Mutex<dyn Write + Send>>);
+
+impl SharedWriter {
+    pub fn new(writer: impl Write + Send + 'static) -> SharedWriter {
+        return SharedWriter(Arc::new(Mutex::new(writer)));
+    }
+
+    fn write_line(&self, text: &str) -> std::io::Result<()> {
+        let mut writer = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        return writeln!(writer, "{}", text);
+    }
+}
+
+impl std::fmt::Debug for SharedWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SharedWriter")
+    }
+}
+
+///writers are equal only to their clones
+impl PartialEq for SharedWriter {
+    fn eq(&self, other: &Self) -> bool {
+        return Arc::ptr_eq(&self.0, &other.0);
+    }
+}
+
+///where lines a script reads come from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Input {
+    #[default]
+    Stdin,
+    ///lines from the reader, so tests and embedders can script the input
+    Reader(SharedReader),
+}
+
+impl Input {
+    ///the next line without its line ending, `None` once the input ended
+    pub fn read_line(&self) -> std::io::Result<Option<String>> {
+        let mut line = String::new();
+        let read = match self {
+            Input::Stdin => std::io::stdin().lock().read_line(&mut line)?,
+            Input::Reader(reader) => reader.read_line(&mut line)?,
+        };
+        if read == 0 {
+            return Ok(None);
+        }
+        let end = line.trim_end_matches(['\r', '\n']).len();
+        line.truncate(end);
+        return Ok(Some(line));
+    }
+}
+
+///where an embedder wants input to come from, clones read from the same
+///place
+#[derive(Clone)]
+pub struct SharedReader(Arc<Mutex<dyn BufRead + Send>>);
+
+impl SharedReader {
+    pub fn new(reader: impl BufRead + Send + 'static) -> SharedReader {
+        return SharedReader(Arc::new(Mutex::new(reader)));
+    }
+
+    fn read_line(&self, line: &mut String) -> std::io::Result<usize> {
+        let mut reader = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        return reader.read_line(line);
+    }
+}
+
+impl std::fmt::Debug for SharedReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SharedReader")
+    }
+}
+
+///readers are equal only to their clones
+impl PartialEq for SharedReader {
+    fn eq(&self, other: &Self) -> bool {
+        return Arc::ptr_eq(&self.0, &other.0);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputEvent {
+    Print(String),
+    Error {
+        line: Option<usize>,
+        code: Option<&'static str>,
+        message: String,
+    },
+}
+
+///what assigning to a name that was never declared does
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum AssignPolicy {
+    ///the assignment fails with an undefined variable error
+    #[default]
+    Strict,
+    ///the assignment declares a new global, convenient in the REPL
+    ImplicitGlobal,
+}
+
+///caps on the size of values a script may create, `None` means unlimited
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limits {
+    pub max_string_length: Option<usize>,
+    ///elements in a single list, tuple or map
+    pub max_elements: Option<usize>,
+    ///approximate bytes held by variables and the values they reach
+    pub max_memory: Option<usize>,
+}
+
+///heap bytes a value owns itself, its elements' own bytes aside
+fn shallow_size(value: &TokenLiteral) -> usize {
+    let slot = std::mem::size_of::<TokenLiteral>();
+    return match value {
+        TokenLiteral::String(s) => s.len(),
+        TokenLiteral::List(list) => list.borrow().len() * slot,
+        TokenLiteral::Map(map) => map.borrow().iter().fold(0, |size, (key, _)| {
+            let key = match key {
+                MapKey::String(s) => s.len(),
+                _ => 0,
+            };
+            size + std::mem::size_of::<(MapKey, TokenLiteral, usize)>() + key
+        }),
+        TokenLiteral::Tuple(elements) => elements.len() * slot,
+        _ => 0,
+    };
+}
+
+///bytes `roots` and everything they reach take, shared lists and maps
+///are counted once however many times they're reached
+fn held_size<'a>(roots: impl Iterator<Item = &'a TokenLiteral>) -> usize {
+    let mut seen = HashSet::new();
+    let mut pending = vec![];
+    let mut size = 0;
+    for root in roots {
+        size += reach(root, &mut seen, &mut pending);
+    }
+    // a stack of its own, lists can nest deeper than the host stack allows
+    while let Some(value) = pending.pop() {
+        match &value {
+            TokenLiteral::List(list) => {
+                for element in list.borrow().iter() {
+                    size += reach(element, &mut seen, &mut pending);
+                }
+            }
+            TokenLiteral::Map(map) => {
+                for (_, element) in map.borrow().iter() {
+                    size += reach(element, &mut seen, &mut pending);
+                }
+            }
+            TokenLiteral::Tuple(elements) => {
+                for element in elements.iter() {
+                    size += reach(element, &mut seen, &mut pending);
+                }
+            }
+            _ => {}
+        }
+    }
+    return size;
+}
+
+///the size of `value` itself, its elements are queued on `pending` the
+///first time a list or map is reached
+fn reach(
+    value: &TokenLiteral,
+    seen: &mut HashSet<usize>,
+    pending: &mut Vec<TokenLiteral>,
+) -> usize {
+    let first = match value {
+        TokenLiteral::List(list) => seen.insert(list.id()),
+        TokenLiteral::Map(map) => seen.insert(map.id()),
+        TokenLiteral::Tuple(_) => true,
+        _ => return shallow_size(value),
+    };
+    if !first {
+        return 0;
+    }
+    pending.push(value.clone());
+    return shallow_size(value);
+}
+
+///the values `names` are bound to, in order
+fn destructure(
+    kind: DestructureKind,
+    open: &Token,
+    names: &[Token],
+    value: TokenLiteral,
+) -> Result<Vec<TokenLiteral>, ParserError> {
+    let elements = match (kind, value) {
+        (DestructureKind::Tuple, TokenLiteral::Tuple(elements)) => elements.to_vec(),
+        (DestructureKind::List, TokenLiteral::List(list)) => list.borrow().clone(),
+        (DestructureKind::Map, TokenLiteral::Map(map)) => {
+            let map = map.borrow();
+            return names
+                .iter()
+                .map(|name| {
+                    let key = MapKey::String(name.lexeme.clone());
+                    return map.get(&key).cloned().ok_or(ParserError::MissingKey {
+                        key: key.to_string(),
+                        line: name.line,
+                        column: name.column,
+                        span: name.span(),
+                    });
+                })
+                .collect();
+        }
+        (kind, other) => {
+            return Err(ParserError::DestructureType {
+                kind: kind.name(),
+                type_name: other.type_name(),
+                line: open.line,
+                column: open.column,
+                span: open.span(),
+            })
+        }
+    };
+    if elements.len() != names.len() {
+        return Err(ParserError::DestructureArity {
+            kind: kind.name(),
+            expected: names.len(),
+            len: elements.len(),
+            line: open.line,
+            column: open.column,
+            span: open.span(),
+        });
+    }
+    return Ok(elements);
+}
+
+///pending work of `Interpreter::evaluate`
+enum Task<'a> {
+    ///pushes the value of the expression
+    Eval(&'a Expression),
+    ///combines the values of the expression's operands, the given number
+    ///of values on top of the stack, keys and values alternate for maps
+    Finish(&'a Expression, usize),
+    ///evaluates the right side of `??` when the left side is nil
+    Coalesce(&'a Expression),
+    ///picks a match arm for the subject on top of the stack
+    Match(&'a [MatchArm]),
+    ///drops the scope of the binding arm whose body just finished
+    EndArm,
+    ///rejects a map key as soon as it's evaluated, before its value
+    CheckKey(&'a Token),
+}
+
+///how many chars have to be inserted, deleted, replaced or swapped with
+///their neighbour to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<char>>();
+    let b = b.chars().collect::<Vec<char>>();
+    // rows[i][j] is the distance between the first i chars of `a` and the
+    // first j of `b`
+    let mut rows = vec![(0..=b.len()).collect::<Vec<usize>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let replace = rows[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            row[j] = replace.min(rows[i - 1][j] + 1).min(row[j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    return rows[a.len()][b.len()];
+}
+
+///the subexpressions evaluated before `expr` itself, in source order
+fn operands(expr: &Expression) -> Vec<&Expression> {
+    return match expr {
+        Expression::Binary { left, right, .. } => vec![left, right],
+        Expression::Unary { right, .. } => vec![right],
+        Expression::Assign { value, .. } => vec![value],
+        Expression::ListLiteral { elements, .. } | Expression::TupleLiteral { elements, .. } => {
+            elements.iter().collect()
+        }
+        Expression::Index { object, index, .. } => vec![object, index],
+        Expression::IndexAssign {
+            object,
+            index,
+            value,
+            ..
+        } => vec![object, index, value],
+        _ => vec![],
+    };
 }
 
 #[derive(Debug, Clone)]
@@ -15,75 +361,1097 @@ pub struct Variable {
 }
 
 impl Interpreter {
-    fn execute(&mut self, statement: Statement) -> Result<(), LoxError> {
+    fn execute(&mut self, statement: &Statement) -> Result<(), LoxError> {
+        self.burn()?;
         match statement {
-            Statement::Expression(ex) => {
-                if let Err(e) = ex.evaluate() {
-                    eprintln!("{}", e.to_string());
+            Statement::Expression(ex, _) => {
+                if let Err(e) = self.evaluate(ex) {
+                    self.recover(statement, e)?;
                 }
             }
-            Statement::Print(ex) => {
-                match ex {
-                    Expression::Grouping(expr) => {
-                        if let Ok(lit) = expr.evaluate() {
-                            println!("{}", lit);
-                        }
+            Statement::Print(ex, _) => match self.evaluate(ex) {
+                Ok(lit) => self.print(lit.to_string()),
+                Err(e) => self.recover(statement, e)?,
+            },
+            Statement::Var(name, initializer, _) => {
+                let value = match initializer {
+                    Some(val) => match self.evaluate(val) {
+                        Ok(lit) => lit,
+                        Err(e) => return Err(LoxError::ParseError(e)),
+                    },
+                    None => TokenLiteral::Empty,
+                };
+                self.define(name.clone(), value);
+                self.charge(std::mem::size_of::<Variable>() + name.lexeme.len(), None)?;
+            }
+            Statement::Destructure {
+                kind,
+                open,
+                names,
+                initializer,
+                ..
+            } => {
+                let values = self
+                    .evaluate(initializer)
+                    .and_then(|value| destructure(*kind, open, names, value))
+                    .map_err(LoxError::ParseError)?;
+                for (name, value) in names.iter().zip(values) {
+                    self.define(name.clone(), value);
+                    self.charge(std::mem::size_of::<Variable>() + name.lexeme.len(), None)?;
+                }
+            }
+            Statement::Dump(_) => {
+                let dump = format!("{:#?}", self);
+                self.print(dump);
+            }
+            Statement::Assert {
+                keyword,
+                condition,
+                source,
+                message,
+                ..
+            } => {
+                if let Err(e) = self.check_assertion(keyword, condition, source, message.as_ref()) {
+                    self.recover(statement, e)?;
+                }
+            }
+            Statement::ForIn {
+                name,
+                iterable,
+                body,
+                ..
+            } => {
+                let items = match self.evaluate(iterable) {
+                    Ok(TokenLiteral::List(list)) => list.borrow().clone(),
+                    Ok(TokenLiteral::Map(map)) => map
+                        .borrow()
+                        .iter()
+                        .map(|(key, _)| key.to_literal())
+                        .collect(),
+                    Ok(other) => {
+                        return Err(LoxError::ParseError(ParserError::NotIterable {
+                            type_name: other.type_name(),
+                            line: name.line,
+                            column: name.column,
+                            span: name.span(),
+                        }))
                     }
-                    Expression::Literal(lit) => {
-                        if let Some(var) = self.variables.iter().find(|v| v.name.literal == lit) {
-                            println!("{}", var.value);
-                        }
+                    Err(e) => return Err(LoxError::ParseError(e)),
+                };
+                // the first failing iteration ends the loop
+                let had_error = std::mem::replace(&mut self.had_error, false);
+                for item in items {
+                    self.scopes.push(vec![Variable {
+                        name: name.clone(),
+                        value: item,
+                    }]);
+                    let result = self.execute(body);
+                    self.scopes.pop();
+                    match result {
+                        Err(LoxError::ParseError(ParserError::FuelExhausted)) => return result,
+                        Err(e) => self.report_error(body, e.code(), e.to_string(), e.location()),
+                        Ok(()) => {}
                     }
-                    x => {
-                        if let Ok(lit) = x.evaluate() {
-                            println!("{}", lit);
-                        }
-                        // dbg!(&x.evaluate());
+                    if self.had_error {
+                        break;
                     }
                 }
-                //else {
-                //     match ex.evaluate() {
-                //         Err(e) => return Err(LoxError::ParseError(e)),
-                //         Ok(lit) => {
-                //             println!("{}", lit.to_string());
-                //             return Ok(());
-                //         }
-                //     }
-                // }
-            }
-            Statement::Var(name, initializer) => {
-                if let Some(val) = initializer {
-                    if let Ok(lit) = val.evaluate() {
-                        self.variables.push(Variable { name, value: lit });
+                self.had_error |= had_error;
+            }
+        }
+        return Ok(());
+    }
+
+    fn check_assertion(
+        &mut self,
+        keyword: &Token,
+        condition: &Expression,
+        source: &str,
+        message: Option<&Expression>,
+    ) -> Result<(), ParserError> {
+        if self.evaluate(condition)?.is_truthy(self.truthiness) {
+            return Ok(());
+        }
+        let message = match message {
+            Some(message) => Some(self.evaluate(message)?.to_string()),
+            None => None,
+        };
+        return Err(ParserError::AssertionFailed {
+            expression: source.to_string(),
+            message,
+            line: keyword.line,
+            column: keyword.column,
+            span: keyword.span(),
+        });
+    }
+
+    pub fn interpret(&mut self, statements: Vec<Statement>) {
+        self.interpret_statements(&statements);
+    }
+
+    pub fn interpret_statements(&mut self, statements: &[Statement]) {
+        for (i, statement) in statements.iter().enumerate() {
+            if !self.run_top_level(i, statement) {
+                break;
+            }
+        }
+    }
+
+    ///runs statements as they're parsed, see `Parser::parse_iter`, after a
+    ///syntax error nothing more runs but the rest is still parsed so every
+    ///error gets reported
+    pub fn interpret_stream(
+        &mut self,
+        statements: impl Iterator<Item = Result<Statement, LoxError>>,
+    ) {
+        let mut running = true;
+        for (i, statement) in statements.enumerate() {
+            match statement {
+                Ok(statement) if running => {
+                    if !self.run_top_level(i, &statement) {
+                        return;
                     }
                 }
+                Ok(_) => {}
+                // already reported by the parser
+                Err(_) => running = false,
+            }
+        }
+    }
+
+    ///runs the `i`th statement of a script, false when running out of fuel
+    ///stopped the script
+    fn run_top_level(&mut self, i: usize, statement: &Statement) -> bool {
+        crash::at_statement(i, statement.line());
+        if let Err(e) = self.execute(statement) {
+            let stop = matches!(e, LoxError::ParseError(ParserError::FuelExhausted));
+            self.report_error(statement, e.code(), e.to_string(), e.location());
+            return !stop;
+        }
+        return true;
+    }
+
+    ///reports an error and carries on with the next statement, running
+    ///out of fuel stops the whole script instead
+    fn recover(&mut self, statement: &Statement, e: ParserError) -> Result<(), LoxError> {
+        if e == ParserError::FuelExhausted {
+            return Err(LoxError::ParseError(e));
+        }
+        self.report_error(statement, e.code(), e.to_string(), e.location());
+        return Ok(());
+    }
+
+    ///uses up one step of fuel
+    fn burn(&mut self) -> Result<(), ParserError> {
+        return match &mut self.fuel {
+            Some(0) => Err(ParserError::FuelExhausted),
+            Some(fuel) => {
+                *fuel -= 1;
+                Ok(())
             }
-            Statement::Dump => {
-                dbg!(self);
+            None => Ok(()),
+        };
+    }
+
+    fn print(&mut self, text: String) {
+        match &mut self.output {
+            Output::Stdio => println!("{}", text),
+            // a writer that fails doesn't fail the script
+            Output::Writer(writer) => {
+                let _ = writer.write_line(&text);
+            }
+            Output::Captured(events) => events.push(OutputEvent::Print(text)),
+        }
+    }
+
+    ///reports `error` at the token that raised it, or at all of
+    ///`statement` when it isn't tied to one
+    fn report_error(
+        &mut self,
+        statement: &Statement,
+        code: Option<&'static str>,
+        message: String,
+        location: Option<(usize, usize, Range<usize>)>,
+    ) {
+        self.had_error = true;
+        let line = location.as_ref().map(|(line, _, _)| *line);
+        match &mut self.output {
+            Output::Stdio | Output::Writer(_) => {
+                let (column, span) = match location {
+                    Some((_, column, span)) => (column, span),
+                    None => (0, statement.span()),
+                };
+                let mut rendered = paint(Style::Error, &message);
+                if let Some(source) = &self.source {
+                    rendered.push('\n');
+                    rendered.push_str(&snippet::render(source, span.clone(), ""));
+                }
+                let diagnostic = Diagnostic {
+                    line: line.or(statement.line()).unwrap_or_default(),
+                    column,
+                    span,
+                    code,
+                    message,
+                };
+                self.sink.report(Severity::Error, &diagnostic, &rendered);
+            }
+            Output::Captured(events) => events.push(OutputEvent::Error {
+                line: line.or(statement.line()),
+                code,
+                message,
+            }),
+        }
+    }
+
+    ///drains captured output, always empty unless it's `Output::Captured`
+    pub fn take_output(&mut self) -> Vec<OutputEvent> {
+        return match &mut self.output {
+            Output::Stdio | Output::Writer(_) => vec![],
+            Output::Captured(events) => std::mem::take(events),
+        };
+    }
+
+    ///evaluates `expr` with a stack of its own instead of recursing, how
+    ///deeply expressions nest isn't limited by the host stack
+    pub fn evaluate(&mut self, expr: &Expression) -> Result<TokenLiteral, ParserError> {
+        let scopes = self.scopes.len();
+        let mut tasks = vec![Task::Eval(expr)];
+        let mut values = vec![];
+        while let Some(task) = tasks.pop() {
+            if let Err(e) = self.step(task, &mut tasks, &mut values) {
+                // match arms that were cut short leave their scopes behind
+                self.scopes.truncate(scopes);
+                return Err(e);
+            }
+        }
+        return Ok(values.pop().unwrap_or(TokenLiteral::Empty));
+    }
+
+    ///runs one task, operands are evaluated in source order onto `values`
+    ///before the task that combines them
+    fn step<'a>(
+        &mut self,
+        task: Task<'a>,
+        tasks: &mut Vec<Task<'a>>,
+        values: &mut Vec<TokenLiteral>,
+    ) -> Result<(), ParserError> {
+        let expr = match task {
+            Task::Eval(expr) => {
+                self.burn()?;
+                expr
+            }
+            Task::Finish(expr, count) => {
+                let operands = values.split_off(values.len() - count);
+                let value = self.finish(expr, operands)?;
+                values.push(value);
+                return Ok(());
+            }
+            // `??` is the only logical operator
+            Task::Coalesce(right) => {
+                let left = values.pop().unwrap_or(TokenLiteral::Empty);
+                if left == TokenLiteral::Empty {
+                    tasks.push(Task::Eval(right));
+                } else {
+                    values.push(left);
+                }
+                return Ok(());
+            }
+            Task::Match(arms) => {
+                let value = values.pop().unwrap_or(TokenLiteral::Empty);
+                self.choose_arm(arms, value, tasks, values);
+                return Ok(());
+            }
+            Task::EndArm => {
+                self.scopes.pop();
+                return Ok(());
+            }
+            Task::CheckKey(brace) => {
+                let key = values.last().cloned().unwrap_or(TokenLiteral::Empty);
+                self.map_key(key, brace)?;
+                return Ok(());
+            }
+        };
+        match expr {
+            Expression::Grouping(inner, _) => tasks.push(Task::Eval(inner)),
+            Expression::Literal(lit, _) => values.push(lit.clone()),
+            Expression::Empty => values.push(TokenLiteral::Empty),
+            Expression::Variable { name, local } => {
+                let value = match local {
+                    Some(local) => self.local(name, *local).map(|v| &v.value),
+                    None => self.lookup(name),
+                };
+                match value {
+                    Some(value) => values.push(value.clone()),
+                    None => return Err(self.undefined(name)),
+                }
+            }
+            Expression::Logical { left, right, .. } => {
+                tasks.push(Task::Coalesce(right));
+                tasks.push(Task::Eval(left));
+            }
+            Expression::Match { subject, arms, .. } => {
+                tasks.push(Task::Match(arms));
+                tasks.push(Task::Eval(subject));
+            }
+            // a key given twice keeps its first position and its last value
+            Expression::MapLiteral { brace, entries, .. } => {
+                tasks.push(Task::Finish(expr, entries.len() * 2));
+                for (key, value) in entries.iter().rev() {
+                    tasks.push(Task::Eval(value));
+                    tasks.push(Task::CheckKey(brace));
+                    tasks.push(Task::Eval(key));
+                }
+            }
+            _ => {
+                let operands = operands(expr);
+                tasks.push(Task::Finish(expr, operands.len()));
+                for operand in operands.into_iter().rev() {
+                    tasks.push(Task::Eval(operand));
+                }
             }
         }
         return Ok(());
     }
 
-    pub fn interpret(&mut self, statements: Vec<Statement>) {
-        for statement in statements {
-            if let Err(e) = self.execute(statement) {
-                eprintln!("{}", e.to_string());
+    ///the value of `expr` from the values of its operands
+    fn finish(
+        &mut self,
+        expr: &Expression,
+        operands: Vec<TokenLiteral>,
+    ) -> Result<TokenLiteral, ParserError> {
+        let mut operands = operands.into_iter();
+        let mut operand = || operands.next().unwrap_or(TokenLiteral::Empty);
+        return match expr {
+            Expression::Binary { operator, .. } => {
+                let left = operand();
+                self.binary(operator, left, operand())
+            }
+            Expression::Unary { operator, .. } => self.unary(operator, operand()),
+            Expression::Assign { name, local, .. } => {
+                let value = operand();
+                match local.and_then(|local| self.local(name, local)) {
+                    Some(variable) => variable.value = value.clone(),
+                    None => self.assign(name, value.clone())?,
+                }
+                Ok(value)
+            }
+            Expression::ListLiteral { .. } => {
+                let list = TokenLiteral::list(operands.collect());
+                self.check_limits(&list)?;
+                Ok(list)
+            }
+            Expression::TupleLiteral { .. } => {
+                let tuple = TokenLiteral::Tuple(operands.collect::<Vec<TokenLiteral>>().into());
+                self.check_limits(&tuple)?;
+                Ok(tuple)
+            }
+            Expression::MapLiteral { brace, .. } => {
+                let mut map = MapEntries::default();
+                while let (Some(key), Some(value)) = (operands.next(), operands.next()) {
+                    map.insert(self.map_key(key, brace)?, value);
+                }
+                let map = TokenLiteral::Map(SharedMap::new(map));
+                self.check_limits(&map)?;
+                Ok(map)
+            }
+            Expression::Index { bracket, .. } => {
+                let object = operand();
+                let index = operand();
+                if let TokenLiteral::Map(map) = object {
+                    let key = self.map_key(index, bracket)?;
+                    return match map.borrow().get(&key) {
+                        Some(value) => Ok(value.clone()),
+                        None => Err(ParserError::MissingKey {
+                            key: key.to_string(),
+                            line: bracket.line,
+                            column: bracket.column,
+                            span: bracket.span(),
+                        }),
+                    };
+                }
+                let (list, i) = self.list_slot(object, index, bracket)?;
+                let value = list.borrow()[i].clone();
+                Ok(value)
+            }
+            Expression::IndexAssign { bracket, .. } => {
+                let object = operand();
+                let index = operand();
+                let value = operand();
+                if let TokenLiteral::Map(map) = object {
+                    let key = self.map_key(index, bracket)?;
+                    let mut entries = map.borrow_mut();
+                    if entries.get(&key).is_none() {
+                        self.check_elements(entries.len() + 1)?;
+                    }
+                    entries.insert(key, value.clone());
+                    return Ok(value);
+                }
+                let (list, i) = self.list_slot(object, index, bracket)?;
+                list.borrow_mut()[i] = value.clone();
+                Ok(value)
+            }
+            _ => Err(ParserError::UnsupportedAction),
+        };
+    }
+
+    ///queues the body of the first arm matching `value`, nil when none does
+    fn choose_arm<'a>(
+        &mut self,
+        arms: &'a [MatchArm],
+        value: TokenLiteral,
+        tasks: &mut Vec<Task<'a>>,
+        values: &mut Vec<TokenLiteral>,
+    ) {
+        for arm in arms {
+            let matched = match &arm.pattern {
+                Pattern::Literal(TokenLiteral::Empty) => value == TokenLiteral::Empty,
+                Pattern::Literal(lit) => value.is_equal(lit.clone()),
+                Pattern::Type(literal_type) => literal_type.matches(&value),
+                Pattern::Binding(_) | Pattern::Wildcard => true,
+            };
+            if !matched {
+                continue;
+            }
+            if let Pattern::Binding(name) = &arm.pattern {
+                self.scopes.push(vec![Variable {
+                    name: name.clone(),
+                    value,
+                }]);
+                tasks.push(Task::EndArm);
+            }
+            tasks.push(Task::Eval(&arm.body));
+            return;
+        }
+        values.push(TokenLiteral::Empty);
+    }
+
+    fn binary(
+        &mut self,
+        operator: &Token,
+        left: TokenLiteral,
+        right: TokenLiteral,
+    ) -> Result<TokenLiteral, ParserError> {
+        let operator_type = match self.mutant {
+            Some(mutant) => mutant.apply(operator.token_type),
+            None => operator.token_type,
+        };
+        match operator_type {
+            TokenType::MINUS => {
+                self.check_number_operand(operator, &right)?;
+                return (left - right).map_err(|e| e.at(operator));
+            }
+            TokenType::PLUS => {
+                if let (TokenLiteral::String(lhs), TokenLiteral::String(rhs)) = (&left, &right) {
+                    self.check_string_length(lhs.len().saturating_add(rhs.len()))?;
+                }
+                let result = (left + right).map_err(|e| e.at(operator))?;
+                self.check_limits(&result)?;
+                return Ok(result);
+            }
+            TokenType::SLASH => {
+                self.check_number_operand(operator, &right)?;
+                return (left / right).map_err(|e| e.at(operator));
+            }
+            TokenType::STAR => {
+                self.check_number_operand(operator, &right)?;
+                return (left * right).map_err(|e| e.at(operator));
+            }
+            TokenType::EXPONENT => {
+                self.check_number_operand(operator, &right)?;
+                return left.pow(right).map_err(|e| e.at(operator));
+            }
+            TokenType::GREATER => {
+                let ordering = self.compare(operator, &left, &right)?;
+                return Ok(TokenLiteral::Boolean(
+                    ordering.is_some_and(|ordering| ordering.is_gt()),
+                ));
+            }
+            TokenType::GREATER_EQUAL => {
+                let ordering = self.compare(operator, &left, &right)?;
+                return Ok(TokenLiteral::Boolean(
+                    ordering.is_some_and(|ordering| ordering.is_ge()),
+                ));
+            }
+            TokenType::LESS => {
+                let ordering = self.compare(operator, &left, &right)?;
+                return Ok(TokenLiteral::Boolean(
+                    ordering.is_some_and(|ordering| ordering.is_lt()),
+                ));
+            }
+            TokenType::LESS_EQUAL => {
+                let ordering = self.compare(operator, &left, &right)?;
+                return Ok(TokenLiteral::Boolean(
+                    ordering.is_some_and(|ordering| ordering.is_le()),
+                ));
+            }
+            TokenType::BANG_EQUAL => {
+                return Ok(TokenLiteral::Boolean(!left.is_equal(right)));
+            }
+            TokenType::EQUAL_EQUAL => {
+                return Ok(TokenLiteral::Boolean(left.is_equal(right)));
+            }
+            _ => Err(ParserError::UnsupportedAction),
+        }
+    }
+
+    fn unary(&self, operator: &Token, right: TokenLiteral) -> Result<TokenLiteral, ParserError> {
+        match operator.token_type {
+            TokenType::MINUS => {
+                self.check_number_operand(operator, &right)?;
+                return match right {
+                    // -isize::MIN overflows like any subtraction
+                    TokenLiteral::Integer(_) => {
+                        (TokenLiteral::Integer(0) - right).map_err(|e| e.at(operator))
+                    }
+                    #[cfg(feature = "bigint")]
+                    TokenLiteral::BigInt(n) => Ok(bigint::normalize(-n)),
+                    TokenLiteral::Float(n) => Ok(TokenLiteral::Float(-n)),
+                    _ => Err(ParserError::UnsupportedAction),
+                };
+            }
+            TokenType::BANG => {
+                return Ok(TokenLiteral::Boolean(!right.is_truthy(self.truthiness)));
+            }
+            _ => Err(ParserError::UnsupportedAction),
+        }
+    }
+
+    fn map_key(&self, key: TokenLiteral, token: &Token) -> Result<MapKey, ParserError> {
+        return MapKey::from_literal(&key).ok_or(ParserError::UnhashableKey {
+            type_name: key.type_name(),
+            line: token.line,
+            column: token.column,
+            span: token.span(),
+        });
+    }
+
+    ///the list and bounds checked position `object[index]` refers to
+    fn list_slot(
+        &self,
+        object: TokenLiteral,
+        index: TokenLiteral,
+        bracket: &Token,
+    ) -> Result<(SharedList, usize), ParserError> {
+        let list = match object {
+            TokenLiteral::List(list) => list,
+            other => {
+                return Err(ParserError::NotIndexable {
+                    type_name: other.type_name(),
+                    line: bracket.line,
+                    column: bracket.column,
+                    span: bracket.span(),
+                })
+            }
+        };
+        let index = match index {
+            TokenLiteral::Integer(index) => index,
+            other => {
+                return Err(ParserError::IndexType {
+                    type_name: other.type_name(),
+                    line: bracket.line,
+                    column: bracket.column,
+                    span: bracket.span(),
+                })
+            }
+        };
+        let len = list.borrow().len();
+        if index < 0 || index as usize >= len {
+            return Err(ParserError::IndexOutOfBounds {
+                index,
+                len,
+                line: bracket.line,
+                column: bracket.column,
+                span: bracket.span(),
+            });
+        }
+        return Ok((list, index as usize));
+    }
+
+    fn check_number_operand(
+        &self,
+        operator: &Token,
+        operand: &TokenLiteral,
+    ) -> Result<(), ParserError> {
+        if operand.is_number() {
+            return Ok(());
+        }
+        let args: &[(&str, &dyn Display)] = &[
+            ("operator", &operator.lexeme),
+            ("type", &operand.type_name()),
+        ];
+        return Err(ParserError::runtime(
+            operator,
+            messages::code("operand-not-number"),
+            message("operand-not-number", args),
+        ));
+    }
+
+    ///orders two numbers, `None` when either is NaN
+    fn compare(
+        &self,
+        operator: &Token,
+        left: &TokenLiteral,
+        right: &TokenLiteral,
+    ) -> Result<Option<Ordering>, ParserError> {
+        #[cfg(feature = "bigint")]
+        if let Some(ordering) = bigint::compare(left, right) {
+            return Ok(ordering);
+        }
+        return match (left, right) {
+            (TokenLiteral::Integer(left), TokenLiteral::Integer(right)) => {
+                Ok(Some(left.cmp(right)))
+            }
+            (TokenLiteral::Integer(left), TokenLiteral::Float(right)) => {
+                Ok((*left as f64).partial_cmp(right))
+            }
+            (TokenLiteral::Float(left), TokenLiteral::Integer(right)) => {
+                Ok(left.partial_cmp(&(*right as f64)))
+            }
+            (TokenLiteral::Float(left), TokenLiteral::Float(right)) => Ok(left.partial_cmp(right)),
+            _ => Err(ParserError::runtime(
+                operator,
+                messages::code("type-mismatch"),
+                message(
+                    "type-mismatch",
+                    &[
+                        ("operator", &operator.lexeme),
+                        ("left", &left.type_name()),
+                        ("right", &right.type_name()),
+                    ],
+                ),
+            )),
+        };
+    }
+
+    fn check_limits(&mut self, value: &TokenLiteral) -> Result<(), ParserError> {
+        if let TokenLiteral::String(s) = value {
+            self.check_string_length(s.len())?;
+        }
+        let elements = match value {
+            TokenLiteral::List(list) => list.borrow().len(),
+            TokenLiteral::Map(map) => map.borrow().len(),
+            TokenLiteral::Tuple(elements) => elements.len(),
+            _ => 0,
+        };
+        self.check_elements(elements)?;
+        return self.charge(shallow_size(value), Some(value));
+    }
+
+    ///a concatenation checks the length it would have before building it
+    fn check_string_length(&self, len: usize) -> Result<(), ParserError> {
+        if let Some(max) = self.limits.max_string_length {
+            if len > max {
+                return Err(ParserError::LimitExceeded {
+                    limit: "string length",
+                    max,
+                });
+            }
+        }
+        return Ok(());
+    }
+
+    fn check_elements(&self, elements: usize) -> Result<(), ParserError> {
+        if let Some(max) = self.limits.max_elements {
+            if elements > max {
+                return Err(ParserError::LimitExceeded {
+                    limit: "element count",
+                    max,
+                });
+            }
+        }
+        return Ok(());
+    }
+
+    ///approximate bytes held by variables and the values they reach
+    pub fn memory_usage(&self) -> usize {
+        return self.held_with(None);
+    }
+
+    fn held_with(&self, fresh: Option<&TokenLiteral>) -> usize {
+        let variables = self.variables.iter().chain(self.scopes.iter().flatten());
+        let names = variables
+            .clone()
+            .map(|v| std::mem::size_of::<Variable>() + v.name.lexeme.len())
+            .sum::<usize>();
+        return names + held_size(variables.map(|v| &v.value).chain(fresh));
+    }
+
+    ///counts `bytes` towards the memory limit, past it the memory in use
+    ///is measured again, `fresh` is a value that isn't held by a variable
+    ///yet
+    fn charge(&mut self, bytes: usize, fresh: Option<&TokenLiteral>) -> Result<(), ParserError> {
+        let Some(max) = self.limits.max_memory else {
+            return Ok(());
+        };
+        self.allocated += bytes;
+        if self.allocated <= max {
+            return Ok(());
+        }
+        self.allocated = self.held_with(fresh);
+        if self.allocated > max {
+            return Err(ParserError::LimitExceeded {
+                limit: "memory",
+                max,
+            });
+        }
+        return Ok(());
+    }
+
+    ///defines a variable in the innermost scope
+    pub fn define(&mut self, name: Token, value: TokenLiteral) {
+        let variable = Variable { name, value };
+        match self.scopes.last_mut() {
+            Some(scope) => scope.push(variable),
+            None => self.declare_global(variable),
+        }
+    }
+
+    ///adds a global declaration, hiding any earlier one of the same name
+    fn declare_global(&mut self, variable: Variable) {
+        self.global_slots
+            .insert(variable.name.lexeme.clone(), self.variables.len());
+        self.variables.push(variable);
+    }
+
+    fn global(&self, name: &str) -> Option<&Variable> {
+        return self.global_slots.get(name).map(|i| &self.variables[*i]);
+    }
+
+    ///points `global_slots` at the latest declarations again after
+    ///`variables` lost some
+    fn index_globals(&mut self) {
+        self.global_slots = self
+            .variables
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (v.name.lexeme.clone(), i))
+            .collect();
+    }
+
+    ///assigns to the innermost existing variable named `name`, falling back
+    ///to the interpreter's assign policy when there is none
+    pub fn assign(&mut self, name: &Token, value: TokenLiteral) -> Result<(), ParserError> {
+        let scoped = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .flat_map(|scope| scope.iter_mut().rev())
+            .find(|v| v.name.lexeme == name.lexeme);
+        let existing = match scoped {
+            Some(variable) => Some(variable),
+            None => match self.global_slots.get(&name.lexeme) {
+                Some(i) => Some(&mut self.variables[*i]),
+                None => None,
+            },
+        };
+        if let Some(variable) = existing {
+            variable.value = value;
+            return Ok(());
+        }
+
+        match self.assign_policy {
+            AssignPolicy::Strict => Err(self.undefined(name)),
+            AssignPolicy::ImplicitGlobal => {
+                self.declare_global(Variable {
+                    name: name.clone(),
+                    value,
+                });
+                Ok(())
+            }
+        }
+    }
+
+    ///the variable where the resolver found `name` bound, `None` when the
+    ///scopes at runtime don't have it there
+    fn local(&mut self, name: &Token, local: Local) -> Option<&mut Variable> {
+        let index = self.scopes.len().checked_sub(local.depth + 1)?;
+        return self.scopes[index]
+            .get_mut(local.slot)
+            .filter(|v| v.name.lexeme == name.lexeme);
+    }
+
+    ///a name the resolver didn't find bound, the scopes an embedder pushed
+    ///come before the globals
+    fn lookup(&self, name: &Token) -> Option<&TokenLiteral> {
+        return self
+            .scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|v| v.name.lexeme == name.lexeme)
+            .or_else(|| self.global(&name.lexeme))
+            .map(|v| &v.value);
+    }
+
+    ///the error for using `name` when nothing defines it, suggesting the
+    ///defined name with the fewest edits away from it
+    fn undefined(&self, name: &Token) -> ParserError {
+        // a typo changes about one char in three, and a name with every
+        // char changed isn't a typo
+        let len = name.lexeme.chars().count();
+        let max_distance = (len.max(3) / 3).min(len.saturating_sub(1));
+        let mut suggestion: Option<(usize, &str)> = None;
+        let names = self
+            .scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .chain(self.variables.iter().rev())
+            .map(|v| v.name.lexeme.as_str());
+        for candidate in names {
+            let distance = edit_distance(&name.lexeme, candidate);
+            if distance > 0
+                && distance <= max_distance
+                && suggestion.is_none_or(|(best, _)| distance < best)
+            {
+                suggestion = Some((distance, candidate));
+            }
+        }
+        return ParserError::UndefinedVariable {
+            name: name.lexeme.clone(),
+            suggestion: suggestion.map(|(_, candidate)| candidate.to_string()),
+            line: name.line,
+            column: name.column,
+            span: name.span(),
+        };
+    }
+
+    ///runs `source` against the current state and returns the value of its
+    ///last statement when that's an expression, nil otherwise, syntax and
+    ///runtime errors are returned instead of reported, prints still go to
+    ///`output` once the whole source ran
+    pub fn eval_str(&mut self, source: &str) -> Result<TokenLiteral, Vec<Diagnostic>> {
+        let mut statements = Parser::new().parse_str(source)?;
+        let last = match statements.last() {
+            Some(Statement::Expression(..)) => statements.pop(),
+            _ => None,
+        };
+        let output = std::mem::replace(&mut self.output, Output::Captured(vec![]));
+        let had_error = self.had_error;
+        self.interpret_statements(&statements);
+        let mut value = TokenLiteral::Empty;
+        let mut events = self.take_output();
+        let ran = events
+            .iter()
+            .all(|event| matches!(event, OutputEvent::Print(_)));
+        if let (true, Some(Statement::Expression(expr, _))) = (ran, &last) {
+            match self.burn().and_then(|_| self.evaluate(expr)) {
+                Ok(result) => value = result,
+                Err(e) => events.push(OutputEvent::Error {
+                    line: last.as_ref().and_then(Statement::line),
+                    code: e.code(),
+                    message: LoxError::ParseError(e).to_string(),
+                }),
             }
         }
+        self.output = output;
+        self.had_error = had_error;
+
+        let mut errors = vec![];
+        for event in events {
+            match event {
+                OutputEvent::Print(text) => self.print(text),
+                OutputEvent::Error {
+                    line,
+                    code,
+                    message,
+                } => errors.push(Diagnostic {
+                    line: line.unwrap_or_default(),
+                    column: 0,
+                    span: 0..0,
+                    code,
+                    message,
+                }),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        return Ok(value);
+    }
+
+    ///parses and evaluates a single expression against the current state
+    pub fn eval_expr_str(&mut self, source: &str) -> Result<TokenLiteral, LoxError> {
+        let expr = Parser::new().load_expression(source.to_string())?;
+        return Ok(self.evaluate(&expr)?);
+    }
+
+    ///sets a global variable, replacing any existing global of that name
+    pub fn set_global(&mut self, name: &str, value: impl Into<TokenLiteral>) {
+        let value = value.into();
+        match self.global_slots.get(name) {
+            Some(i) => self.variables[*i].value = value,
+            None => self.declare_global(Variable {
+                name: Token::identifier(name, 0),
+                value,
+            }),
+        }
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<TokenLiteral> {
+        return self.global(name).map(|v| v.value.clone());
+    }
+
+    ///every global name once, in the order they were first declared, with
+    ///its latest value
+    pub fn globals(&self) -> Vec<(String, TokenLiteral)> {
+        let mut globals: Vec<(String, TokenLiteral)> = vec![];
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        for variable in &self.variables {
+            match seen.get(variable.name.lexeme.as_str()) {
+                Some(i) => globals[*i].1 = variable.value.clone(),
+                None => {
+                    seen.insert(&variable.name.lexeme, globals.len());
+                    globals.push((variable.name.lexeme.clone(), variable.value.clone()));
+                }
+            }
+        }
+        return globals;
+    }
+
+    ///removes the global `name`, every declaration of it, and returns its
+    ///value
+    pub fn remove(&mut self, name: &str) -> Option<TokenLiteral> {
+        let value = self.get_global(name);
+        self.variables.retain(|v| v.name.lexeme != name);
+        self.index_globals();
+        return value;
+    }
+
+    pub fn clear_globals(&mut self) {
+        self.variables.clear();
+        self.global_slots.clear();
+    }
+
+    ///forgets every variable and error, the limits, policies, fuel, input
+    ///and output stay as they are
+    pub fn reset(&mut self) {
+        self.variables.clear();
+        self.global_slots.clear();
+        self.scopes.clear();
+        self.had_error = false;
+        self.allocated = 0;
+    }
+
+    ///runs `f` with a temporary scope pushed, the scope and everything
+    ///defined in it is dropped when `f` returns
+    pub fn with_scope<R>(&mut self, f: impl FnOnce(&mut Scope) -> R) -> R {
+        let mut scope = Scope::new(self);
+        return f(&mut scope);
     }
 
     pub fn stringify(&self, literal: TokenLiteral) -> String {
         match literal {
             TokenLiteral::Empty => String::new(),
             TokenLiteral::Integer(i) => i.to_string(),
+            #[cfg(feature = "bigint")]
+            TokenLiteral::BigInt(n) => n.to_string(),
             TokenLiteral::Float(f) => f.to_string(),
             TokenLiteral::String(s) => s,
             TokenLiteral::Boolean(b) => b.to_string(),
+            value @ (TokenLiteral::List(_) | TokenLiteral::Map(_) | TokenLiteral::Tuple(_)) => {
+                value.to_string()
+            }
         }
     }
 
     pub fn new() -> Interpreter {
-        Interpreter { variables: vec![] }
+        Interpreter {
+            variables: vec![],
+            global_slots: HashMap::new(),
+            scopes: vec![],
+            limits: Limits::default(),
+            assign_policy: AssignPolicy::default(),
+            truthiness: Truthiness::default(),
+            fuel: None,
+            allocated: 0,
+            had_error: false,
+            mutant: None,
+            output: Output::default(),
+            input: Input::default(),
+            source: None,
+            sink: SharedSink::default(),
+        }
+    }
+
+    pub fn with_assign_policy(mut self, assign_policy: AssignPolicy) -> Interpreter {
+        self.assign_policy = assign_policy;
+        return self;
+    }
+
+    pub fn with_truthiness(mut self, truthiness: Truthiness) -> Interpreter {
+        self.truthiness = truthiness;
+        return self;
+    }
+
+    ///stops the script with `FuelExhausted` after `fuel` statements and
+    ///expressions
+    pub fn with_fuel(mut self, fuel: usize) -> Interpreter {
+        self.fuel = Some(fuel);
+        return self;
+    }
+
+    ///sends prints and dumps to `writer` instead of stdout
+    pub fn with_writer(mut self, writer: impl Write + Send + 'static) -> Interpreter {
+        self.output = Output::Writer(SharedWriter::new(writer));
+        return self;
+    }
+
+    ///reads lines from `reader` instead of stdin
+    pub fn with_reader(mut self, reader: impl BufRead + Send + 'static) -> Interpreter {
+        self.input = Input::Reader(SharedReader::new(reader));
+        return self;
+    }
+
+    pub fn with_limits(mut self, limits: Limits) -> Interpreter {
+        self.limits = limits;
+        return self;
+    }
+}
+
+///a temporary scope on top of an interpreter's environment, popped on drop,
+///the interpreter itself isn't reachable through it so nothing can pop the
+///scope out from under it
+pub struct Scope<'a> {
+    interpreter: &'a mut Interpreter,
+    depth: usize,
+}
+
+impl<'a> Scope<'a> {
+    fn new(interpreter: &'a mut Interpreter) -> Scope<'a> {
+        let depth = interpreter.scopes.len();
+        interpreter.scopes.push(vec![]);
+        return Scope { interpreter, depth };
+    }
+
+    pub fn define(&mut self, name: &str, value: TokenLiteral) {
+        self.interpreter.scopes[self.depth].push(Variable {
+            name: Token::identifier(name, 0),
+            value,
+        });
+    }
+
+    ///evaluates a single expression against the scope's bindings
+    pub fn eval(&mut self, source: &str) -> Result<TokenLiteral, LoxError> {
+        return self.interpreter.eval_expr_str(source);
+    }
+
+    ///the value `name` has here, from this scope or the ones around it
+    pub fn get(&self, name: &str) -> Option<TokenLiteral> {
+        return self
+            .interpreter
+            .lookup(&Token::identifier(name, 0))
+            .cloned();
+    }
+
+    ///runs `f` with another scope pushed on top of this one
+    pub fn with_scope<R>(&mut self, f: impl FnOnce(&mut Scope) -> R) -> R {
+        return self.interpreter.with_scope(f);
+    }
+}
+
+impl Drop for Scope<'_> {
+    fn drop(&mut self) {
+        self.interpreter.scopes.truncate(self.depth);
     }
 }
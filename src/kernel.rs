@@ -0,0 +1,221 @@
+use std::io::{BufRead, Write};
+
+use serde_json::{json, Value};
+
+use crate::{
+    ast::{LoxError, Position},
+    config::LoxConfig,
+    repl::{Event, OutputMode, Repl},
+};
+
+pub const PROTOCOL_VERSION: &str = "5.3";
+
+///answers Jupyter style requests, one JSON message per line on stdin and
+///stdout, the messages carry `msg_type`, `msg_id`, `parent_id` and `content`
+///like Jupyter's but without the ZeroMQ framing and signatures
+pub struct Kernel {
+    repl: Repl,
+    execution_count: usize,
+    next_id: usize,
+}
+
+impl Kernel {
+    pub fn new(config: &LoxConfig) -> Kernel {
+        return Kernel {
+            repl: Repl::new(config, OutputMode::Json),
+            execution_count: 0,
+            next_id: 0,
+        };
+    }
+
+    ///handles one request, returning the messages to send back and whether
+    ///the kernel should shut down afterwards
+    pub fn handle(&mut self, request: &str) -> (Vec<Value>, bool) {
+        let request: Value = match serde_json::from_str(request) {
+            Ok(request) => request,
+            Err(e) => {
+                let reply = self.message(
+                    "error",
+                    None,
+                    error_content("ProtocolError", &e.to_string()),
+                );
+                return (vec![reply], false);
+            }
+        };
+        let parent = request.get("msg_id").cloned();
+        let msg_type = request["msg_type"].as_str().unwrap_or_default();
+
+        let mut replies = vec![self.status(&parent, "busy")];
+        let mut shutdown = false;
+        match msg_type {
+            "kernel_info_request" => {
+                let content = json!({
+                    "status": "ok",
+                    "protocol_version": PROTOCOL_VERSION,
+                    "implementation": "rlox",
+                    "implementation_version": env!("CARGO_PKG_VERSION"),
+                    "language_info": {
+                        "name": "lox",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "file_extension": ".lox",
+                        "mimetype": "text/x-lox",
+                    },
+                    "banner": "rlox, a Lox interpreter",
+                });
+                replies.push(self.message("kernel_info_reply", parent.clone(), content));
+            }
+            "execute_request" => {
+                let code = request["content"]["code"].as_str().unwrap_or_default();
+                replies.extend(self.execute(&parent, code));
+            }
+            "shutdown_request" => {
+                let restart = request["content"]["restart"].as_bool().unwrap_or(false);
+                let content = json!({ "status": "ok", "restart": restart });
+                replies.push(self.message("shutdown_reply", parent.clone(), content));
+                shutdown = true;
+            }
+            other => {
+                let content = error_content(
+                    "ProtocolError",
+                    &format!("Unsupported message type '{}'", other),
+                );
+                replies.push(self.message("error", parent.clone(), content));
+            }
+        }
+        replies.push(self.status(&parent, "idle"));
+        return (replies, shutdown);
+    }
+
+    ///runs a cell, whatever follows the last line ending in ';' is evaluated
+    ///as an expression and becomes the cell's result
+    fn execute(&mut self, parent: &Option<Value>, code: &str) -> Vec<Value> {
+        self.execution_count += 1;
+        let code = code.trim_end();
+        let mut split = 0;
+        let mut offset = 0;
+        for line in code.split_inclusive('\n') {
+            offset += line.len();
+            if line.trim_end().ends_with(';') {
+                split = offset;
+            }
+        }
+        let (statements, expression) = code.split_at(split);
+
+        let mut events = vec![];
+        let mut failed = false;
+        for input in [statements, expression] {
+            if input.trim().is_empty() {
+                continue;
+            }
+            let (mut produced, result) = self.repl.eval(format!("{}\n", input));
+            events.append(&mut produced);
+            failed |= result.is_err();
+        }
+
+        let mut replies = vec![];
+        for event in events {
+            match event {
+                Event::Print(text) => replies.push(self.message(
+                    "stream",
+                    parent.clone(),
+                    json!({ "name": "stdout", "text": format!("{}\n", text) }),
+                )),
+                Event::Result(value) => replies.push(self.message(
+                    "execute_result",
+                    parent.clone(),
+                    json!({
+                        "execution_count": self.execution_count,
+                        "data": { "text/plain": value.to_string() },
+                        "metadata": {},
+                    }),
+                )),
+                Event::Diagnostic {
+                    severity: "warning",
+                    line,
+                    column,
+                    message,
+                    ..
+                } => {
+                    let text = format!("{}\n", located(line, column, &message));
+                    replies.push(self.message(
+                        "stream",
+                        parent.clone(),
+                        json!({ "name": "stderr", "text": text }),
+                    ))
+                }
+                Event::Diagnostic {
+                    line,
+                    column,
+                    message,
+                    ..
+                } => {
+                    failed = true;
+                    let content = error_content("LoxError", &located(line, column, &message));
+                    replies.push(self.message("error", parent.clone(), content));
+                }
+            }
+        }
+        let status = if failed { "error" } else { "ok" };
+        replies.push(self.message(
+            "execute_reply",
+            parent.clone(),
+            json!({ "status": status, "execution_count": self.execution_count }),
+        ));
+        return replies;
+    }
+
+    fn status(&mut self, parent: &Option<Value>, state: &str) -> Value {
+        return self.message(
+            "status",
+            parent.clone(),
+            json!({ "execution_state": state }),
+        );
+    }
+
+    fn message(&mut self, msg_type: &str, parent: Option<Value>, content: Value) -> Value {
+        self.next_id += 1;
+        return json!({
+            "msg_type": msg_type,
+            "msg_id": format!("rlox-{}", self.next_id),
+            "parent_id": parent,
+            "content": content,
+        });
+    }
+}
+
+fn located(line: Option<usize>, column: Option<usize>, message: &str) -> String {
+    return match line {
+        Some(line) => format!("[{}] {}", Position(line, column.unwrap_or(0)), message),
+        None => message.to_string(),
+    };
+}
+
+fn error_content(name: &str, value: &str) -> Value {
+    return json!({
+        "status": "error",
+        "ename": name,
+        "evalue": value,
+        "traceback": [value],
+    });
+}
+
+///serves requests from stdin until a shutdown request or EOF
+pub fn run(config: &LoxConfig) -> Result<(), LoxError> {
+    let mut kernel = Kernel::new(config);
+    let mut stdout = std::io::stdout();
+    for line in std::io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (replies, shutdown) = kernel.handle(&line);
+        for reply in replies {
+            writeln!(stdout, "{}", reply)?;
+        }
+        stdout.flush()?;
+        if shutdown {
+            break;
+        }
+    }
+    return Ok(());
+}
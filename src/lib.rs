@@ -0,0 +1,67 @@
+//! A tree-walking interpreter for Lox, the language from Crafting
+//! Interpreters, with lists, maps, tuples, `match` and more on top.
+//!
+//! A script goes through the [`Scanner`], which turns the source into
+//! [`Token`]s, the [`Parser`], which builds [`Statement`]s and
+//! [`Expression`]s from them, and the [`Interpreter`], which runs them.
+//! Problems in the script are [`Diagnostic`]s, reported to a
+//! [`sink::DiagnosticSink`] as they're found, failures are [`LoxError`]s.
+//!
+//! ```
+//! use rlox::{interpreter::Output, Interpreter, Parser};
+//!
+//! let statements = Parser::new()
+//!     .parse_str("var a = 6 * 7; print a;")
+//!     .expect("no syntax errors");
+//! let mut interpreter = Interpreter::new();
+//! interpreter.output = Output::Captured(vec![]);
+//! interpreter.interpret(statements);
+//! assert_eq!(interpreter.get_global("a").unwrap().to_string(), "42");
+//! ```
+//!
+//! [`lox::Session`] keeps an interpreter around between inputs for REPLs
+//! and [`lox::Lox::compile`] parses a script once to run it many times.
+
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+#![allow(clippy::needless_return)]
+#![allow(clippy::should_implement_trait)]
+
+pub mod ast;
+pub mod ast_impl;
+#[cfg(feature = "bigint")]
+pub mod bigint;
+pub mod cli;
+pub mod color;
+pub mod config;
+pub mod crash;
+pub mod diff;
+pub mod fix;
+pub mod formatter;
+pub mod hash;
+pub mod interpreter;
+#[cfg(feature = "json")]
+pub mod kernel;
+pub mod lint;
+pub mod lox;
+pub mod messages;
+pub mod mutate;
+pub mod parser;
+pub mod repl;
+pub mod resolver;
+pub mod scanner;
+pub mod sink;
+#[cfg(feature = "json")]
+pub mod snapshot;
+pub mod snippet;
+pub mod stmt;
+#[cfg(test)]
+pub mod tests;
+pub mod xref;
+
+pub use ast::{Diagnostic, Expression, LoxError, ParserError, Token, TokenLiteral, TokenType};
+pub use interpreter::Interpreter;
+pub use lox::eval;
+pub use parser::Parser;
+pub use scanner::Scanner;
+pub use stmt::Statement;
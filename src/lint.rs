@@ -0,0 +1,215 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ast::{Expression, LoxError, ParserError, Pattern, Token},
+    config::{LoxConfig, WarningLevel},
+    messages::message,
+    stmt::Statement,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub line: usize,
+    pub message: String,
+}
+
+///parses `source` and collects lint findings sorted by line, parser
+///warnings are reported as findings too
+pub fn lint_source(source: &str, config: &LoxConfig) -> Result<Vec<Finding>, LoxError> {
+    let mut parser = config.parser();
+    parser.warning_level = WarningLevel::Allow;
+    let statements = parser.load(source.to_string());
+    if parser.had_error || parser.scanner.had_error {
+        return Err(LoxError::ParseError(ParserError::Generic(message(
+            "syntax-errors",
+            &[],
+        ))));
+    }
+    let statements = statements?;
+
+    let mut findings = parser
+        .warnings
+        .iter()
+        .map(|warning| Finding {
+            rule: "parser-warning",
+            line: warning.line,
+            message: warning.message.clone(),
+        })
+        .collect::<Vec<Finding>>();
+    findings.extend(lint_statements(&statements));
+    findings.sort_by_key(|finding| finding.line);
+    return Ok(findings);
+}
+
+pub fn lint_statements(statements: &[Statement]) -> Vec<Finding> {
+    let mut findings = vec![];
+    let mut declared: HashMap<&str, usize> = HashMap::new();
+    let mut reads = HashSet::new();
+    for statement in statements {
+        match statement {
+            Statement::Expression(_, _)
+            | Statement::Print(_, _)
+            | Statement::ForIn { .. }
+            | Statement::Assert { .. } => {
+                collect_statement_reads(statement, &mut vec![], &mut reads);
+            }
+            Statement::Var(name, initializer, _) => {
+                if let Some(initializer) = initializer {
+                    collect_reads(initializer, &mut vec![], &mut reads);
+                }
+                declare(name, &mut declared, &mut findings);
+            }
+            Statement::Destructure {
+                names, initializer, ..
+            } => {
+                collect_reads(initializer, &mut vec![], &mut reads);
+                for name in names {
+                    declare(name, &mut declared, &mut findings);
+                }
+            }
+            // dump prints every variable declared so far
+            Statement::Dump(_) => reads.extend(declared.keys().copied()),
+        }
+    }
+    for (name, line) in declared {
+        if !reads.contains(name) {
+            findings.push(Finding {
+                rule: "unused-variable",
+                line,
+                message: message("lint-never-read", &[("name", &name)]),
+            });
+        }
+    }
+    return findings;
+}
+
+fn declare<'a>(
+    name: &'a Token,
+    declared: &mut HashMap<&'a str, usize>,
+    findings: &mut Vec<Finding>,
+) {
+    if let Some(line) = declared.insert(&name.lexeme, name.line) {
+        findings.push(Finding {
+            rule: "redeclared-variable",
+            line: name.line,
+            message: message(
+                "lint-redeclared",
+                &[("name", &name.lexeme), ("line", &line)],
+            ),
+        });
+    }
+}
+
+///like `collect_reads` for the statements that can be a loop body, a loop
+///variable shadows globals inside its body
+fn collect_statement_reads<'a>(
+    statement: &'a Statement,
+    bound: &mut Vec<&'a str>,
+    reads: &mut HashSet<&'a str>,
+) {
+    match statement {
+        Statement::Expression(expr, _) | Statement::Print(expr, _) => {
+            collect_reads(expr, bound, reads)
+        }
+        Statement::ForIn {
+            name,
+            iterable,
+            body,
+            ..
+        } => {
+            collect_reads(iterable, bound, reads);
+            bound.push(&name.lexeme);
+            collect_statement_reads(body, bound, reads);
+            bound.pop();
+        }
+        Statement::Assert {
+            condition, message, ..
+        } => {
+            collect_reads(condition, bound, reads);
+            if let Some(message) = message {
+                collect_reads(message, bound, reads);
+            }
+        }
+        Statement::Var(..) | Statement::Destructure { .. } | Statement::Dump(_) => {}
+    }
+}
+
+///records every global read in `expr`, names bound by match arms shadow
+///globals inside their arm, walks with a stack of its own since a long
+///chain like `a + b + c` nests as deep as it has operators
+fn collect_reads<'a>(expr: &'a Expression, bound: &mut Vec<&'a str>, reads: &mut HashSet<&'a str>) {
+    let mut tasks = vec![Task::Expression(expr)];
+    while let Some(task) = tasks.pop() {
+        let expr = match task {
+            Task::Expression(expr) => expr,
+            Task::Bind(name) => {
+                bound.push(name);
+                continue;
+            }
+            Task::Unbind => {
+                bound.pop();
+                continue;
+            }
+        };
+        match expr {
+            Expression::Binary { left, right, .. } | Expression::Logical { left, right, .. } => {
+                tasks.extend([Task::Expression(right), Task::Expression(left)]);
+            }
+            Expression::Unary { right: inner, .. }
+            | Expression::Grouping(inner, _)
+            | Expression::Assign { value: inner, .. } => tasks.push(Task::Expression(inner)),
+            Expression::Variable { name, .. } => {
+                if !bound.contains(&name.lexeme.as_str()) {
+                    reads.insert(&name.lexeme);
+                }
+            }
+            Expression::Match { subject, arms, .. } => {
+                for arm in arms.iter().rev() {
+                    if let Pattern::Binding(name) = &arm.pattern {
+                        tasks.extend([
+                            Task::Unbind,
+                            Task::Expression(&arm.body),
+                            Task::Bind(&name.lexeme),
+                        ]);
+                    } else {
+                        tasks.push(Task::Expression(&arm.body));
+                    }
+                }
+                tasks.push(Task::Expression(subject));
+            }
+            Expression::ListLiteral { elements, .. }
+            | Expression::TupleLiteral { elements, .. } => {
+                tasks.extend(elements.iter().rev().map(Task::Expression));
+            }
+            Expression::MapLiteral { entries, .. } => {
+                for (key, value) in entries.iter().rev() {
+                    tasks.extend([Task::Expression(value), Task::Expression(key)]);
+                }
+            }
+            Expression::Index { object, index, .. } => {
+                tasks.extend([Task::Expression(index), Task::Expression(object)]);
+            }
+            Expression::IndexAssign {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                tasks.extend([
+                    Task::Expression(value),
+                    Task::Expression(index),
+                    Task::Expression(object),
+                ]);
+            }
+            Expression::Literal(_, _) | Expression::Empty => {}
+        }
+    }
+}
+
+enum Task<'a> {
+    Expression(&'a Expression),
+    ///a match arm binding this name starts
+    Bind(&'a str),
+    Unbind,
+}
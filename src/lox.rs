@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use crate::{
+    ast::{Diagnostic, LoxError, TokenLiteral},
+    config::{Extensions, LoxConfig, WarningLevel},
+    interpreter::Interpreter,
+    parser::Parser,
+    stmt::Statement,
+};
+
+pub struct Lox;
+
+impl Lox {
+    ///scans and parses `source` once with `parser`'s settings, starting it
+    ///over first, the resulting program can be run any number of times
+    ///without re-parsing, a script with syntax errors returns all of them
+    ///after reporting them to the parser's sink unless it's silent
+    pub fn compile(parser: &mut Parser, source: &str) -> Result<Program, Vec<Diagnostic>> {
+        parser.reset();
+        let (statements, diagnostics) = parser.load_collected(source.to_string());
+        if !diagnostics.is_empty() {
+            return Err(diagnostics);
+        }
+        return Ok(Program {
+            statements: statements.into(),
+        });
+    }
+}
+
+///parses a whole script on a parser of its own, nothing is printed and
+///nothing carries over between calls, a script with syntax errors returns
+///all of them, scanner errors first
+pub fn parse_program(source: &str) -> Result<Program, Vec<Diagnostic>> {
+    let statements = Parser::new().parse_str(source)?;
+    return Ok(Program {
+        statements: statements.into(),
+    });
+}
+
+///runs `source` on a fresh interpreter, see `Interpreter::eval_str`
+pub fn eval(source: &str) -> Result<TokenLiteral, Vec<Diagnostic>> {
+    return Interpreter::new().eval_str(source);
+}
+
+///a validated program, cloning only bumps a reference count
+#[derive(Debug, Clone)]
+pub struct Program {
+    statements: Arc<[Statement]>,
+}
+
+impl Program {
+    pub fn run(&self, interpreter: &mut Interpreter) {
+        interpreter.interpret_statements(&self.statements);
+    }
+
+    pub fn statements(&self) -> &[Statement] {
+        return &self.statements;
+    }
+}
+
+///an interpreter and the parsing state feeding it, for REPLs and other
+///embedders running one input after another, every input gets a parser
+///of its own so earlier tokens and statements can never be parsed or run
+///again, only the line count carries over
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub interpreter: Interpreter,
+    pub warning_level: WarningLevel,
+    pub extensions: Extensions,
+    ///collect diagnostics without printing them
+    pub silent: bool,
+    ///the line the next input starts on
+    pub line: usize,
+}
+
+///what parsing one input reported
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Parsed {
+    ///errors, scanner errors first
+    pub diagnostics: Vec<Diagnostic>,
+    pub warnings: Vec<Diagnostic>,
+    ///line of the last token the parser consumed
+    pub line: usize,
+}
+
+impl Session {
+    pub fn new(config: &LoxConfig) -> Session {
+        return Session {
+            interpreter: config.interpreter(),
+            warning_level: config.warnings,
+            extensions: config.extensions.clone(),
+            silent: false,
+            line: 1,
+        };
+    }
+
+    ///parses `source` and runs the statements that parsed
+    pub fn run(&mut self, source: String) -> (Result<(), LoxError>, Parsed) {
+        let mut parser = self.parser();
+        let statements = match parser.load(source) {
+            // recorded in `Parsed`, the statements around them still run
+            Err(LoxError::ScanError(_)) => Ok(std::mem::take(&mut parser.statements)),
+            statements => statements,
+        };
+        let parsed = self.finish(parser);
+        let result =
+            statements.map(|statements| self.interpreter.interpret_statements(&statements));
+        return (result, parsed);
+    }
+
+    ///parses and evaluates a single expression, no trailing ';' required
+    pub fn evaluate(&mut self, source: String) -> (Result<TokenLiteral, LoxError>, Parsed) {
+        let mut parser = self.parser();
+        let expr = parser.load_expression(source);
+        let parsed = self.finish(parser);
+        let value = expr.and_then(|expr| Ok(self.interpreter.evaluate(&expr)?));
+        return (value, parsed);
+    }
+
+    fn parser(&self) -> Parser<'static> {
+        let mut parser = Parser::new();
+        parser.warning_level = self.warning_level;
+        parser.extensions = self.extensions.clone();
+        parser.silent = self.silent;
+        parser.sink = self.interpreter.sink.clone();
+        parser.line = self.line;
+        parser.scanner.line = self.line;
+        return parser;
+    }
+
+    fn finish(&mut self, mut parser: Parser<'_>) -> Parsed {
+        self.line = parser.scanner.line;
+        let mut diagnostics = std::mem::take(&mut parser.scanner.diagnostics);
+        diagnostics.append(&mut parser.diagnostics);
+        return Parsed {
+            diagnostics,
+            warnings: parser.warnings,
+            line: parser.line,
+        };
+    }
+}
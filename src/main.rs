@@ -1,83 +1,655 @@
-#![allow(dead_code)]
-#![allow(non_camel_case_types)]
+#![allow(clippy::needless_return)]
 
-pub mod ast;
-pub mod ast_impl;
-pub mod interpreter;
-pub mod parser;
-pub mod scanner;
-pub mod stmt;
-pub mod tests;
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    time::Instant,
+};
 
-use std::path::Path;
-
-use parser::Parser;
-
-use crate::{ast::LoxError, interpreter::Interpreter};
+use rlox::{
+    ast::{Diagnostic, LoxError},
+    cli::{self, Command},
+    color::{self, paint, Style},
+    config::LoxConfig,
+    crash::{self, Script},
+    diff::unified_diff,
+    fix::fix_source,
+    formatter::format_source,
+    hash,
+    interpreter::{Output, SharedWriter},
+    lint,
+    lox::Lox,
+    messages::{self, Catalog},
+    mutate::{Mutant, MUTANTS},
+    repl::{OutputMode, Repl},
+    scanner::scan_to_vec,
+    sink::Severity,
+    snippet::SourceText,
+    xref::{self, Query},
+};
+#[cfg(feature = "json")]
+use rlox::{kernel, snapshot};
 
 fn main() {
-    let mut args = std::env::args();
-    if args.len() != 2 {
-        eprintln!("Usage: rlox [file.lox]");
-        std::process::exit(0);
-    }
+    crash::install();
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let (command, overrides) = match cli::split_config_flags(&args).and_then(|(args, overrides)| {
+        return Ok((cli::parse_args(&args)?, overrides));
+    }) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, cli::USAGE);
+            std::process::exit(64);
+        }
+    };
+    color::install(overrides.color);
 
-    let path = args.nth(1).expect("Failed to get script file");
+    let config = if overrides.no_config {
+        Ok(LoxConfig::default())
+    } else if let Some(path) = &overrides.config {
+        LoxConfig::load(path)
+    } else {
+        LoxConfig::discover(Path::new(command.target().unwrap_or(".")))
+    };
+    let config = match config {
+        Ok(mut config) => {
+            config.merge(&overrides);
+            config
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(78);
+        }
+    };
 
-    if path == "-" {
-        run_repl().expect("REPL Crashed");
-        return;
+    if let Some(path) = &config.messages {
+        match Catalog::load(path) {
+            Ok(catalog) => messages::install(catalog),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(78);
+            }
+        }
     }
 
-    if !Path::new(&path).exists() {
-        eprintln!("Cannot find {}\nexiting.", &path);
-        return;
+    if let Some(target) = command.target() {
+        crash::set_script(Script::File(target.into()));
     }
+    let result = match command {
+        Command::Help { topic } => {
+            println!("{}", cli::help(topic.as_deref()));
+            Ok(())
+        }
+        Command::Repl { output, restore } => run_repl(&config, output, restore),
+        #[cfg(feature = "json")]
+        Command::Kernel => kernel::run(&config),
+        Command::Messages => {
+            print!("{}", messages::render_catalog());
+            Ok(())
+        }
+        Command::Run {
+            path,
+            stream,
+            mutant,
+        } => run_file(&config, &path, stream, mutant),
+        Command::Check { paths } => check_files(&config, &paths),
+        Command::Fmt {
+            paths,
+            check,
+            max_unformatted,
+            changed_only,
+        } => file_list(paths, changed_only)
+            .and_then(|paths| format_files(&config, &paths, check, max_unformatted)),
+        Command::Lint {
+            paths,
+            max_warnings,
+            changed_only,
+        } => file_list(paths, changed_only)
+            .and_then(|paths| lint_files(&config, &paths, max_warnings)),
+        Command::Ast { path } => print_ast(&config, path),
+        Command::Tokens { path } => print_tokens(&path),
+        Command::Test { dir } => run_tests(&config, &dir),
+        Command::Bench { path, iterations } => bench_file(&config, &path, iterations),
+        Command::Mutate { dir, min_score } => mutate(&dir, min_score),
+        Command::Fix { paths, apply } => fix_files(&config, &paths, apply),
+        Command::Hash {
+            paths,
+            ignore_names,
+            canonical,
+        } => hash_files(&config, &paths, ignore_names, canonical),
+        Command::Xref { path, query, json } => cross_reference(&config, &path, &query, json),
+    };
 
-    if let Err(LoxError::ExitCode(n)) = run_file(path) {
-        std::process::exit(n);
+    match result {
+        Ok(()) => {}
+        Err(LoxError::ExitCode(n)) => std::process::exit(n),
+        Err(err) => {
+            eprintln!("{}", paint(Style::Error, &err.to_string()));
+            std::process::exit(74);
+        }
     }
 }
-fn run_repl() -> Result<(), LoxError> {
-    let mut parser = Parser::new();
-    let mut interpreter = Interpreter::new();
+
+#[cfg_attr(not(feature = "json"), allow(unused_variables))]
+fn run_repl(
+    config: &LoxConfig,
+    output: OutputMode,
+    restore: Option<String>,
+) -> Result<(), LoxError> {
+    let mut repl = Repl::new(config, output);
+    #[cfg(feature = "json")]
+    if let Some(path) = restore {
+        snapshot::restore_file(&mut repl.session.interpreter, Path::new(&path))?;
+    }
+    let mut stdout = std::io::stdout();
 
     loop {
         let mut line = String::new();
-        std::io::stdin()
+        let read = std::io::stdin()
             .read_line(&mut line)
             .expect("Failed to read line from stdin");
-        line = line.trim().to_string();
-        match parser.load(line) {
-            Ok(stmts) => {
-                interpreter.interpret(stmts);
+        if read == 0 {
+            break;
+        }
+        crash::set_script(Script::Input(line.clone()));
+        let result = repl.eval_line(line);
+        if let Ok(out) = &result {
+            for line in out {
+                writeln!(stdout, "{}", line)?;
+            }
+        }
+        // frontends driving the REPL over a pipe wait for each reply
+        stdout.flush()?;
+        if result.is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn run_file(
+    config: &LoxConfig,
+    path: &str,
+    stream: bool,
+    mutant: Option<Mutant>,
+) -> Result<(), LoxError> {
+    let path = match config.resolve(path) {
+        Some(path) => path,
+        None => {
+            eprintln!("Cannot find {}\nexiting.", path);
+            return Err(LoxError::ExitCode(66));
+        }
+    };
+
+    crash::set_script(Script::File(path.clone()));
+    let mut interpreter = config.interpreter();
+    interpreter.mutant = mutant;
+    let mut parser = config.parser();
+
+    if stream {
+        let scanned = parser.scan_file(path.display().to_string());
+        if let Err(LoxError::ScanError(_)) = scanned {
+            // parsed anyway so syntax errors are reported too
+            parser.parse_iter().for_each(drop);
+            return Err(LoxError::ExitCode(65));
+        }
+        scanned?;
+        interpreter.source = Some(SourceText::new(parser.scanner.source.as_str()));
+        interpreter.interpret_stream(parser.parse_iter());
+        if parser.had_error {
+            return Err(LoxError::ExitCode(65));
+        }
+    } else {
+        match parser.load_file(path.display().to_string()) {
+            Ok(expr) => {
+                if parser.had_error || parser.scanner.had_error {
+                    return Err(LoxError::ExitCode(65));
+                }
+                interpreter.source = Some(SourceText::new(parser.scanner.source.as_str()));
+                interpreter.interpret(expr);
             }
+            // already reported by the parser
+            Err(LoxError::ScanError(_)) => return Err(LoxError::ExitCode(65)),
             Err(err) => {
-                eprintln!("> {}", err);
-                break;
+                let diagnostic = Diagnostic {
+                    line: parser.line,
+                    column: 0,
+                    span: 0..0,
+                    code: err.code(),
+                    message: err.to_string(),
+                };
+                let rendered = paint(Style::Error, &diagnostic.message);
+                parser.sink.report(Severity::Error, &diagnostic, &rendered);
+                return Err(LoxError::ExitCode(74));
             }
         }
     }
-    Ok(())
+
+    if interpreter.had_error {
+        return Err(LoxError::ExitCode(70));
+    }
+    return Ok(());
 }
 
-fn run_file(path: String) -> Result<(), LoxError> {
-    let mut interpreter = Interpreter::new();
-    let mut parser = Parser::new();
+///applies dialect fixes in place, or without `apply` prints a diff for each
+///file that needs them and fails when there is one
+fn fix_files(config: &LoxConfig, paths: &[String], apply: bool) -> Result<(), LoxError> {
+    let mut unfixed = 0;
+    let mut had_error = false;
+    for path in paths {
+        let source = std::fs::read_to_string(path)?;
+        let fixed = match fix_source(&source, config) {
+            Ok(fixed) => fixed,
+            Err(diagnostics) => {
+                for diagnostic in diagnostics {
+                    eprintln!("{}: {}", path, diagnostic);
+                }
+                had_error = true;
+                continue;
+            }
+        };
+        if fixed.applied.is_empty() {
+            continue;
+        }
+        let summary = fixed
+            .applied
+            .iter()
+            .map(|(name, count)| format!("{} ({})", name, count))
+            .collect::<Vec<String>>()
+            .join(", ");
+        if apply {
+            std::fs::write(path, fixed.source)?;
+            eprintln!("{}: fixed {}", path, summary);
+        } else {
+            print!(
+                "{}",
+                unified_diff(
+                    &source,
+                    &fixed.source,
+                    &format!("a/{}", path),
+                    &format!("b/{}", path)
+                )
+            );
+            eprintln!("{}: needs {}", path, summary);
+            unfixed += 1;
+        }
+    }
+    if had_error {
+        return Err(LoxError::ExitCode(65));
+    }
+    if unfixed > 0 {
+        return Err(LoxError::ExitCode(1));
+    }
+    return Ok(());
+}
 
-    if parser.scanner.had_error {
+///prints `hash  path` for every file, or their canonical forms
+fn hash_files(
+    config: &LoxConfig,
+    paths: &[String],
+    ignore_names: bool,
+    canonical: bool,
+) -> Result<(), LoxError> {
+    let mut had_error = false;
+    for path in paths {
+        crash::set_script(Script::File(path.into()));
+        let mut parser = config.parser();
+        let statements = parser.load_file(path.clone());
+        if parser.had_error || parser.scanner.had_error {
+            eprintln!("{}: failed", path);
+            had_error = true;
+            continue;
+        }
+        let statements = statements?;
+        if canonical {
+            print!("{}", hash::canonical_form(&statements, ignore_names));
+        } else {
+            println!(
+                "{:016x}  {}",
+                hash::hash_program(&statements, ignore_names),
+                path
+            );
+        }
+    }
+    if had_error {
         return Err(LoxError::ExitCode(65));
     }
+    return Ok(());
+}
 
-    match parser.load_file(path) {
-        Ok(expr) => {
-            interpreter.interpret(expr);
+///prints the sites answering `query`, failing when there are none
+fn cross_reference(
+    config: &LoxConfig,
+    path: &str,
+    query: &Query,
+    json: bool,
+) -> Result<(), LoxError> {
+    let mut parser = config.parser();
+    let statements = parser.load_file(path.to_string());
+    if parser.had_error || parser.scanner.had_error {
+        return Err(LoxError::ExitCode(65));
+    }
+    let statements = statements?;
+    let sites = xref::query(&xref::index(&statements), query);
+    if json {
+        #[cfg(feature = "json")]
+        println!("{}", xref::render_json(path, query, &sites));
+    } else {
+        print!("{}", xref::render_text(path, query, &sites));
+    }
+    if sites.is_empty() {
+        if !json {
+            eprintln!("'{}' isn't used in {}", query.name(), path);
         }
-        Err(err) => {
-            eprintln!("[line: {}] Error while parsing: {:#?}", parser.line, &err);
-            return Err(err);
+        return Err(LoxError::ExitCode(1));
+    }
+    return Ok(());
+}
+
+///parses each file without running it, reporting every file with errors
+fn check_files(config: &LoxConfig, paths: &[String]) -> Result<(), LoxError> {
+    let mut failed = false;
+    for path in paths {
+        crash::set_script(Script::File(path.into()));
+        let mut parser = config.parser();
+        let ok = parser.load_file(path.clone()).is_ok()
+            && !parser.had_error
+            && !parser.scanner.had_error;
+        if !ok {
+            eprintln!("{}: failed", path);
+            failed = true;
+        }
+    }
+    if failed {
+        return Err(LoxError::ExitCode(65));
+    }
+    return Ok(());
+}
+
+///formats files in place, or with `check` prints a diff for each file that
+///isn't formatted and fails when there are more than `max_unformatted`
+fn format_files(
+    config: &LoxConfig,
+    paths: &[String],
+    check: bool,
+    max_unformatted: usize,
+) -> Result<(), LoxError> {
+    let mut unformatted = 0;
+    let mut had_error = false;
+    for path in paths {
+        let source = std::fs::read_to_string(path)?;
+        let formatted = match format_source(&source, &config.format) {
+            Ok(formatted) => formatted,
+            Err(diagnostics) => {
+                for diagnostic in diagnostics {
+                    eprintln!("{}: {}", path, diagnostic);
+                }
+                had_error = true;
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        if check {
+            print!(
+                "{}",
+                unified_diff(
+                    &source,
+                    &formatted,
+                    &format!("a/{}", path),
+                    &format!("b/{}", path)
+                )
+            );
+            unformatted += 1;
+        } else {
+            std::fs::write(path, formatted)?;
+        }
+    }
+    if had_error {
+        return Err(LoxError::ExitCode(65));
+    }
+    if check && unformatted > 0 {
+        eprintln!(
+            "{} of {} files not formatted (allowed: {})",
+            unformatted,
+            paths.len(),
+            max_unformatted
+        );
+    }
+    if unformatted > max_unformatted {
+        return Err(LoxError::ExitCode(1));
+    }
+    return Ok(());
+}
+
+///prints findings grouped by file and then by rule, failing when there are
+///more than `max_warnings` in total
+fn lint_files(config: &LoxConfig, paths: &[String], max_warnings: usize) -> Result<(), LoxError> {
+    let mut total = 0;
+    let mut had_error = false;
+    for path in paths {
+        crash::set_script(Script::File(path.into()));
+        let source = std::fs::read_to_string(path)?;
+        let findings = match lint::lint_source(&source, config) {
+            Ok(findings) => findings,
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                had_error = true;
+                continue;
+            }
+        };
+        if findings.is_empty() {
+            continue;
+        }
+        println!("{}", path);
+        let mut rules = findings
+            .iter()
+            .map(|finding| finding.rule)
+            .collect::<Vec<&str>>();
+        rules.sort();
+        rules.dedup();
+        for rule in rules {
+            let matching = findings
+                .iter()
+                .filter(|finding| finding.rule == rule)
+                .collect::<Vec<&lint::Finding>>();
+            println!("  {} ({})", rule, matching.len());
+            for finding in matching {
+                println!("    {}:{}: {}", path, finding.line, finding.message);
+            }
         }
+        total += findings.len();
     }
+    if had_error {
+        return Err(LoxError::ExitCode(65));
+    }
+    println!(
+        "{} findings in {} files (allowed: {})",
+        total,
+        paths.len(),
+        max_warnings
+    );
+    if total > max_warnings {
+        return Err(LoxError::ExitCode(1));
+    }
+    return Ok(());
+}
+
+///with `changed_only` the .lox files listed on stdin that still exist, as
+///piped from `git diff --name-only`, otherwise `paths`
+fn file_list(paths: Vec<String>, changed_only: bool) -> Result<Vec<String>, LoxError> {
+    if !changed_only {
+        return Ok(paths);
+    }
+    let mut paths = vec![];
+    for line in std::io::stdin().lines() {
+        let line = line?;
+        let path = line.trim();
+        if path.ends_with(".lox") && Path::new(path).is_file() {
+            paths.push(path.to_string());
+        }
+    }
+    return Ok(paths);
+}
+
+fn print_ast(config: &LoxConfig, path: String) -> Result<(), LoxError> {
+    let mut parser = config.parser();
+    let statements = match parser.load_file(path) {
+        Err(LoxError::ScanError(_)) => return Err(LoxError::ExitCode(65)),
+        statements => statements?,
+    };
+    for statement in statements {
+        println!("{:#?}", statement);
+    }
+    if parser.had_error {
+        return Err(LoxError::ExitCode(65));
+    }
+    return Ok(());
+}
 
+fn print_tokens(path: &str) -> Result<(), LoxError> {
+    let source = std::fs::read_to_string(path)?;
+    let (tokens, diagnostics) = scan_to_vec(&source);
+    for token in tokens {
+        println!(
+            "{}:{} {:?} {:?} {:?}",
+            token.line, token.column, token.token_type, token.lexeme, token.literal
+        );
+    }
+    if !diagnostics.is_empty() {
+        return Err(LoxError::ExitCode(65));
+    }
+    return Ok(());
+}
+
+///runs every .lox file directly inside `dir`, each one in a fresh interpreter
+fn run_tests(config: &LoxConfig, dir: &str) -> Result<(), LoxError> {
+    let paths = lox_files(dir)?;
+
+    let mut parser = config.parser();
+    let mut failed = 0;
+    for path in &paths {
+        crash::set_script(Script::File(path.clone()));
+        let passed = match std::fs::read_to_string(path) {
+            Ok(source) => match Lox::compile(&mut parser, &source) {
+                Ok(program) => {
+                    let mut interpreter = config.interpreter();
+                    program.run(&mut interpreter);
+                    !interpreter.had_error
+                }
+                // already reported by the parser
+                Err(_) => false,
+            },
+            Err(_) => false,
+        };
+        println!(
+            "{} ... {}",
+            path.display(),
+            if passed { "ok" } else { "FAILED" }
+        );
+        if !passed {
+            failed += 1;
+        }
+    }
+    println!("{} passed, {} failed", paths.len() - failed, failed);
+    if failed > 0 {
+        return Err(LoxError::ExitCode(1));
+    }
+    return Ok(());
+}
+
+///the .lox files directly inside `dir`, sorted
+fn lox_files(dir: &str) -> Result<Vec<PathBuf>, LoxError> {
+    let mut paths = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lox"))
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+    return Ok(paths);
+}
+
+///runs every script in `dir` once unmutated and once per mutant, each in a
+///child rlox, a mutant is caught when any script's output or exit status
+///differs from the unmutated run
+fn mutate(dir: &str, min_score: usize) -> Result<(), LoxError> {
+    let exe = std::env::current_exe()?;
+    let scripts = lox_files(dir)?;
+    let run = |script: &PathBuf, mutant: Option<&Mutant>| -> Result<(bool, Vec<u8>), LoxError> {
+        let mut command = std::process::Command::new(&exe);
+        command.arg("run").arg(script);
+        if let Some(mutant) = mutant {
+            command.arg("--mutant").arg(mutant.name);
+        }
+        let output = command.output()?;
+        return Ok((output.status.success(), output.stdout));
+    };
+
+    let baseline = scripts
+        .iter()
+        .map(|script| run(script, None))
+        .collect::<Result<Vec<(bool, Vec<u8>)>, LoxError>>()?;
+
+    let mut caught = 0;
+    for mutant in MUTANTS {
+        let mut killed_by = None;
+        for (script, expected) in scripts.iter().zip(&baseline) {
+            if run(script, Some(mutant))? != *expected {
+                killed_by = Some(script);
+                break;
+            }
+        }
+        match killed_by {
+            Some(script) => {
+                caught += 1;
+                println!("{} ... caught by {}", mutant.name, script.display());
+            }
+            None => println!("{} ... SURVIVED", mutant.name),
+        }
+    }
+    let score = caught * 100 / MUTANTS.len();
+    println!(
+        "{} of {} mutants caught, score {}% (required: {}%)",
+        caught,
+        MUTANTS.len(),
+        score,
+        min_score
+    );
+    if score < min_score {
+        return Err(LoxError::ExitCode(1));
+    }
+    return Ok(());
+}
+
+///compiles once and times `iterations` runs of the program
+fn bench_file(config: &LoxConfig, path: &str, iterations: usize) -> Result<(), LoxError> {
+    let source = std::fs::read_to_string(path)?;
+    let started = Instant::now();
+    let Ok(program) = Lox::compile(&mut config.parser(), &source) else {
+        // already reported by the parser
+        return Err(LoxError::ExitCode(65));
+    };
+    let compiled = started.elapsed();
+
+    let mut timings = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        // the script's prints would swamp the timings, errors still show
+        let mut interpreter = config.interpreter();
+        interpreter.output = Output::Writer(SharedWriter::new(std::io::sink()));
+        let started = Instant::now();
+        program.run(&mut interpreter);
+        timings.push(started.elapsed());
+    }
+    timings.sort();
+    let total = timings.iter().sum::<std::time::Duration>();
+    eprintln!("compile: {:?}", compiled);
+    eprintln!(
+        "run: {} iterations, min {:?}, median {:?}, max {:?}, mean {:?}",
+        iterations,
+        timings[0],
+        timings[iterations / 2],
+        timings[iterations - 1],
+        total / iterations as u32
+    );
     return Ok(());
 }
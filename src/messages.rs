@@ -0,0 +1,372 @@
+use std::{collections::HashMap, fmt::Display, path::Path, sync::RwLock};
+
+use crate::{
+    ast::LoxError,
+    config::{parse_string, strip_comment},
+};
+
+///every error and warning rlox reports, keyed by code, `{name}` in the
+///text is replaced by the argument of that name
+pub const CATALOG: &[(&str, &str)] = &[
+    // scanner
+    ("scan-error-at", "Error[{code}]: {message}"),
+    ("unexpected-character", "Unexpected character: {char}"),
+    ("unterminated-string", "Unterminated string"),
+    ("malformed-number", "Malformed number literal '{literal}'"),
+    (
+        "number-too-large",
+        "Number literal '{literal}' is too large",
+    ),
+    ("unterminated-block-comment", "Unterminated block comment!"),
+    (
+        "minimal-read",
+        "Cannot read {path}, rlox was built with the minimal feature",
+    ),
+    // parser
+    ("error-at", "Error[{code}] {location}: {message}"),
+    ("location-token", "at '{lexeme}'"),
+    ("location-end", "at end"),
+    ("warning-at", "Warning[{code}] at '{lexeme}': {message}"),
+    ("expected-expression", "Expression Expected"),
+    ("expected-end-of-expression", "Expected end of expression."),
+    (
+        "expected-semicolon-after-dump",
+        "Expected ; after dump statement.",
+    ),
+    ("expected-variable-name", "Expected variable name"),
+    (
+        "expected-close-destructure",
+        "Expected '{close}' after variable names.",
+    ),
+    (
+        "expected-destructure-initializer",
+        "Expected '=' after variable names.",
+    ),
+    ("expected-close-tuple", "Expected ')' after tuple elements."),
+    (
+        "expected-semicolon-after-var",
+        "Expected ';' after variale declaration.",
+    ),
+    (
+        "expected-semicolon-after-expression",
+        "Expected ';' after expression.",
+    ),
+    ("invalid-assignment-target", "Invalid assignment target."),
+    ("expected-close-paren", "Expected ')' after expression."),
+    ("expected-close-index", "Expected ']' after index."),
+    ("expected-close-list", "Expected ']' after list elements."),
+    ("expected-map-colon", "Expected ':' after map key."),
+    ("expected-close-map", "Expected '}' after map entries."),
+    ("expected-match-brace", "Expected '{' after match subject."),
+    ("expected-match-arrow", "Expected '=>' after match pattern."),
+    ("expected-close-match", "Expected '}' after match arms."),
+    ("expected-pattern", "Expected pattern."),
+    ("expected-for-paren", "Expected '(' after 'for'."),
+    ("expected-loop-variable", "Expected loop variable name."),
+    ("expected-for-in", "Expected 'in' after loop variable."),
+    ("expected-close-for", "Expected ')' after for clause."),
+    (
+        "expected-semicolon-after-assert",
+        "Expected ';' after assertion.",
+    ),
+    (
+        "expected-pattern-number",
+        "Expected number after '-' in pattern.",
+    ),
+    (
+        "reserved-word",
+        "'{word}' is a reserved word and can't be used as an identifier.",
+    ),
+    (
+        "non-exhaustive-match",
+        "Non-exhaustive match, missing {missing}. Add a '_' arm to handle them.",
+    ),
+    ("syntax-errors", "Program contains syntax errors"),
+    ("extension-disabled", "Extension '{name}' is disabled."),
+    (
+        "nesting-too-deep",
+        "Expression nests more than {max} levels deep.",
+    ),
+    (
+        "brace-at-statement-start",
+        "A statement can't start with '{'. Wrap a map literal in parentheses.",
+    ),
+    // lint
+    (
+        "lint-redeclared",
+        "'{name}' was already declared on line {line}",
+    ),
+    ("lint-never-read", "'{name}' is never read"),
+    // runtime
+    ("runtime-error-at", "Error[{code}]: {message}"),
+    ("unsupported-action", "Unsupported Action"),
+    (
+        "operand-not-number",
+        "Operand of '{operator}' must be a number, not {type}.",
+    ),
+    ("integer-overflow", "Integer overflow in '{operator}'."),
+    (
+        "type-mismatch",
+        "Can't apply '{operator}' to {left} and {right}.",
+    ),
+    ("generic-error", "Generic Error({message})"),
+    (
+        "limit-exceeded",
+        "Limit exceeded: {limit} is capped at {max}",
+    ),
+    (
+        "fuel-exhausted",
+        "Step budget exhausted, the script was stopped.",
+    ),
+    ("undefined-variable", "Undefined variable '{name}'."),
+    (
+        "undefined-variable-suggestion",
+        "Undefined variable '{name}', did you mean '{suggestion}'?",
+    ),
+    (
+        "index-out-of-bounds",
+        "Index {index} is out of bounds for a list of length {len}.",
+    ),
+    (
+        "not-indexable",
+        "Can only index lists and maps, not {type}.",
+    ),
+    ("index-type", "List indices must be integers, not {type}."),
+    ("missing-key", "Key {key} isn't in the map."),
+    (
+        "not-iterable",
+        "Can only iterate over lists and maps, not {type}.",
+    ),
+    (
+        "unhashable-key",
+        "Map keys must be nil, booleans, integers or strings, not {type}.",
+    ),
+    (
+        "destructure-type",
+        "Can't destructure {type} with a {kind} pattern.",
+    ),
+    (
+        "destructure-arity",
+        "Expected a {kind} of {expected} values, got {len}.",
+    ),
+    ("assertion-failed", "Assertion failed: {expression}"),
+    (
+        "assertion-failed-with-message",
+        "Assertion failed: {expression}: {message}",
+    ),
+    ("runtime-exception", "Unhandled runtime exception"),
+    ("exit-code", "Lox exited with code {code}"),
+    ("parser-error", "Parser error: {error}"),
+    (
+        "invalid-token",
+        "Invalid token {token} at {line}:{location}",
+    ),
+    ("invalid-conversion", "Cannot convert {from} to {to}"),
+    ("io-error", "IO error: {error}"),
+    // sessions
+    ("session-error", "Session {path}: {message}"),
+    (
+        "session-cycle",
+        "'{name}' contains itself and can't be saved.",
+    ),
+    (
+        "session-version",
+        "Unsupported session version {version}, expected {expected}.",
+    ),
+    (
+        "session-format",
+        "Expected an object with a version and a list of globals.",
+    ),
+    ("session-value", "Invalid saved value for '{name}'."),
+    (
+        "unknown-command",
+        "Unknown command ':{name}', expected ':save <file>'.",
+    ),
+];
+
+///the stable code of each error and warning, a code keeps its meaning
+///when the wording changes and is never reused, `E1` is the scanner,
+///`E2` and `W2` the parser and `R3` the interpreter
+pub const CODES: &[(&str, &str)] = &[
+    ("unterminated-string", "E1001"),
+    ("unexpected-character", "E1002"),
+    ("malformed-number", "E1003"),
+    ("number-too-large", "E1004"),
+    ("unterminated-block-comment", "E1005"),
+    ("expected-expression", "E2001"),
+    ("expected-end-of-expression", "E2002"),
+    ("invalid-assignment-target", "E2003"),
+    ("expected-semicolon-after-expression", "E2004"),
+    ("expected-semicolon-after-var", "E2005"),
+    ("expected-semicolon-after-dump", "E2006"),
+    ("expected-semicolon-after-assert", "E2007"),
+    ("expected-variable-name", "E2008"),
+    ("expected-close-destructure", "E2009"),
+    ("expected-destructure-initializer", "E2010"),
+    ("expected-close-paren", "E2011"),
+    ("expected-close-tuple", "E2012"),
+    ("expected-close-index", "E2013"),
+    ("expected-close-list", "E2014"),
+    ("expected-map-colon", "E2015"),
+    ("expected-close-map", "E2016"),
+    ("expected-match-brace", "E2017"),
+    ("expected-match-arrow", "E2018"),
+    ("expected-close-match", "E2019"),
+    ("expected-pattern", "E2020"),
+    ("expected-pattern-number", "E2021"),
+    ("expected-for-paren", "E2022"),
+    ("expected-loop-variable", "E2023"),
+    ("expected-for-in", "E2024"),
+    ("expected-close-for", "E2025"),
+    ("reserved-word", "E2026"),
+    ("extension-disabled", "E2027"),
+    ("nesting-too-deep", "E2028"),
+    ("brace-at-statement-start", "E2029"),
+    ("non-exhaustive-match", "W2001"),
+    ("undefined-variable", "R3001"),
+    ("type-mismatch", "R3002"),
+    ("operand-not-number", "R3003"),
+    ("integer-overflow", "R3004"),
+    ("index-out-of-bounds", "R3005"),
+    ("not-indexable", "R3006"),
+    ("index-type", "R3007"),
+    ("missing-key", "R3008"),
+    ("unhashable-key", "R3009"),
+    ("not-iterable", "R3010"),
+    ("destructure-type", "R3011"),
+    ("destructure-arity", "R3012"),
+    ("assertion-failed", "R3013"),
+    ("limit-exceeded", "R3014"),
+    ("fuel-exhausted", "R3015"),
+    ("unsupported-action", "R3016"),
+];
+
+///the stable code for the catalog entry `key`, `None` for entries that
+///aren't errors or warnings
+pub fn code(key: &str) -> Option<&'static str> {
+    return CODES
+        .iter()
+        .find(|(known, _)| *known == key)
+        .map(|(_, code)| *code);
+}
+
+///replacement texts for some of the catalog, usually a translation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Catalog {
+    pub overrides: HashMap<String, String>,
+}
+
+static ACTIVE: RwLock<Option<Catalog>> = RwLock::new(None);
+
+impl Catalog {
+    ///parses `code = "text"` lines, the same TOML subset as rlox.toml,
+    ///codes that aren't in the catalog are rejected to catch typos
+    pub fn parse(source: &str) -> Result<Catalog, (usize, String)> {
+        let mut overrides = HashMap::new();
+        for (i, line) in source.lines().enumerate() {
+            let line_no = i + 1;
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (code, text) = line
+                .split_once('=')
+                .ok_or((line_no, "Expected 'code = \"text\"'".to_string()))?;
+            let code = code.trim();
+            if default_text(code).is_none() {
+                return Err((line_no, format!("Unknown message code '{}'", code)));
+            }
+            let text = parse_string(text.trim()).map_err(|msg| (line_no, msg))?;
+            overrides.insert(code.to_string(), text);
+        }
+        return Ok(Catalog { overrides });
+    }
+
+    pub fn load(path: &Path) -> Result<Catalog, LoxError> {
+        let source = std::fs::read_to_string(path)?;
+        return Catalog::parse(&source).map_err(|(line, message)| LoxError::Config {
+            path: path.display().to_string(),
+            line,
+            message,
+        });
+    }
+
+    pub fn render(&self, code: &str, args: &[(&str, &dyn Display)]) -> String {
+        return interpolate(&self.template(code), args);
+    }
+
+    fn template(&self, code: &str) -> String {
+        return match self.overrides.get(code) {
+            Some(text) => text.clone(),
+            None => default_text(code).unwrap_or(code).to_string(),
+        };
+    }
+}
+
+fn default_text(code: &str) -> Option<&'static str> {
+    return CATALOG
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map(|(_, text)| *text);
+}
+
+///replaces each `{name}` with its argument, unknown names are kept as is
+fn interpolate(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let arg = after.find('}').and_then(|end| {
+            let name = &after[..end];
+            return args
+                .iter()
+                .find(|(arg, _)| *arg == name)
+                .map(|(_, value)| (end, value.to_string()));
+        });
+        match arg {
+            Some((end, value)) => {
+                out.push_str(&value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    return out;
+}
+
+///makes `catalog` the one every later message comes from
+pub fn install(catalog: Catalog) {
+    *ACTIVE
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(catalog);
+}
+
+///the text for `code` from the installed catalog
+pub fn message(code: &str, args: &[(&str, &dyn Display)]) -> String {
+    // arguments can be errors rendering their own messages, so the lock
+    // isn't held while they're formatted
+    let template = match ACTIVE
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .as_ref()
+    {
+        Some(catalog) => catalog.template(code),
+        None => default_text(code).unwrap_or(code).to_string(),
+    };
+    return interpolate(&template, args);
+}
+
+///the built in catalog in the format `Catalog::parse` reads, a starting
+///point for translations
+pub fn render_catalog() -> String {
+    let mut out = String::new();
+    for (code, text) in CATALOG {
+        out.push_str(&format!("{} = {:?}\n", code, text));
+    }
+    return out;
+}
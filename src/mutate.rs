@@ -0,0 +1,58 @@
+use crate::ast::TokenType;
+
+///a deliberate change to what a binary operator does, used by `rlox mutate`
+///to check that the .lox test suite notices
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mutant {
+    pub name: &'static str,
+    pub from: TokenType,
+    pub to: TokenType,
+}
+
+pub const MUTANTS: &[Mutant] = &[
+    Mutant::new("less-as-less-equal", TokenType::LESS, TokenType::LESS_EQUAL),
+    Mutant::new("less-equal-as-less", TokenType::LESS_EQUAL, TokenType::LESS),
+    Mutant::new(
+        "greater-as-greater-equal",
+        TokenType::GREATER,
+        TokenType::GREATER_EQUAL,
+    ),
+    Mutant::new(
+        "greater-equal-as-greater",
+        TokenType::GREATER_EQUAL,
+        TokenType::GREATER,
+    ),
+    Mutant::new(
+        "equal-as-not-equal",
+        TokenType::EQUAL_EQUAL,
+        TokenType::BANG_EQUAL,
+    ),
+    Mutant::new(
+        "not-equal-as-equal",
+        TokenType::BANG_EQUAL,
+        TokenType::EQUAL_EQUAL,
+    ),
+    Mutant::new("plus-as-minus", TokenType::PLUS, TokenType::MINUS),
+    Mutant::new("minus-as-plus", TokenType::MINUS, TokenType::PLUS),
+    Mutant::new("star-as-slash", TokenType::STAR, TokenType::SLASH),
+    Mutant::new("slash-as-star", TokenType::SLASH, TokenType::STAR),
+    Mutant::new("exponent-as-star", TokenType::EXPONENT, TokenType::STAR),
+];
+
+impl Mutant {
+    const fn new(name: &'static str, from: TokenType, to: TokenType) -> Mutant {
+        return Mutant { name, from, to };
+    }
+
+    pub fn from_name(name: &str) -> Option<Mutant> {
+        return MUTANTS.iter().find(|mutant| mutant.name == name).copied();
+    }
+
+    ///the operator the interpreter should apply in place of `operator`
+    pub fn apply(&self, operator: TokenType) -> TokenType {
+        if operator == self.from {
+            return self.to;
+        }
+        return operator;
+    }
+}
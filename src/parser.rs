@@ -1,19 +1,80 @@
+use std::{fmt::Display, ops::Range};
+
+#[cfg(feature = "bigint")]
+use crate::bigint;
 use crate::{
-    ast::{Expression, LoxError, ParserError, Token, TokenLiteral, TokenType},
-    scanner::Scanner,
-    stmt::Statement,
+    ast::{
+        Diagnostic, Expression, LiteralType, LoxError, MatchArm, ParserError, Pattern, Position,
+        Token, TokenLiteral, TokenType, IDENT_MAP,
+    },
+    color::{paint, Style},
+    config::{Extension, Extensions, WarningLevel},
+    crash,
+    messages::{self, message},
+    resolver::{resolve, resolve_expression},
+    scanner::{Scanner, Source},
+    sink::{Severity, SharedSink},
+    snippet,
+    stmt::{DestructureKind, Statement},
 };
 
+///how deep expressions and statements may nest, every later pass walks
+///the tree recursively so deeper input would overflow the stack
+pub const MAX_DEPTH: usize = 256;
+
+///`'src` is the source when it's borrowed, see `validate`, anything that
+///loads owned source works as a `Parser<'static>`
 #[derive(Debug, Clone, Default)]
-pub struct Parser {
+pub struct Parser<'src> {
     pub current: usize,
     pub line: usize,
     pub statements: Vec<Statement>,
-    pub scanner: Scanner,
+    pub scanner: Scanner<'src>,
     pub had_error: bool,
+    pub warnings: Vec<Diagnostic>,
+    pub warning_level: WarningLevel,
+    ///every error reported while parsing
+    pub diagnostics: Vec<Diagnostic>,
+    ///collect diagnostics without reporting them to the sink
+    pub silent: bool,
+    pub extensions: Extensions,
+    ///where errors and warnings are reported, stderr by default
+    pub sink: SharedSink,
+    ///prints the tokens skipped after a syntax error to stderr
+    pub trace: bool,
+    ///how deep the expression being parsed nests so far
+    depth: usize,
+}
+
+///statements parsed as they're asked for, see `Parser::parse_iter`
+pub struct ParseIter<'a, 'src> {
+    parser: &'a mut Parser<'src>,
+}
+
+impl Iterator for ParseIter<'_, '_> {
+    type Item = Result<Statement, LoxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.parser.is_at_end() {
+            return None;
+        }
+        let result = self.parser.declaration();
+        return Some(match result {
+            // every statement binds its own names, so resolving them one at
+            // a time is the same as resolving the whole script
+            Ok(mut statement) => {
+                resolve(std::slice::from_mut(&mut statement));
+                Ok(statement)
+            }
+            Err(err) => {
+                self.parser.synchronize();
+                Err(err)
+            }
+        });
+    }
 }
 
-impl Parser {
+impl<'src> Parser<'src> {
     pub fn new() -> Self {
         Self {
             line: 1,
@@ -21,21 +82,169 @@ impl Parser {
         }
     }
 
+    ///scans and parses `source` as a continuation of what was loaded
+    ///before, its lines are counted on from there, `parse_str` starts over
     pub fn load(&mut self, source: String) -> Result<Vec<Statement>, LoxError> {
-        self.scanner.load(source.chars().collect());
+        return self.load_source(Source::Owned(source));
+    }
+
+    ///parses `source` on its own, nothing loaded before is kept except the
+    ///settings, and nothing is printed, a script with syntax errors returns
+    ///all of them, scanner errors first
+    pub fn parse_str(&mut self, source: &str) -> Result<Vec<Statement>, Vec<Diagnostic>> {
+        self.reset();
+        let (statements, diagnostics) = self.load_silently(source.to_string());
+        if !diagnostics.is_empty() {
+            return Err(diagnostics);
+        }
+        return Ok(statements);
+    }
+
+    ///forgets every token, statement and diagnostic, keeping the settings
+    pub fn reset(&mut self) {
+        *self = Parser {
+            warning_level: self.warning_level,
+            extensions: std::mem::take(&mut self.extensions),
+            silent: self.silent,
+            sink: self.sink.clone(),
+            trace: self.trace,
+            ..Parser::new()
+        };
+    }
+
+    ///parses `source` without printing anything and returns its syntax
+    ///errors, scanner errors first
+    pub fn syntax_errors(&mut self, source: String) -> Vec<Diagnostic> {
+        return self.load_silently(source).1;
+    }
+
+    ///like `syntax_errors`, also returning the statements that parsed
+    pub fn load_silently(&mut self, source: String) -> (Vec<Statement>, Vec<Diagnostic>) {
+        self.silent = true;
+        return self.load_collected(source);
+    }
+
+    ///like `load_silently`, reporting to the sink unless `silent` is set
+    pub fn load_collected(&mut self, source: String) -> (Vec<Statement>, Vec<Diagnostic>) {
+        let result = self.load(source);
+        let mut diagnostics = std::mem::take(&mut self.scanner.diagnostics);
+        diagnostics.append(&mut self.diagnostics);
+        let statements = match result {
+            Ok(statements) => statements,
+            Err(err) => {
+                if diagnostics.is_empty() {
+                    let token = self.peek();
+                    diagnostics.push(Diagnostic {
+                        line: token.line,
+                        column: token.column,
+                        span: token.span(),
+                        code: err.code(),
+                        message: err.to_string(),
+                    });
+                }
+                vec![]
+            }
+        };
+        return (statements, diagnostics);
+    }
+
+    ///parses whatever scanned even when scanning failed, so syntax errors
+    ///after a scan error are still reported, but then fails with the scan
+    ///errors
+    fn load_source(&mut self, source: Source<'src>) -> Result<Vec<Statement>, LoxError> {
+        let scan_errors = self.scan(source);
         let stmts = self.parse()?;
         self.statements.extend_from_slice(&stmts);
+        if !scan_errors.is_empty() {
+            return Err(LoxError::ScanError(scan_errors));
+        }
         return Ok(stmts);
     }
 
+    ///loads source holding a single expression, no trailing ';' required
+    pub fn load_expression(&mut self, source: String) -> Result<Expression, LoxError> {
+        let scan_errors = self.scan(Source::Owned(source));
+        let expr = self.expression().and_then(|mut expr| {
+            if self.is_at_end() {
+                resolve_expression(&mut expr);
+                return Ok(expr);
+            }
+            return Err(self.err("expected-end-of-expression", &[]));
+        });
+        // skip to the EOF token so the next load starts on fresh input
+        if expr.is_err() {
+            self.current = self.scanner.tokens.len() - 1;
+        }
+        if !scan_errors.is_empty() {
+            return Err(LoxError::ScanError(scan_errors));
+        }
+        return expr;
+    }
+
+    ///scans `source` after the tokens already loaded and returns its
+    ///errors, printing them unless silent since the scanner never prints
+    fn scan(&mut self, source: Source<'src>) -> Vec<Diagnostic> {
+        let known = self.scanner.diagnostics.len();
+        self.scanner.load_source(source);
+        self.skip_trivia();
+        let errors = self.scanner.diagnostics[known..].to_vec();
+        if !self.silent {
+            for error in &errors {
+                let args: &[(&str, &dyn Display)] = &[
+                    ("code", &error.code.unwrap_or_default()),
+                    ("message", &error.message),
+                ];
+                let header = format!(
+                    "[{}] {}",
+                    Position(error.line, error.column),
+                    message("scan-error-at", args)
+                );
+                let source = self.scanner.source.as_str();
+                let rendered = format!(
+                    "{}\n{}",
+                    paint(Style::Error, &header),
+                    snippet::render_from(
+                        source,
+                        self.scanner.first_line,
+                        error.span.clone(),
+                        &error.message
+                    )
+                );
+                self.sink.report(Severity::Error, error, &rendered);
+            }
+        }
+        return errors;
+    }
+
     pub fn load_file(&mut self, path: String) -> Result<Vec<Statement>, LoxError> {
-        match std::fs::read_to_string(path) {
-            Ok(source) => self.load(source),
-            Err(e) => Err(LoxError::ParseError(ParserError::Generic(format!(
-                "{}",
-                e.to_string()
-            )))),
+        let source = Source::read(&path)?;
+        return self.load_source(source);
+    }
+
+    ///scans a script without parsing it, for `parse_iter`
+    pub fn scan_file(&mut self, path: String) -> Result<(), LoxError> {
+        let source = Source::read(&path)?;
+        return self.scan_only(source);
+    }
+
+    pub fn scan_str(&mut self, source: &str) -> Result<(), LoxError> {
+        return self.scan_only(Source::Owned(source.to_string()));
+    }
+
+    fn scan_only(&mut self, source: Source<'src>) -> Result<(), LoxError> {
+        let scan_errors = self.scan(source);
+        if !scan_errors.is_empty() {
+            return Err(LoxError::ScanError(scan_errors));
         }
+        return Ok(());
+    }
+
+    ///parses the scanned tokens one statement at a time, they aren't kept
+    ///in `statements`, a statement that fails to parse is reported and
+    ///skipped like in `parse`, the tokens themselves are all scanned up
+    ///front
+    pub fn parse_iter(&mut self) -> ParseIter<'_, 'src> {
+        return ParseIter { parser: self };
     }
 
     pub fn parse(&mut self) -> Result<Vec<Statement>, LoxError> {
@@ -45,12 +254,11 @@ impl Parser {
                 Ok(stmt) => {
                     statements.push(stmt);
                 }
-                Err(e) => {
-                    let cur_token = self.peek();
-                    self.err(cur_token, e.to_string());
-                }
+                // the error was reported where it was found
+                Err(_) => self.synchronize(),
             }
         }
+        resolve(&mut statements);
         return Ok(statements);
     }
 
@@ -64,71 +272,249 @@ impl Parser {
     }
 
     fn dump_statement(&mut self) -> Result<Statement, LoxError> {
-        let err_msg = "Expected ; after dump statement.";
-        self.consume(TokenType::SEMICOLON, err_msg.to_string())?;
-        return Ok(Statement::Dump);
+        let start = self.previous().offset;
+        self.require_previous(Extension::Dump);
+        self.consume(TokenType::SEMICOLON, "expected-semicolon-after-dump", &[])?;
+        return Ok(Statement::Dump(self.span_from(start)));
     }
 
     fn var_declaration(&mut self) -> Result<Statement, LoxError> {
-        let name = self.consume(TokenType::IDENTIFIER, "Expected variable name".to_string())?;
+        let start = self.previous().offset;
+        if self.consume_if_type(&[
+            TokenType::LEFT_PAREN,
+            TokenType::LEFT_BRACKET,
+            TokenType::LEFT_BRACE,
+        ]) {
+            return self.destructure_declaration(start);
+        }
+        let name = self.consume_identifier("expected-variable-name")?;
         let mut initializer = None;
         if self.consume_if_type(&[TokenType::EQUAL]) {
-            initializer = self.expression().ok();
+            initializer = Some(self.expression()?);
         }
-        if let Err(e) = self.consume(
-            TokenType::SEMICOLON,
-            "Expected ';' after variale declaration.".to_string(),
-        ) {
-            let cur_token = self.peek();
-            self.err(cur_token, e.to_string());
-            return Err(e);
+        self.consume(TokenType::SEMICOLON, "expected-semicolon-after-var", &[])?;
+        return Ok(Statement::Var(name, initializer, self.span_from(start)));
+    }
+
+    fn destructure_declaration(&mut self, start: usize) -> Result<Statement, LoxError> {
+        let open = self.previous().clone();
+        let (kind, extension, close, lexeme) = match open.token_type {
+            TokenType::LEFT_PAREN => (
+                DestructureKind::Tuple,
+                Extension::Tuples,
+                TokenType::RIGHT_PAREN,
+                ")",
+            ),
+            TokenType::LEFT_BRACKET => (
+                DestructureKind::List,
+                Extension::Lists,
+                TokenType::RIGHT_BRACKET,
+                "]",
+            ),
+            _ => (
+                DestructureKind::Map,
+                Extension::Maps,
+                TokenType::RIGHT_BRACE,
+                "}",
+            ),
+        };
+        self.require(extension, &open);
+        let mut names = vec![self.consume_identifier("expected-variable-name")?];
+        while self.consume_if_type(&[TokenType::COMMA]) {
+            names.push(self.consume_identifier("expected-variable-name")?);
         }
-        return Ok(Statement::Var(name, initializer));
+        self.consume(close, "expected-close-destructure", &[("close", &lexeme)])?;
+        self.consume(TokenType::EQUAL, "expected-destructure-initializer", &[])?;
+        let initializer = self.expression()?;
+        self.consume(TokenType::SEMICOLON, "expected-semicolon-after-var", &[])?;
+        return Ok(Statement::Destructure {
+            kind,
+            open,
+            names,
+            initializer,
+            span: self.span_from(start),
+        });
     }
 
     fn statement(&mut self) -> Result<Statement, LoxError> {
         if self.consume_if_type(&[TokenType::PRINT]) {
             return self.print_statement();
         }
+        if self.consume_if_type(&[TokenType::FOR]) {
+            return self.for_in_statement();
+        }
+        if self.consume_if_type(&[TokenType::ASSERT]) {
+            return self.assert_statement();
+        }
         return self.expression_statement();
     }
 
-    fn print_statement(&mut self) -> Result<Statement, LoxError> {
-        if self.peek().token_type == TokenType::IDENTIFIER {
-            let cur_token = self.peek();
-            self.next();
-            self.consume(
-                TokenType::SEMICOLON,
-                "Expected ';' after expression.".to_string(),
-            )?;
-            return Ok(Statement::Print(Expression::Literal(cur_token.literal)));
+    ///`in` is only special here, it's still a valid variable name
+    fn for_in_statement(&mut self) -> Result<Statement, LoxError> {
+        let start = self.previous().offset;
+        self.require_previous(Extension::ForIn);
+        self.consume(TokenType::LEFT_PAREN, "expected-for-paren", &[])?;
+        let name = self.consume_identifier("expected-loop-variable")?;
+        if !(self.check(TokenType::IDENTIFIER) && self.peek().lexeme == "in") {
+            return Err(self.err("expected-for-in", &[]));
         }
+        self.next();
+        let iterable = self.expression()?;
+        self.consume(TokenType::RIGHT_PAREN, "expected-close-for", &[])?;
+        let body = self.nested(Parser::statement)?;
+        return Ok(Statement::ForIn {
+            name,
+            iterable,
+            body: Box::new(body),
+            span: self.span_from(start),
+        });
+    }
+
+    fn assert_statement(&mut self) -> Result<Statement, LoxError> {
+        let keyword = self.previous().clone();
+        self.require(Extension::Assert, &keyword);
+        let start = self.peek().offset;
+        let condition = self.expression()?;
+        let end = self.previous().span().end;
+        let source = self.scanner.source.as_str()[start..end].to_string();
+        let mut assert_message = None;
+        if self.consume_if_type(&[TokenType::COMMA]) {
+            assert_message = Some(self.expression()?);
+        }
+        self.consume(TokenType::SEMICOLON, "expected-semicolon-after-assert", &[])?;
+        return Ok(Statement::Assert {
+            span: self.span_from(keyword.offset),
+            keyword,
+            condition,
+            source,
+            message: assert_message,
+        });
+    }
+
+    fn print_statement(&mut self) -> Result<Statement, LoxError> {
+        let start = self.previous().offset;
         let expr = self.expression()?;
         self.consume(
             TokenType::SEMICOLON,
-            "Expected ';' after expression.".to_string(),
+            "expected-semicolon-after-expression",
+            &[],
         )?;
-        return Ok(Statement::Print(expr));
+        return Ok(Statement::Print(expr, self.span_from(start)));
     }
 
+    ///a `{` here is kept for blocks, so a map literal can't start a
+    ///statement, `({"a": 1})["a"];` still works
     fn expression_statement(&mut self) -> Result<Statement, LoxError> {
+        let start = self.peek().offset;
+        if self.check(TokenType::LEFT_BRACE) {
+            let error = self.err("brace-at-statement-start", &[]);
+            self.skip_braces();
+            return Err(error);
+        }
         let expr = self.expression()?;
         self.consume(
             TokenType::SEMICOLON,
-            "Expected ';' after expression.".to_string(),
+            "expected-semicolon-after-expression",
+            &[],
         )?;
-        return Ok(Statement::Expression(expr));
+        return Ok(Statement::Expression(expr, self.span_from(start)));
     }
 
     fn expression(&mut self) -> Result<Expression, LoxError> {
-        return self.equality();
+        return self.nested(Parser::assignment);
+    }
+
+    fn assignment(&mut self) -> Result<Expression, LoxError> {
+        let mut expr = self.coalesce()?;
+
+        if self.consume_if_type(&[
+            TokenType::EQUAL,
+            TokenType::PLUS_EQUAL,
+            TokenType::MINUS_EQUAL,
+            TokenType::STAR_EQUAL,
+            TokenType::SLASH_EQUAL,
+        ]) {
+            let equals = self.previous().clone();
+            let mut value = self.nested(Parser::assignment)?;
+            // `a += b` is sugar for `a = a + b`
+            let operator = match equals.token_type {
+                TokenType::PLUS_EQUAL => Some((TokenType::PLUS, "+")),
+                TokenType::MINUS_EQUAL => Some((TokenType::MINUS, "-")),
+                TokenType::STAR_EQUAL => Some((TokenType::STAR, "*")),
+                TokenType::SLASH_EQUAL => Some((TokenType::SLASH, "/")),
+                _ => None,
+            };
+            if let Some((token_type, lexeme)) = operator {
+                self.require(Extension::CompoundAssignment, &equals);
+                value = Expression::Binary {
+                    left: Box::new(expr.clone()),
+                    operator: Token {
+                        token_type,
+                        lexeme: lexeme.to_string(),
+                        literal: TokenLiteral::Empty,
+                        line: equals.line,
+                        column: equals.column,
+                        offset: equals.offset,
+                    },
+                    right: Box::new(value),
+                };
+            }
+            match &mut expr {
+                Expression::Variable { name, .. } => {
+                    return Ok(Expression::Assign {
+                        name: name.clone(),
+                        value: Box::new(value),
+                        local: None,
+                    });
+                }
+                Expression::Index {
+                    object,
+                    bracket,
+                    index,
+                } => {
+                    return Ok(Expression::IndexAssign {
+                        object: std::mem::take(object),
+                        bracket: bracket.clone(),
+                        index: std::mem::take(index),
+                        value: Box::new(value),
+                    });
+                }
+                _ => {}
+            }
+            self.had_error = true;
+            self.report(
+                &equals,
+                message("location-token", &[("lexeme", &equals.lexeme)]),
+                "invalid-assignment-target",
+                message("invalid-assignment-target", &[]),
+            );
+        }
+
+        return Ok(expr);
+    }
+
+    fn coalesce(&mut self) -> Result<Expression, LoxError> {
+        let mut expr = self.equality()?;
+
+        while self.consume_if_type(&[TokenType::QUESTION_QUESTION]) {
+            let operator = self.previous().clone();
+            self.require(Extension::NilCoalescing, &operator);
+            let right = self.equality()?;
+            expr = Expression::Logical {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            }
+        }
+
+        return Ok(expr);
     }
 
     pub fn equality(&mut self) -> Result<Expression, LoxError> {
         let mut expr = self.comparison()?;
 
         while self.consume_if_type(&[TokenType::BANG_EQUAL, TokenType::EQUAL_EQUAL]) {
-            let operator = self.previous();
+            let operator = self.previous().clone();
             let right = self.comparison()?;
             expr = Expression::Binary {
                 left: Box::new(expr),
@@ -149,7 +535,7 @@ impl Parser {
             TokenType::LESS,
             TokenType::LESS_EQUAL,
         ]) {
-            let operator = self.previous();
+            let operator = self.previous().clone();
             let right = self.term()?;
             expr = Expression::Binary {
                 left: Box::new(expr),
@@ -165,7 +551,7 @@ impl Parser {
         let mut expr = self.factor()?;
 
         while self.consume_if_type(&[TokenType::MINUS, TokenType::PLUS]) {
-            let operator = self.previous();
+            let operator = self.previous().clone();
             let right = self.factor()?;
             expr = Expression::Binary {
                 left: Box::new(expr),
@@ -181,7 +567,10 @@ impl Parser {
         let mut expr = self.unary()?;
 
         while self.consume_if_type(&[TokenType::SLASH, TokenType::STAR, TokenType::EXPONENT]) {
-            let operator = self.previous();
+            let operator = self.previous().clone();
+            if operator.token_type == TokenType::EXPONENT {
+                self.require(Extension::Exponent, &operator);
+            }
             let right = self.unary()?;
             expr = Expression::Binary {
                 left: Box::new(expr),
@@ -195,54 +584,347 @@ impl Parser {
 
     pub fn unary(&mut self) -> Result<Expression, LoxError> {
         if self.consume_if_type(&[TokenType::BANG, TokenType::MINUS]) {
-            let operator = self.previous();
-            let right = self.unary()?;
+            let operator = self.previous().clone();
+            let right = self.nested(Parser::unary)?;
             return Ok(Expression::Unary {
                 operator,
                 right: Box::new(right),
             });
         }
 
-        return self.primary();
+        return self.index();
+    }
+
+    pub fn index(&mut self) -> Result<Expression, LoxError> {
+        let mut expr = self.primary()?;
+
+        while self.consume_if_type(&[TokenType::LEFT_BRACKET]) {
+            // indexing works on lists and maps, either one allows it
+            if !self.extensions.enabled(Extension::Maps) {
+                self.require_previous(Extension::Lists);
+            }
+            let index = self.expression()?;
+            let bracket = self
+                .consume(TokenType::RIGHT_BRACKET, "expected-close-index", &[])?
+                .clone();
+            expr = Expression::Index {
+                object: Box::new(expr),
+                bracket,
+                index: Box::new(index),
+            };
+        }
+
+        return Ok(expr);
     }
 
     pub fn primary(&mut self) -> Result<Expression, LoxError> {
         if self.consume_if_type(&[TokenType::FALSE]) {
-            return Ok(Expression::Literal(TokenLiteral::Boolean(false)));
+            let span = self.previous().span();
+            return Ok(Expression::Literal(TokenLiteral::Boolean(false), span));
         }
         if self.consume_if_type(&[TokenType::TRUE]) {
-            return Ok(Expression::Literal(TokenLiteral::Boolean(true)));
+            let span = self.previous().span();
+            return Ok(Expression::Literal(TokenLiteral::Boolean(true), span));
         }
 
         if self.consume_if_type(&[TokenType::NIL]) {
-            return Ok(Expression::Literal(TokenLiteral::Empty));
+            let span = self.previous().span();
+            return Ok(Expression::Literal(TokenLiteral::Empty, span));
         }
 
         if self.consume_if_type(&[TokenType::NUMBER, TokenType::STRING]) {
-            let prev = self.previous();
-            return Ok(Expression::Literal(prev.literal));
+            let literal = self.literal();
+            return Ok(Expression::Literal(literal, self.previous().span()));
         }
 
         if self.consume_if_type(&[TokenType::LEFT_PAREN]) {
+            let paren = self.previous().clone();
             let expr = self.expression()?;
-            self.consume(
-                TokenType::RIGHT_PAREN,
-                "Expected ')' after expression.".to_string(),
-            )?;
-            return Ok(Expression::Grouping(Box::new(expr)));
+            if self.consume_if_type(&[TokenType::COMMA]) {
+                return self.tuple_literal(paren, expr);
+            }
+            self.consume(TokenType::RIGHT_PAREN, "expected-close-paren", &[])?;
+            let span = self.span_from(paren.offset);
+            return Ok(Expression::Grouping(Box::new(expr), span));
         }
 
-        if self.peek().token_type == TokenType::IDENTIFIER {
-            return Ok(Expression::Literal(self.peek().literal));
+        if self.consume_if_type(&[TokenType::IDENTIFIER]) {
+            return Ok(Expression::Variable {
+                name: self.previous().clone(),
+                local: None,
+            });
         }
 
-        return Err(LoxError::ParseError(ParserError::Generic(
-            "Expression Expected".to_string(),
-        )));
+        if self.consume_if_type(&[TokenType::LEFT_BRACKET]) {
+            let bracket = self.previous().clone();
+            self.require(Extension::Lists, &bracket);
+            let mut elements = vec![];
+            while !self.check(TokenType::RIGHT_BRACKET) && !self.is_at_end() {
+                elements.push(self.expression()?);
+                if !self.consume_if_type(&[TokenType::COMMA]) {
+                    break;
+                }
+            }
+            self.consume(TokenType::RIGHT_BRACKET, "expected-close-list", &[])?;
+            return Ok(Expression::ListLiteral {
+                span: self.span_from(bracket.offset),
+                bracket,
+                elements,
+            });
+        }
+
+        if self.consume_if_type(&[TokenType::LEFT_BRACE]) {
+            let brace = self.previous().clone();
+            self.require(Extension::Maps, &brace);
+            let mut entries = vec![];
+            while !self.check(TokenType::RIGHT_BRACE) && !self.is_at_end() {
+                let key = self.expression()?;
+                self.consume(TokenType::COLON, "expected-map-colon", &[])?;
+                entries.push((key, self.expression()?));
+                if !self.consume_if_type(&[TokenType::COMMA]) {
+                    break;
+                }
+            }
+            self.consume(TokenType::RIGHT_BRACE, "expected-close-map", &[])?;
+            return Ok(Expression::MapLiteral {
+                span: self.span_from(brace.offset),
+                brace,
+                entries,
+            });
+        }
+
+        if self.consume_if_type(&[TokenType::MATCH]) {
+            return self.match_expression();
+        }
+
+        return Err(self.err("expected-expression", &[]));
+    }
+
+    ///the rest of a tuple after its first element and comma, a trailing
+    ///comma is allowed
+    fn tuple_literal(&mut self, paren: Token, first: Expression) -> Result<Expression, LoxError> {
+        self.require(Extension::Tuples, &paren);
+        let mut elements = vec![first];
+        while !self.check(TokenType::RIGHT_PAREN) {
+            elements.push(self.expression()?);
+            if !self.consume_if_type(&[TokenType::COMMA]) {
+                break;
+            }
+        }
+        self.consume(TokenType::RIGHT_PAREN, "expected-close-tuple", &[])?;
+        return Ok(Expression::TupleLiteral {
+            span: self.span_from(paren.offset),
+            paren,
+            elements,
+        });
+    }
+
+    fn match_expression(&mut self) -> Result<Expression, LoxError> {
+        let keyword = self.previous().clone();
+        self.require(Extension::Match, &keyword);
+        let subject = self.expression()?;
+        self.consume(TokenType::LEFT_BRACE, "expected-match-brace", &[])?;
+
+        let mut arms = vec![];
+        while !self.check(TokenType::RIGHT_BRACE) && !self.is_at_end() {
+            let pattern = self.pattern()?;
+            self.consume(TokenType::FAT_ARROW, "expected-match-arrow", &[])?;
+            let body = self.expression()?;
+            arms.push(MatchArm { pattern, body });
+            if !self.consume_if_type(&[TokenType::COMMA]) {
+                break;
+            }
+        }
+        self.consume(TokenType::RIGHT_BRACE, "expected-close-match", &[])?;
+
+        self.check_exhaustiveness(&keyword, &arms);
+        return Ok(Expression::Match {
+            span: self.span_from(keyword.offset),
+            keyword,
+            subject: Box::new(subject),
+            arms,
+        });
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, LoxError> {
+        if self.consume_if_type(&[TokenType::TRUE]) {
+            return Ok(Pattern::Literal(TokenLiteral::Boolean(true)));
+        }
+        if self.consume_if_type(&[TokenType::FALSE]) {
+            return Ok(Pattern::Literal(TokenLiteral::Boolean(false)));
+        }
+        if self.consume_if_type(&[TokenType::NIL]) {
+            return Ok(Pattern::Literal(TokenLiteral::Empty));
+        }
+        if self.consume_if_type(&[TokenType::NUMBER, TokenType::STRING]) {
+            return Ok(Pattern::Literal(self.literal()));
+        }
+        if self.consume_if_type(&[TokenType::MINUS]) {
+            self.consume(TokenType::NUMBER, "expected-pattern-number", &[])?;
+            return match self.literal() {
+                TokenLiteral::Integer(n) => Ok(Pattern::Literal(TokenLiteral::Integer(-n))),
+                TokenLiteral::Float(n) => Ok(Pattern::Literal(TokenLiteral::Float(-n))),
+                #[cfg(feature = "bigint")]
+                TokenLiteral::BigInt(n) => Ok(Pattern::Literal(bigint::normalize(-n))),
+                _ => Err(LoxError::ParseError(ParserError::UnsupportedAction)),
+            };
+        }
+        if self.consume_if_type(&[TokenType::IDENTIFIER]) {
+            let name = self.previous();
+            if name.lexeme == "_" {
+                return Ok(Pattern::Wildcard);
+            }
+            if let Some(literal_type) = LiteralType::from_name(&name.lexeme) {
+                return Ok(Pattern::Type(literal_type));
+            }
+            return Ok(Pattern::Binding(name.clone()));
+        }
+
+        return Err(self.err("expected-pattern", &[]));
     }
 
-    pub fn previous(&mut self) -> Token {
-        return self.scanner.tokens[self.current - 1].clone();
+    ///warns when a match over booleans/nil leaves some of them unhandled
+    fn check_exhaustiveness(&mut self, keyword: &Token, arms: &[MatchArm]) {
+        let mut covered = vec![];
+        for arm in arms {
+            match &arm.pattern {
+                Pattern::Literal(lit @ (TokenLiteral::Boolean(_) | TokenLiteral::Empty)) => {
+                    covered.push(lit.clone());
+                }
+                Pattern::Type(LiteralType::Boolean) => {
+                    covered.push(TokenLiteral::Boolean(true));
+                    covered.push(TokenLiteral::Boolean(false));
+                }
+                Pattern::Type(LiteralType::Nil) => covered.push(TokenLiteral::Empty),
+                _ => return,
+            }
+        }
+
+        let mut domain = vec![TokenLiteral::Boolean(true), TokenLiteral::Boolean(false)];
+        if covered.contains(&TokenLiteral::Empty) {
+            domain.push(TokenLiteral::Empty);
+        }
+        let missing = domain
+            .iter()
+            .filter(|lit| !covered.contains(lit))
+            .map(|lit| match lit {
+                TokenLiteral::Empty => "nil".to_string(),
+                lit => lit.to_string(),
+            })
+            .collect::<Vec<String>>();
+
+        if !missing.is_empty() {
+            self.warn(
+                keyword,
+                "non-exhaustive-match",
+                message("non-exhaustive-match", &[("missing", &missing.join(", "))]),
+            );
+        }
+    }
+
+    ///the value of the number or string token just consumed, without the
+    ///integers extension every number is a float
+    fn literal(&mut self) -> TokenLiteral {
+        let token = self.previous();
+        if token.lexeme.starts_with('\'') {
+            self.require_previous(Extension::SingleQuotes);
+        }
+        let token = self.previous();
+        // only `0x`, `0o` and `0b` numbers have letters in them
+        if token.token_type == TokenType::NUMBER && token.lexeme.contains(char::is_alphabetic) {
+            self.require_previous(Extension::RadixLiterals);
+        }
+        return match &self.previous().literal {
+            TokenLiteral::Integer(n) if !self.extensions.enabled(Extension::Integers) => {
+                TokenLiteral::Float(*n as f64)
+            }
+            #[cfg(feature = "bigint")]
+            TokenLiteral::BigInt(n) if !self.extensions.enabled(Extension::Integers) => {
+                TokenLiteral::Float(bigint::to_f64(n))
+            }
+            literal => literal.clone(),
+        };
+    }
+
+    ///reports `token` when it starts a construct from a disabled extension,
+    ///parsing carries on as if it were enabled
+    fn require(&mut self, extension: Extension, token: &Token) {
+        if self.extensions.enabled(extension) {
+            return;
+        }
+        self.had_error = true;
+        let location = self.location(token);
+        self.report(
+            token,
+            location,
+            "extension-disabled",
+            message("extension-disabled", &[("name", &extension.name())]),
+        );
+    }
+
+    ///`require` for the token just consumed
+    fn require_previous(&mut self, extension: Extension) {
+        if !self.extensions.enabled(extension) {
+            let token = self.previous().clone();
+            self.require(extension, &token);
+        }
+    }
+
+    ///runs `parse` one level deeper, reporting input that nests past
+    ///`MAX_DEPTH` instead, the operators of a chain like `a + b + c` don't
+    ///count since the parser loops over them and so does everything
+    ///walking the tree afterwards
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Parser<'src>) -> Result<T, LoxError>,
+    ) -> Result<T, LoxError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.err("nesting-too-deep", &[("max", &MAX_DEPTH)]));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        return result;
+    }
+
+    ///byte range from `start` to the end of the token just consumed
+    fn span_from(&self, start: usize) -> Range<usize> {
+        return start..self.previous().span().end;
+    }
+
+    fn location(&self, token: &Token) -> String {
+        if token.token_type == TokenType::EOF {
+            return message("location-end", &[]);
+        }
+        return message("location-token", &[("lexeme", &token.lexeme)]);
+    }
+
+    ///consumes an identifier, explaining when a reserved word was used instead
+    fn consume_identifier(&mut self, code: &'static str) -> Result<Token, LoxError> {
+        if self.at_reserved_word() {
+            return Err(self.reserved_word_error());
+        }
+        return self.consume(TokenType::IDENTIFIER, code, &[]).cloned();
+    }
+
+    fn at_reserved_word(&self) -> bool {
+        let token = self.peek();
+        return token.token_type != TokenType::IDENTIFIER
+            && IDENT_MAP.contains_key(token.lexeme.as_str());
+    }
+
+    fn reserved_word_error(&mut self) -> LoxError {
+        let word = self.peek().lexeme.clone();
+        return self.err("reserved-word", &[("word", &word)]);
+    }
+
+    ///the token just consumed, comments in between are passed over
+    pub fn previous(&self) -> &Token {
+        let mut i = self.current - 1;
+        while i > 0 && self.scanner.tokens[i].is_trivia() {
+            i -= 1;
+        }
+        return &self.scanner.tokens[i];
     }
 
     pub fn consume_if_type(&mut self, token_types: &[TokenType]) -> bool {
@@ -255,89 +937,118 @@ impl Parser {
         return false;
     }
 
-    pub fn check(&mut self, token_type: TokenType) -> bool {
+    pub fn check(&self, token_type: TokenType) -> bool {
         if self.is_at_end() {
             return false;
         }
         return self.peek().token_type == token_type;
     }
 
-    pub fn is_at_end(&mut self) -> bool {
+    pub fn is_at_end(&self) -> bool {
         return self.peek().token_type == TokenType::EOF;
     }
 
-    pub fn next(&mut self) -> Token {
+    ///moves past the current token, at the end this does nothing
+    pub fn next(&mut self) {
         if self.is_at_end() {
-            return Token {
-                token_type: TokenType::EOF,
-                lexeme: String::new(),
-                literal: TokenLiteral::Empty,
-                line: self.line,
-            };
+            return;
         }
-
         self.current += 1;
-        let tok = self.scanner.tokens[self.current - 1].clone();
-
-        return tok;
+        let line = self.previous().line;
+        self.line = line;
+        crash::at_token(self.current - 1, line);
+        self.skip_trivia();
     }
 
-    pub fn peek(&mut self) -> Token {
-        if self.current >= self.scanner.tokens.len() {
-            return Token {
-                token_type: TokenType::EOF,
-                lexeme: String::new(),
-                literal: TokenLiteral::Empty,
-                line: self.line,
-            };
+    ///moves past comments so the current token is never one, the EOF
+    ///token stops it
+    fn skip_trivia(&mut self) {
+        while self.scanner.tokens[self.current].is_trivia() {
+            self.current += 1;
         }
-
-        return self.scanner.tokens[self.current].clone();
     }
 
-    pub fn peek_next(&mut self) -> Token {
-        if self.is_at_end() {
-            return Token {
-                token_type: TokenType::EOF,
-                lexeme: String::new(),
-                literal: TokenLiteral::Empty,
-                line: self.line,
-            };
-        }
+    ///the current token, `next` stops at the EOF token the scanner ends
+    ///with so there always is one
+    pub fn peek(&self) -> &Token {
+        return &self.scanner.tokens[self.current];
+    }
 
-        return self.scanner.tokens[self.current].clone();
+    ///the token after the current one, EOF again at the end
+    pub fn peek_next(&self) -> &Token {
+        return self.scanner.tokens[self.current + 1..]
+            .iter()
+            .find(|token| !token.is_trivia())
+            .unwrap_or(self.peek());
     }
 
-    pub fn consume(&mut self, token_type: TokenType, err_msg: String) -> Result<Token, LoxError> {
+    ///consumes a token of `token_type`, otherwise reports the catalog
+    ///message `code` at the current token
+    pub fn consume(
+        &mut self,
+        token_type: TokenType,
+        code: &'static str,
+        args: &[(&str, &dyn Display)],
+    ) -> Result<&Token, LoxError> {
         if self.check(token_type) {
-            return Ok(self.next());
+            self.next();
+            return Ok(self.previous());
         }
-        let cur_token = self.peek();
-        self.err(cur_token, err_msg.clone());
-
-        return Err(LoxError::ParseError(ParserError::Generic(
-            err_msg.to_string(),
-        )));
+        return Err(self.err(code, args));
     }
 
-    pub fn err(&mut self, token: Token, msg: String) {
+    ///reports the catalog message `code` at the current token and returns
+    ///it as an error
+    pub fn err(&mut self, code: &'static str, args: &[(&str, &dyn Display)]) -> LoxError {
         self.had_error = true;
-        if token.token_type == TokenType::EOF {
-            self.report(token.line, "at end".to_string(), msg.to_string());
-        } else {
-            self.report(
-                token.line,
-                format!("at '{}'", token.lexeme),
-                msg.to_string(),
-            );
+        let msg = message(code, args);
+        let token = self.peek().clone();
+        if !self.cut_short(&token) {
+            let location = self.location(&token);
+            self.report(&token, location, code, msg.clone());
+        }
+        return LoxError::ParseError(ParserError::Generic(msg));
+    }
+
+    ///whether `token` is the end of a script an unterminated string or
+    ///comment ran into, the scan error already explains what's missing
+    fn cut_short(&self, token: &Token) -> bool {
+        let end = self.scanner.source.len();
+        return token.token_type == TokenType::EOF
+            && self
+                .scanner
+                .diagnostics
+                .last()
+                .is_some_and(|error| error.span.end == end);
+    }
+
+    ///skips past the statement that failed to parse so the ones after it
+    ///are still checked
+    ///skips a `{` and everything up to its matching `}`, leaving the `}`
+    ///for `synchronize` so what's inside doesn't report errors of its own,
+    ///`synchronize` in turn stops before a `{` as it starts a statement,
+    ///an unmatched `{` is left as it is
+    fn skip_braces(&mut self) {
+        let (current, line) = (self.current, self.line);
+        let mut depth = 0;
+        while !self.is_at_end() {
+            match self.peek().token_type {
+                TokenType::LEFT_BRACE => depth += 1,
+                TokenType::RIGHT_BRACE if depth == 1 => return,
+                TokenType::RIGHT_BRACE => depth -= 1,
+                _ => {}
+            }
+            self.next();
         }
-        self.synchronize();
+        (self.current, self.line) = (current, line);
     }
 
     fn synchronize(&mut self) {
         self.next();
         while !self.is_at_end() {
-            println!("Skipping {:?}", self.peek().token_type);
+            if self.trace {
+                eprintln!("Skipping {:?}", self.peek().token_type);
+            }
             if self.previous().token_type == TokenType::SEMICOLON {
                 return;
             }
@@ -349,7 +1060,8 @@ impl Parser {
                 | TokenType::IF
                 | TokenType::WHILE
                 | TokenType::PRINT
-                | TokenType::RETURN => {
+                | TokenType::RETURN
+                | TokenType::LEFT_BRACE => {
                     return;
                 }
                 _ => self.next(),
@@ -357,7 +1069,98 @@ impl Parser {
         }
     }
 
-    pub fn report(&self, line: usize, loc: String, msg: String) {
-        eprintln!("[line {}] Error {}: {}", line, loc, msg);
+    ///`code` is the catalog entry `msg` was rendered from
+    pub fn report(&mut self, token: &Token, loc: String, code: &'static str, msg: String) {
+        let code = messages::code(code);
+        let args: &[(&str, &dyn Display)] = &[
+            ("code", &code.unwrap_or_default()),
+            ("location", &loc),
+            ("message", &msg),
+        ];
+        let diagnostic = Diagnostic {
+            line: token.line,
+            column: token.column,
+            span: token.span(),
+            code,
+            message: message("error-at", args),
+        };
+        if !self.silent {
+            let mut rendered = paint(Style::Error, &diagnostic.to_string());
+            // synthesized tokens have nothing to point at
+            if token.column > 0 {
+                let source = self.scanner.source.as_str();
+                rendered.push('\n');
+                let first_line = self.scanner.first_line;
+                rendered.push_str(&snippet::render_from(
+                    source,
+                    first_line,
+                    token.span(),
+                    &msg,
+                ));
+            }
+            self.sink.report(Severity::Error, &diagnostic, &rendered);
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    ///`code` is the catalog entry `msg` was rendered from
+    pub fn warn(&mut self, token: &Token, code: &'static str, msg: String) {
+        let warning = Diagnostic {
+            line: token.line,
+            column: token.column,
+            span: token.span(),
+            code: messages::code(code),
+            message: msg,
+        };
+        match self.warning_level {
+            WarningLevel::Allow => {}
+            WarningLevel::Warn if !self.silent => {
+                let args: &[(&str, &dyn Display)] = &[
+                    ("code", &warning.code.unwrap_or_default()),
+                    ("lexeme", &token.lexeme),
+                    ("message", &warning.message),
+                ];
+                let rendered = format!(
+                    "[{}] {}",
+                    Position(token.line, token.column),
+                    message("warning-at", args)
+                );
+                let rendered = paint(Style::Warning, &rendered);
+                self.sink.report(Severity::Warning, &warning, &rendered);
+            }
+            WarningLevel::Warn => {}
+            WarningLevel::Deny => {
+                self.report(
+                    token,
+                    message("location-token", &[("lexeme", &token.lexeme)]),
+                    code,
+                    warning.message.clone(),
+                );
+                self.had_error = true;
+            }
+        }
+        self.warnings.push(warning);
+    }
+}
+
+///scans and parses `source` without running it, for editors that check
+///every keystroke, returns each error with the byte range it covers,
+///the source isn't copied and each statement is dropped once it parsed,
+///nothing is resolved or rendered
+pub fn validate(source: &str) -> Vec<(Range<usize>, String)> {
+    let mut parser = Parser::new();
+    parser.silent = true;
+    parser.scan(Source::Borrowed(source));
+    while !parser.is_at_end() {
+        if parser.declaration().is_err() {
+            parser.synchronize();
+        }
     }
+    return parser
+        .scanner
+        .diagnostics
+        .into_iter()
+        .chain(parser.diagnostics)
+        .map(|diagnostic| (diagnostic.span, diagnostic.message))
+        .collect();
 }
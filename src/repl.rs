@@ -0,0 +1,261 @@
+#[cfg(feature = "json")]
+use std::path::Path;
+
+#[cfg(feature = "json")]
+use crate::snapshot;
+use crate::{
+    ast::{LoxError, TokenLiteral},
+    color::{paint, Style},
+    config::LoxConfig,
+    interpreter::{Output, OutputEvent},
+    lox::{Parsed, Session},
+    messages::message,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum OutputMode {
+    ///values of bare expressions are echoed, everything else goes to
+    ///stdout/stderr as it happens
+    #[default]
+    Plain,
+    ///one JSON object per line on stdout for every result, print and
+    ///diagnostic, nothing is written to stderr
+    #[cfg(feature = "json")]
+    Json,
+    ///like plain without echoing values
+    Quiet,
+}
+
+impl OutputMode {
+    pub fn from_name(name: &str) -> Result<OutputMode, String> {
+        return match name {
+            "plain" => Ok(OutputMode::Plain),
+            #[cfg(feature = "json")]
+            "json" => Ok(OutputMode::Json),
+            #[cfg(not(feature = "json"))]
+            "json" => Err("rlox was built without the json feature".to_string()),
+            "quiet" => Ok(OutputMode::Quiet),
+            other => Err(format!("Unknown output mode '{}'", other)),
+        };
+    }
+
+    ///whether events are recorded to be written as JSON instead of being
+    ///printed as they happen
+    pub fn records(self) -> bool {
+        #[cfg(feature = "json")]
+        return self == OutputMode::Json;
+        #[cfg(not(feature = "json"))]
+        return false;
+    }
+}
+
+///something a single input produced, in the order it happened
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Result(TokenLiteral),
+    Print(String),
+    Diagnostic {
+        severity: &'static str,
+        line: Option<usize>,
+        ///only known for problems the scanner or parser found
+        column: Option<usize>,
+        ///the stable code of the problem, like `R3001`
+        code: Option<&'static str>,
+        message: String,
+    },
+}
+
+pub struct Repl {
+    pub session: Session,
+    pub mode: OutputMode,
+}
+
+impl Repl {
+    pub fn new(config: &LoxConfig, mode: OutputMode) -> Repl {
+        let mut session = Session::new(config);
+        if mode.records() {
+            session.silent = true;
+            session.interpreter.output = Output::Captured(vec![]);
+        }
+        return Repl { session, mode };
+    }
+
+    ///runs one line of input, returning the lines to write to stdout that
+    ///weren't printed already, an error ends the session
+    pub fn eval_line(&mut self, line: String) -> Result<Vec<String>, LoxError> {
+        let (events, result) = self.eval(line);
+        let mut out = vec![];
+        for event in events {
+            match (self.mode, event) {
+                (OutputMode::Plain, Event::Result(value)) => out.push(value.to_string()),
+                #[cfg(feature = "json")]
+                (OutputMode::Json, event) => out.push(event.to_json()),
+                _ => {}
+            }
+        }
+        return result.map(|_| out);
+    }
+
+    ///runs one line of input and collects what it produced, input without
+    ///a trailing ';' is evaluated as an expression and input starting with
+    ///':' is a command
+    pub fn eval(&mut self, line: String) -> (Vec<Event>, Result<(), LoxError>) {
+        let mut events = vec![];
+        // the raw line keeps its newline so the scanner's line counter
+        // carries over between inputs
+        let input = line.trim();
+        if let Some(command) = input.strip_prefix(':') {
+            self.command(&mut events, command);
+            self.session.line += line.matches('\n').count();
+            return (events, Ok(()));
+        }
+        let (result, parsed) = if !input.is_empty() && !input.ends_with(';') {
+            let (value, parsed) = self.session.evaluate(line);
+            match value {
+                Ok(value) => events.push(Event::Result(value)),
+                Err(err) => self.report(&mut events, &err, &parsed),
+            }
+            (Ok(()), parsed)
+        } else {
+            let (result, parsed) = self.session.run(line);
+            if let Err(err) = &result {
+                self.report(&mut events, err, &parsed);
+            }
+            (result, parsed)
+        };
+
+        let mut collected = vec![];
+        for diagnostic in parsed.diagnostics {
+            collected.push(Event::Diagnostic {
+                severity: "error",
+                line: Some(diagnostic.line),
+                column: Some(diagnostic.column).filter(|column| *column > 0),
+                code: diagnostic.code,
+                message: diagnostic.message,
+            });
+        }
+        for warning in parsed.warnings {
+            collected.push(Event::Diagnostic {
+                severity: "warning",
+                line: Some(warning.line),
+                column: Some(warning.column).filter(|column| *column > 0),
+                code: warning.code,
+                message: warning.message,
+            });
+        }
+        for event in self.session.interpreter.take_output() {
+            collected.push(match event {
+                OutputEvent::Print(text) => Event::Print(text),
+                OutputEvent::Error {
+                    line,
+                    code,
+                    message,
+                } => Event::Diagnostic {
+                    severity: "error",
+                    line,
+                    column: None,
+                    code,
+                    message,
+                },
+            });
+        }
+        // parsing and running happen before the final result or error
+        collected.append(&mut events);
+        return (collected, result);
+    }
+
+    ///`:save <file>` writes the globals to a session file that
+    ///`rlox repl --restore <file>` starts from
+    fn command(&mut self, events: &mut Vec<Event>, command: &str) {
+        let (name, arg) = match command.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (command, ""),
+        };
+        let result: Result<(), String> = match (name, arg) {
+            #[cfg(feature = "json")]
+            ("save", path) if !path.is_empty() => {
+                snapshot::save_file(&self.session.interpreter, Path::new(path))
+                    .map_err(|err| err.to_string())
+            }
+            #[cfg(not(feature = "json"))]
+            ("save", path) if !path.is_empty() => {
+                Err("rlox was built without the json feature".to_string())
+            }
+            _ => Err(message("unknown-command", &[("name", &name)])),
+        };
+        if let Err(message) = result {
+            self.error(events, message);
+        }
+    }
+
+    ///prints an error in plain modes, json mode records it, unless the
+    ///parser already reported it to the sink and recorded it
+    fn report(&self, events: &mut Vec<Event>, err: &LoxError, parsed: &Parsed) {
+        let recorded = !parsed.diagnostics.is_empty();
+        if matches!(err, LoxError::ScanError(_)) || recorded {
+            return;
+        }
+        // runtime errors are `ParseError`s too, the wrapper's "Parser
+        // error" prefix would mislabel them
+        let text = match err {
+            LoxError::ParseError(e) => e.to_string(),
+            err => err.to_string(),
+        };
+        if !self.mode.records() {
+            eprintln!("> {}", paint(Style::Error, &text));
+            return;
+        }
+        events.push(Event::Diagnostic {
+            severity: "error",
+            line: Some(parsed.line),
+            column: None,
+            code: err.code(),
+            message: text,
+        });
+    }
+
+    ///an error that isn't tied to a line of the script
+    fn error(&self, events: &mut Vec<Event>, message: String) {
+        if !self.mode.records() {
+            eprintln!("> {}", paint(Style::Error, &message));
+            return;
+        }
+        events.push(Event::Diagnostic {
+            severity: "error",
+            line: None,
+            column: None,
+            code: None,
+            message,
+        });
+    }
+}
+
+impl Event {
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        let json = match self {
+            Event::Result(value) => serde_json::json!({
+                "type": "result",
+                "value": serde_json::Value::from(value.clone()),
+                "value_type": value.type_name(),
+                "display": value.to_string(),
+            }),
+            Event::Print(text) => serde_json::json!({ "type": "print", "text": text }),
+            Event::Diagnostic {
+                severity,
+                line,
+                column,
+                code,
+                message,
+            } => serde_json::json!({
+                "type": "diagnostic",
+                "severity": severity,
+                "line": line,
+                "column": column,
+                "code": code,
+                "message": message,
+            }),
+        };
+        return json.to_string();
+    }
+}
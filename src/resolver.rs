@@ -0,0 +1,146 @@
+use crate::{
+    ast::{Expression, Local, MatchArm, Pattern},
+    stmt::Statement,
+};
+
+///stores on every variable use how many scopes lie between it and the
+///match arm or for loop binding it and where in that scope it is, so the
+///interpreter can go straight to the variable, uses that nothing in the
+///program binds are globals and stay unresolved
+pub fn resolve(statements: &mut [Statement]) {
+    let mut resolver = Resolver::default();
+    for statement in statements {
+        resolver.statement(statement);
+    }
+}
+
+pub fn resolve_expression(expr: &mut Expression) {
+    Resolver::default().expression(expr);
+}
+
+#[derive(Default)]
+struct Resolver {
+    ///the names bound by the enclosing arms and loops, innermost last, in
+    ///the scopes and slots they get at runtime
+    scopes: Vec<Vec<String>>,
+}
+
+impl Resolver {
+    fn local(&self, name: &str) -> Option<Local> {
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if let Some(slot) = scope.iter().rposition(|bound| bound == name) {
+                return Some(Local { depth, slot });
+            }
+        }
+        return None;
+    }
+
+    fn statement(&mut self, statement: &mut Statement) {
+        match statement {
+            Statement::Expression(expr, _) | Statement::Print(expr, _) => self.expression(expr),
+            Statement::Var(_, initializer, _) => {
+                if let Some(initializer) = initializer {
+                    self.expression(initializer);
+                }
+            }
+            Statement::Destructure { initializer, .. } => self.expression(initializer),
+            Statement::Assert {
+                condition, message, ..
+            } => {
+                self.expression(condition);
+                if let Some(message) = message {
+                    self.expression(message);
+                }
+            }
+            Statement::ForIn {
+                name,
+                iterable,
+                body,
+                ..
+            } => {
+                self.expression(iterable);
+                self.scopes.push(vec![name.lexeme.clone()]);
+                self.statement(body);
+                self.scopes.pop();
+            }
+            Statement::Dump(_) => {}
+        }
+    }
+
+    ///walks `expr` with a stack of its own, a long chain like `a + b + c`
+    ///nests as deep as it has operators
+    fn expression(&mut self, expr: &mut Expression) {
+        let mut tasks = vec![Task::Resolve(expr)];
+        while let Some(task) = tasks.pop() {
+            let expr = match task {
+                Task::Resolve(expr) => expr,
+                Task::Bind(name) => {
+                    self.scopes.push(vec![name]);
+                    continue;
+                }
+                Task::Unbind => {
+                    self.scopes.pop();
+                    continue;
+                }
+            };
+            match expr {
+                Expression::Variable { name, local } => *local = self.local(&name.lexeme),
+                Expression::Assign { name, value, local } => {
+                    *local = self.local(&name.lexeme);
+                    tasks.push(Task::Resolve(value));
+                }
+                Expression::Binary { left, right, .. }
+                | Expression::Logical { left, right, .. } => {
+                    tasks.push(Task::Resolve(right));
+                    tasks.push(Task::Resolve(left));
+                }
+                Expression::Unary { right, .. } => tasks.push(Task::Resolve(right)),
+                Expression::Grouping(inner, _) => tasks.push(Task::Resolve(inner)),
+                Expression::Match { subject, arms, .. } => {
+                    for MatchArm { pattern, body } in arms.iter_mut().rev() {
+                        if let Pattern::Binding(name) = pattern {
+                            tasks.push(Task::Unbind);
+                            tasks.push(Task::Resolve(body));
+                            tasks.push(Task::Bind(name.lexeme.clone()));
+                        } else {
+                            tasks.push(Task::Resolve(body));
+                        }
+                    }
+                    tasks.push(Task::Resolve(subject));
+                }
+                Expression::ListLiteral { elements, .. }
+                | Expression::TupleLiteral { elements, .. } => {
+                    tasks.extend(elements.iter_mut().rev().map(Task::Resolve));
+                }
+                Expression::MapLiteral { entries, .. } => {
+                    for (key, value) in entries.iter_mut().rev() {
+                        tasks.push(Task::Resolve(value));
+                        tasks.push(Task::Resolve(key));
+                    }
+                }
+                Expression::Index { object, index, .. } => {
+                    tasks.push(Task::Resolve(index));
+                    tasks.push(Task::Resolve(object));
+                }
+                Expression::IndexAssign {
+                    object,
+                    index,
+                    value,
+                    ..
+                } => {
+                    tasks.push(Task::Resolve(value));
+                    tasks.push(Task::Resolve(index));
+                    tasks.push(Task::Resolve(object));
+                }
+                Expression::Literal(_, _) | Expression::Empty => {}
+            }
+        }
+    }
+}
+
+enum Task<'a> {
+    Resolve(&'a mut Expression),
+    ///a match arm binding `name` starts
+    Bind(String),
+    Unbind,
+}
@@ -1,43 +1,178 @@
-use crate::ast::{LoxError, Token, TokenLiteral, TokenType, IDENT_MAP};
+#[cfg(feature = "mmap")]
+use std::sync::Arc;
+use std::{fmt::Display, num::IntErrorKind};
+
+#[cfg(feature = "bigint")]
+use crate::bigint;
+use crate::{
+    ast::{Diagnostic, Token, TokenLiteral, TokenType, IDENT_MAP},
+    messages::{self, message},
+};
+
+///source text the scanner walks over by byte offset
+#[derive(Debug, Clone)]
+pub enum Source<'a> {
+    Owned(String),
+    ///text the caller keeps, for checks that shouldn't copy it
+    Borrowed(&'a str),
+    #[cfg(feature = "mmap")]
+    Mapped(Arc<memmap2::Mmap>),
+}
+
+impl Default for Source<'_> {
+    fn default() -> Self {
+        Source::Owned(String::new())
+    }
+}
+
+impl<'a> Source<'a> {
+    ///reads a script from disk, memory mapping it when the `mmap` feature is
+    ///on, minimal builds can't read files and always fail
+    pub fn read(path: &str) -> std::io::Result<Source<'a>> {
+        #[cfg(feature = "minimal")]
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            message("minimal-read", &[("path", &path)]),
+        ));
+        #[cfg(all(feature = "mmap", not(feature = "minimal")))]
+        return Source::map(path);
+        #[cfg(not(any(feature = "mmap", feature = "minimal")))]
+        return Ok(Source::Owned(std::fs::read_to_string(path)?));
+    }
+
+    ///memory maps a file, validating that it is UTF-8 up front
+    #[cfg(all(feature = "mmap", not(feature = "minimal")))]
+    pub fn map(path: &str) -> std::io::Result<Source<'a>> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: the mapping is read-only and scripts are not expected to be
+        // modified while they are being scanned
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        if let Err(e) = std::str::from_utf8(&map) {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+        }
+        return Ok(Source::Mapped(Arc::new(map)));
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Source::Owned(s) => s,
+            Source::Borrowed(s) => s,
+            // SAFETY: mapped sources are validated as UTF-8 in Source::map
+            #[cfg(feature = "mmap")]
+            Source::Mapped(map) => unsafe { std::str::from_utf8_unchecked(map) },
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        return self.as_str().len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    fn push(&mut self, source: Source<'a>) {
+        if self.is_empty() {
+            *self = source;
+            return;
+        }
+        match self {
+            Source::Owned(s) => s.push_str(source.as_str()),
+            _ => {
+                let mut owned = self.as_str().to_string();
+                owned.push_str(source.as_str());
+                *self = Source::Owned(owned);
+            }
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
-pub struct Scanner {
+pub struct Scanner<'a> {
     pub had_error: bool,
-    pub source: Vec<char>,
+    pub source: Source<'a>,
     pub start: usize,
     pub current: usize,
     pub line: usize,
+    ///byte offset where the current line starts
+    pub line_start: usize,
+    ///line and column the token being scanned starts on, a string can
+    ///end on a later line
+    pub start_line: usize,
+    pub start_column: usize,
+    ///the line `source` starts on, later than 1 when it was set up to
+    ///continue earlier input
+    pub first_line: usize,
+    ///the last offset a column was counted for and its column, so long
+    ///lines are only counted once
+    last_column: (usize, usize),
+    ///always ends with an EOF token, even before anything was loaded
     pub tokens: Vec<Token>,
+    pub diagnostics: Vec<Diagnostic>,
 }
 
-impl Default for Scanner {
+impl Default for Scanner<'_> {
     fn default() -> Self {
         Self {
             line: 1,
+            line_start: 0,
+            start_line: 1,
+            start_column: 1,
+            first_line: 1,
+            last_column: (0, 1),
             had_error: false,
-            source: vec![],
+            source: Source::default(),
             start: 0,
             current: 0,
-            tokens: vec![],
+            tokens: vec![Token::default()],
+            diagnostics: vec![],
         }
     }
 }
 
-impl Scanner {
+impl<'a> Scanner<'a> {
     ///loads source and scans it for tokens
-    pub fn load(&mut self, source: Vec<char>) {
-        self.source.extend(source);
+    pub fn load(&mut self, source: &str) {
+        self.load_source(Source::Owned(source.to_string()));
+    }
+
+    ///like `load`, but takes ownership so the first load doesn't copy
+    pub fn load_source(&mut self, source: Source<'a>) {
+        if self.source.is_empty() {
+            self.first_line = self.line;
+        }
+        self.source.push(source);
+        // the newline after a shebang is still scanned so line numbers
+        // don't shift
+        if let Some(line) = shebang(&self.source.as_str()[self.current..]) {
+            self.current += line.len();
+        }
         self.scan_tokens();
     }
 
+    ///scans up to the end of the source and ends the tokens with an EOF
+    ///token, the one a previous load ended with is replaced
     fn scan_tokens(&mut self) {
+        if self
+            .tokens
+            .last()
+            .is_some_and(|token| token.token_type == TokenType::EOF)
+        {
+            self.tokens.pop();
+        }
         while !self.is_at_end() {
             self.start = self.current;
-            self.scan_token().expect("Failed to scan token");
+            self.start_line = self.line;
+            self.start_column = self.column(self.start);
+            // a failed token is recorded as a diagnostic, keep going
+            self.scan_token();
         }
+        let end = self.source.len();
+        let eof = Token::eof(self.line, self.column(end), end);
+        self.tokens.push(eof);
     }
 
-    fn scan_token(&mut self) -> Result<(), LoxError> {
+    fn scan_token(&mut self) {
         let c = self.next();
 
         match c {
@@ -49,21 +184,47 @@ impl Scanner {
 
             '}' => self.add_token(TokenType::RIGHT_BRACE, TokenLiteral::Empty),
 
+            '[' => self.add_token(TokenType::LEFT_BRACKET, TokenLiteral::Empty),
+
+            ']' => self.add_token(TokenType::RIGHT_BRACKET, TokenLiteral::Empty),
+
+            ':' => self.add_token(TokenType::COLON, TokenLiteral::Empty),
+
             ',' => self.add_token(TokenType::COMMA, TokenLiteral::Empty),
 
             '.' => self.add_token(TokenType::DOT, TokenLiteral::Empty),
 
-            '-' => self.add_token(TokenType::MINUS, TokenLiteral::Empty),
+            '-' => {
+                let tok_type = if self.consume_if_next('=') {
+                    TokenType::MINUS_EQUAL
+                } else {
+                    TokenType::MINUS
+                };
+                self.add_token(tok_type, TokenLiteral::Empty);
+            }
 
-            '+' => self.add_token(TokenType::PLUS, TokenLiteral::Empty),
+            '+' => {
+                let tok_type = if self.consume_if_next('=') {
+                    TokenType::PLUS_EQUAL
+                } else {
+                    TokenType::PLUS
+                };
+                self.add_token(tok_type, TokenLiteral::Empty);
+            }
 
             ';' => self.add_token(TokenType::SEMICOLON, TokenLiteral::Empty),
 
+            '?' if self.consume_if_next('?') => {
+                self.add_token(TokenType::QUESTION_QUESTION, TokenLiteral::Empty)
+            }
+
             '^' => self.add_token(TokenType::EXPONENT, TokenLiteral::Empty),
 
             '*' => {
                 let tok_type = if self.consume_if_next('*') {
                     TokenType::EXPONENT
+                } else if self.consume_if_next('=') {
+                    TokenType::STAR_EQUAL
                 } else {
                     TokenType::STAR
                 };
@@ -82,6 +243,8 @@ impl Scanner {
             '=' => {
                 let tok_type = if self.consume_if_next('=') {
                     TokenType::EQUAL_EQUAL
+                } else if self.consume_if_next('>') {
+                    TokenType::FAT_ARROW
                 } else {
                     TokenType::EQUAL
                 };
@@ -111,6 +274,8 @@ impl Scanner {
                     self.comment();
                 } else if self.consume_if_next('*') {
                     self.block_comment();
+                } else if self.consume_if_next('=') {
+                    self.add_token(TokenType::SLASH_EQUAL, TokenLiteral::Empty)
                 } else {
                     self.add_token(TokenType::SLASH, TokenLiteral::Empty)
                 }
@@ -124,34 +289,46 @@ impl Scanner {
 
             '\n' => {
                 self.line += 1;
+                self.line_start = self.current;
             }
 
             c => {
-                if c.is_digit(10) {
+                if c.is_ascii_digit() {
                     self.number();
-                } else if c.is_alphabetic() {
+                } else if c.is_alphabetic() || c == '_' {
                     self.identifier();
                 } else {
-                    self.err(self.line, &format!("Unexpected character: {}", c));
-                    return Err(LoxError::ScanError(c));
+                    self.err(self.line, "unexpected-character", &[("char", &c)]);
                 }
             }
         };
-        return Ok(());
     }
 
     fn consume_if_next(&mut self, c: char) -> bool {
         if self.is_at_end() {
             return false;
         }
-        if self.source[self.current] != c {
+        if self.peek() != c {
             return false;
         }
-        self.current += 1;
+        self.current += c.len_utf8();
         return true;
     }
 
     fn number(&mut self) {
+        if self.text(self.start, self.current) == "0" {
+            let radix = match self.peek() {
+                'x' | 'X' => Some(16),
+                'o' | 'O' => Some(8),
+                'b' | 'B' => Some(2),
+                _ => None,
+            };
+            if let Some(radix) = radix {
+                self.next();
+                self.radix_number(radix);
+                return;
+            }
+        }
         let mut is_float = false;
 
         while self.peek().is_ascii_digit() {
@@ -169,40 +346,72 @@ impl Scanner {
             self.add_token(
                 TokenType::NUMBER,
                 TokenLiteral::Float(
-                    self.source[self.start..self.current]
-                        .iter()
-                        .collect::<String>()
+                    self.text(self.start, self.current)
                         .parse()
                         .unwrap_or_default(),
                 ),
             );
         } else {
-            self.add_token(
-                TokenType::NUMBER,
-                TokenLiteral::Integer(
-                    self.source[self.start..self.current]
-                        .iter()
-                        .collect::<String>()
-                        .parse()
-                        .unwrap_or_default(),
-                ),
-            );
+            let literal = self.text(self.start, self.current).to_string();
+            match literal.parse() {
+                Ok(value) => self.add_token(TokenType::NUMBER, TokenLiteral::Integer(value)),
+                Err(_) => self.too_large(literal, 10),
+            }
+        }
+    }
+
+    ///an integer literal past isize, kept exactly with the bigint feature
+    #[cfg_attr(not(feature = "bigint"), allow(unused_variables))]
+    fn too_large(&mut self, literal: String, radix: u32) {
+        #[cfg(feature = "bigint")]
+        {
+            let digits = if radix == 10 { &literal } else { &literal[2..] };
+            if let Some(value) = bigint::parse(digits, radix) {
+                self.add_token(TokenType::NUMBER, value);
+                return;
+            }
+        }
+        self.err(self.line, "number-too-large", &[("literal", &literal)]);
+        self.placeholder_number();
+    }
+
+    ///stands in for a number that failed to scan so the parser doesn't
+    ///report a missing expression on top of the scan error
+    fn placeholder_number(&mut self) {
+        self.add_token(TokenType::NUMBER, TokenLiteral::Integer(0));
+    }
+
+    ///`0xFF`, `0o755` and `0b1010`, the whole alphanumeric run is part of
+    ///the literal so a bad digit is one error instead of a stray identifier
+    fn radix_number(&mut self, radix: u32) {
+        while self.peek().is_alphanumeric() || self.peek() == '_' {
+            self.next();
+        }
+        let literal = self.text(self.start, self.current).to_string();
+        match isize::from_str_radix(&literal[2..], radix) {
+            Ok(value) => self.add_token(TokenType::NUMBER, TokenLiteral::Integer(value)),
+            Err(e) if *e.kind() == IntErrorKind::PosOverflow => self.too_large(literal, radix),
+            Err(_) => {
+                self.err(self.line, "malformed-number", &[("literal", &literal)]);
+                self.placeholder_number();
+            }
         }
     }
 
     fn identifier(&mut self) {
-        while self.peek().is_alphanumeric() {
+        while self.peek().is_alphanumeric() || self.peek() == '_' {
             self.next();
         }
 
-        let ident: String = self.source[self.start..self.current].iter().collect();
+        let ident = self.text(self.start, self.current);
 
-        match IDENT_MAP.get(&ident) {
+        match IDENT_MAP.get(ident) {
             Some(idm) => {
                 self.add_token(*idm, TokenLiteral::Empty);
             }
             None => {
-                self.add_token(TokenType::IDENTIFIER,TokenLiteral::String(ident));
+                let ident = ident.to_string();
+                self.add_token(TokenType::IDENTIFIER, TokenLiteral::String(ident));
             }
         };
     }
@@ -211,19 +420,19 @@ impl Scanner {
         while self.peek() != c && !self.is_at_end() {
             if self.peek() == '\n' {
                 self.line += 1;
+                self.line_start = self.current + 1;
             }
             self.next();
         }
+        // reported on the line the string opened on, without a token
         if self.is_at_end() {
-            println!("{}", &self.source.iter().collect::<String>());
-            self.err(self.line, "Unterminated string");
+            self.err(self.start_line, "unterminated-string", &[]);
+            return;
         }
 
         self.next();
 
-        let lit = self.source[self.start + 1..self.current - 1]
-            .iter()
-            .collect::<String>();
+        let lit = self.text(self.start + 1, self.current - 1).to_string();
         self.add_token(TokenType::STRING, TokenLiteral::String(lit));
     }
 
@@ -233,49 +442,71 @@ impl Scanner {
         }
         self.add_token(
             TokenType::COMMENT,
-            TokenLiteral::String(
-                self.source[self.start + 2..self.current]
-                    .iter()
-                    .collect::<String>(),
-            ),
+            TokenLiteral::String(self.text(self.start + 2, self.current).to_string()),
         );
     }
 
+    ///`/* ... */`, comments nest so code that already has comments can be
+    ///commented out, one left open is reported on the line it opened on
     fn block_comment(&mut self) {
-        while self.peek() != '*' && self.peek_next() == '/' && self.is_at_end() {
-            self.next();
-        }
-        if self.is_at_end() {
-            self.err(self.line, "Unterminated block comment!");
-            return;
+        let mut depth = 1;
+        while depth > 0 {
+            if self.is_at_end() {
+                self.err(self.start_line, "unterminated-block-comment", &[]);
+                return;
+            }
+            match self.next() {
+                '/' if self.consume_if_next('*') => depth += 1,
+                '*' if self.consume_if_next('/') => depth -= 1,
+                '\n' => {
+                    self.line += 1;
+                    self.line_start = self.current;
+                }
+                _ => {}
+            }
         }
-        self.current += 2;
         self.add_token(
             TokenType::BLOCK_COMMENT,
-            TokenLiteral::String(
-                self.source[self.start + 2..self.current - 2]
-                    .iter()
-                    .collect::<String>(),
-            ),
+            TokenLiteral::String(self.text(self.start + 2, self.current - 2).to_string()),
         );
     }
 
     fn add_token(&mut self, token_type: TokenType, literal: TokenLiteral) {
-        let text = &self.source[self.start..self.current];
+        let lexeme = self.text(self.start, self.current).to_string();
         self.tokens.push(Token {
             token_type,
-            lexeme: text.iter().collect(),
+            lexeme,
             literal,
-            line: self.line,
+            line: self.start_line,
+            column: self.start_column,
+            offset: self.start,
         });
     }
 
+    ///1-based column of byte `offset`, which is on the current line,
+    ///counted in chars
+    pub fn column(&mut self, offset: usize) -> usize {
+        let (from, column) = match self.last_column {
+            (at, column) if at >= self.line_start && at <= offset => (at, column),
+            _ => (self.line_start, 1),
+        };
+        let column = column + self.text(from, offset).chars().count();
+        self.last_column = (offset, column);
+        return column;
+    }
+
+    ///source between two byte offsets, both always on char boundaries
+    fn text(&self, start: usize, end: usize) -> &str {
+        return &self.source.as_str()[start..end];
+    }
+
     fn next(&mut self) -> char {
-        if self.current >= self.source.len() {
-            return 0x00 as char;
+        if self.is_at_end() {
+            return '\0';
         }
-        self.current += 1;
-        return self.source[self.current - 1];
+        let c = self.peek();
+        self.current += c.len_utf8();
+        return c;
     }
 
     fn is_at_end(&self) -> bool {
@@ -283,24 +514,52 @@ impl Scanner {
     }
 
     fn peek(&self) -> char {
-        if self.is_at_end() {
-            return 0x00.into();
-        }
-        return self.source[self.current];
+        return self.char_at(self.current);
     }
 
     fn peek_next(&self) -> char {
-        if self.current + 1 >= self.source.len() {
-            return 0x00.into();
+        if self.is_at_end() {
+            return '\0';
         }
-        return self.source[self.current + 1];
+        return self.char_at(self.current + self.peek().len_utf8());
     }
 
-    fn err(&mut self, line: usize, msg: &str) {
-        self.report(line, "", msg)
+    ///decodes the char starting at byte `offset`, ASCII skips UTF-8 decoding
+    fn char_at(&self, offset: usize) -> char {
+        let source = self.source.as_str();
+        return match source.as_bytes().get(offset) {
+            None => '\0',
+            Some(byte) if byte.is_ascii() => *byte as char,
+            Some(_) => source[offset..].chars().next().unwrap_or('\0'),
+        };
     }
-    fn report(&mut self, line: usize, loc: &str, msg: &str) {
-        eprintln!("[line: {}] Error {}: {}", line, loc, msg);
+
+    ///records the catalog message `code` against the token being scanned,
+    ///nothing is printed, the parser reports scan errors
+    fn err(&mut self, line: usize, code: &'static str, args: &[(&str, &dyn Display)]) {
         self.had_error = true;
+        self.diagnostics.push(Diagnostic {
+            line,
+            column: self.start_column,
+            span: self.start..self.current,
+            code: messages::code(code),
+            message: message(code, args),
+        });
     }
 }
+
+///a leading `#!/usr/bin/env rlox` line without its newline, it lets
+///scripts be executable and isn't Lox
+pub fn shebang(source: &str) -> Option<&str> {
+    if !source.starts_with("#!") {
+        return None;
+    }
+    return source.lines().next();
+}
+
+///scans `source` on its own scanner, returning the tokens and diagnostics
+pub fn scan_to_vec(source: &str) -> (Vec<Token>, Vec<Diagnostic>) {
+    let mut scanner = Scanner::default();
+    scanner.load(source);
+    return (scanner.tokens, scanner.diagnostics);
+}
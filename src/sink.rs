@@ -0,0 +1,73 @@
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::ast::Diagnostic;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+///receives the errors and warnings the scanner, parser and interpreter
+///find, as they find them
+pub trait DiagnosticSink: Send {
+    ///`rendered` is `diagnostic` the way rlox prints it, followed by the
+    ///source line it points at when that's known
+    fn report(&mut self, severity: Severity, diagnostic: &Diagnostic, rendered: &str);
+}
+
+///the default, prints to stderr
+pub struct Stderr;
+
+impl DiagnosticSink for Stderr {
+    fn report(&mut self, _: Severity, _: &Diagnostic, rendered: &str) {
+        eprintln!("{}", rendered);
+    }
+}
+
+///keeps every diagnostic for embedders that show them their own way,
+///clones keep into the same list
+#[derive(Debug, Clone, Default)]
+pub struct Collector(Arc<Mutex<Vec<(Severity, Diagnostic)>>>);
+
+impl Collector {
+    ///the diagnostics reported so far, leaving the list empty
+    pub fn take(&self) -> Vec<(Severity, Diagnostic)> {
+        return std::mem::take(&mut self.0.lock().unwrap_or_else(PoisonError::into_inner));
+    }
+}
+
+impl DiagnosticSink for Collector {
+    fn report(&mut self, severity: Severity, diagnostic: &Diagnostic, _: &str) {
+        let mut diagnostics = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        diagnostics.push((severity, diagnostic.clone()));
+    }
+}
+
+///the sink a parser or interpreter reports to, clones report to the same
+///place so a session's parsers and interpreter can share one
+#[derive(Clone)]
+pub struct SharedSink(Arc<Mutex<dyn DiagnosticSink>>);
+
+impl SharedSink {
+    pub fn new(sink: impl DiagnosticSink + 'static) -> SharedSink {
+        return SharedSink(Arc::new(Mutex::new(sink)));
+    }
+
+    pub fn report(&self, severity: Severity, diagnostic: &Diagnostic, rendered: &str) {
+        let mut sink = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        sink.report(severity, diagnostic, rendered);
+    }
+}
+
+impl Default for SharedSink {
+    fn default() -> Self {
+        return SharedSink::new(Stderr);
+    }
+}
+
+impl std::fmt::Debug for SharedSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SharedSink")
+    }
+}
@@ -0,0 +1,162 @@
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+#[cfg(feature = "bigint")]
+use crate::bigint;
+use crate::{
+    ast::{LoxError, MapEntries, MapKey, SharedMap, TokenLiteral},
+    interpreter::Interpreter,
+    messages::message,
+};
+
+///bumped whenever the format changes so older files are rejected rather
+///than misread
+pub const VERSION: u64 = 1;
+
+///the globals of `interpreter` as a session file, values come back with
+///the same types, only a list or map several globals share is restored
+///as separate copies
+pub fn save(interpreter: &Interpreter) -> Result<String, String> {
+    let mut globals = vec![];
+    for (name, value) in interpreter.globals() {
+        let value =
+            encode(&value, &mut vec![]).ok_or(message("session-cycle", &[("name", &name)]))?;
+        globals.push(json!({ "name": name, "value": value }));
+    }
+    let session = json!({ "version": VERSION, "globals": globals });
+    return Ok(serde_json::to_string_pretty(&session).unwrap_or_default());
+}
+
+///defines every global saved in `source`, replacing globals of the same
+///name, nothing is defined unless the whole session is valid
+pub fn restore(interpreter: &mut Interpreter, source: &str) -> Result<usize, String> {
+    let session: Value = serde_json::from_str(source).map_err(|e| e.to_string())?;
+    if session["version"].as_u64() != Some(VERSION) {
+        let args: &[(&str, &dyn std::fmt::Display)] =
+            &[("version", &session["version"]), ("expected", &VERSION)];
+        return Err(message("session-version", args));
+    }
+    let saved = session["globals"]
+        .as_array()
+        .ok_or(message("session-format", &[]))?;
+    let mut globals = vec![];
+    for global in saved {
+        let (Some(name), Some(value)) = (global["name"].as_str(), global.get("value")) else {
+            return Err(message("session-format", &[]));
+        };
+        let value = decode(value).ok_or(message("session-value", &[("name", &name)]))?;
+        globals.push((name, value));
+    }
+    let count = globals.len();
+    for (name, value) in globals {
+        interpreter.set_global(name, value);
+    }
+    return Ok(count);
+}
+
+pub fn save_file(interpreter: &Interpreter, path: &Path) -> Result<(), LoxError> {
+    let session = save(interpreter).map_err(|message| LoxError::Session {
+        path: path.display().to_string(),
+        message,
+    })?;
+    std::fs::write(path, session)?;
+    return Ok(());
+}
+
+pub fn restore_file(interpreter: &mut Interpreter, path: &Path) -> Result<usize, LoxError> {
+    let source = std::fs::read_to_string(path)?;
+    return restore(interpreter, &source).map_err(|message| LoxError::Session {
+        path: path.display().to_string(),
+        message,
+    });
+}
+
+///`value` as JSON, the types JSON lacks are objects with a single key
+///naming them, `None` when a list or map contains itself, `open` holds
+///the ones being encoded
+fn encode(value: &TokenLiteral, open: &mut Vec<usize>) -> Option<Value> {
+    let encoded = match value {
+        TokenLiteral::Empty => Value::Null,
+        TokenLiteral::Boolean(b) => Value::Bool(*b),
+        TokenLiteral::Integer(i) => Value::from(*i),
+        TokenLiteral::Float(f) if f.is_finite() => Value::from(*f),
+        // JSON has no infinities or NaN
+        TokenLiteral::Float(f) => json!({ "float": f.to_string() }),
+        TokenLiteral::String(s) => Value::String(s.clone()),
+        #[cfg(feature = "bigint")]
+        TokenLiteral::BigInt(n) => json!({ "bigint": n.to_string() }),
+        TokenLiteral::Tuple(elements) => json!({ "tuple": encode_all(elements.iter(), open)? }),
+        TokenLiteral::List(list) => {
+            if open.contains(&list.id()) {
+                return None;
+            }
+            open.push(list.id());
+            let elements = encode_all(list.borrow().iter(), open)?;
+            open.pop();
+            Value::Array(elements)
+        }
+        // keys needn't be strings, so entries are [key, value] pairs
+        TokenLiteral::Map(map) => {
+            if open.contains(&map.id()) {
+                return None;
+            }
+            open.push(map.id());
+            let mut entries = vec![];
+            for (key, value) in map.borrow().iter() {
+                entries.push(json!([
+                    encode(&key.to_literal(), open)?,
+                    encode(value, open)?
+                ]));
+            }
+            open.pop();
+            json!({ "map": entries })
+        }
+    };
+    return Some(encoded);
+}
+
+fn encode_all<'a>(
+    values: impl Iterator<Item = &'a TokenLiteral>,
+    open: &mut Vec<usize>,
+) -> Option<Vec<Value>> {
+    return values.map(|value| encode(value, open)).collect();
+}
+
+fn decode(value: &Value) -> Option<TokenLiteral> {
+    let decoded = match value {
+        Value::Null => TokenLiteral::Empty,
+        Value::Bool(b) => TokenLiteral::Boolean(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => TokenLiteral::Integer(isize::try_from(i).ok()?),
+            None => TokenLiteral::Float(n.as_f64()?),
+        },
+        Value::String(s) => TokenLiteral::String(s.clone()),
+        Value::Array(elements) => TokenLiteral::list(decode_all(elements)?),
+        Value::Object(object) if object.len() == 1 => match object.iter().next()? {
+            (tag, Value::String(f)) if tag == "float" => TokenLiteral::Float(f.parse().ok()?),
+            #[cfg(feature = "bigint")]
+            (tag, Value::String(n)) if tag == "bigint" => bigint::parse(n, 10)?,
+            (tag, Value::Array(elements)) if tag == "tuple" => {
+                TokenLiteral::Tuple(decode_all(elements)?.into())
+            }
+            (tag, Value::Array(entries)) if tag == "map" => {
+                let mut map = MapEntries::default();
+                for entry in entries {
+                    let [key, value] = entry.as_array()?.as_slice() else {
+                        return None;
+                    };
+                    map.insert(MapKey::from_literal(&decode(key)?)?, decode(value)?);
+                }
+                TokenLiteral::Map(SharedMap::new(map))
+            }
+            _ => return None,
+        },
+        Value::Object(_) => return None,
+    };
+    return Some(decoded);
+}
+
+fn decode_all(values: &[Value]) -> Option<Vec<TokenLiteral>> {
+    return values.iter().map(decode).collect();
+}
@@ -0,0 +1,76 @@
+use std::{ops::Range, sync::Arc};
+
+use crate::color::{paint, Style};
+
+///the text of a script kept for quoting in errors, cloning it only bumps
+///a reference count
+#[derive(Clone, Default, PartialEq)]
+pub struct SourceText(Arc<str>);
+
+impl SourceText {
+    pub fn new(source: &str) -> SourceText {
+        return SourceText(source.into());
+    }
+}
+
+impl std::ops::Deref for SourceText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        return &self.0;
+    }
+}
+
+///dumping the interpreter shouldn't print the whole script
+impl std::fmt::Debug for SourceText {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SourceText({} bytes)", self.0.len())
+    }
+}
+
+///the source line `span` starts on, numbered, with `^` under the part of
+///it the span covers followed by `label`, a span running past the end of
+///the line is cut there and an empty one still gets a single caret, the
+///margin and carets are colored when color is installed
+pub fn render(source: &str, span: Range<usize>, label: &str) -> String {
+    return render_from(source, 1, span, label);
+}
+
+///`render` for source that starts on `first_line` rather than line 1,
+///like a REPL input continuing the ones before it
+pub fn render_from(source: &str, first_line: usize, span: Range<usize>, label: &str) -> String {
+    let start = span.start.min(source.len());
+    let Some(before) = source.get(..start) else {
+        return String::new();
+    };
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[start..]
+        .find(['\r', '\n'])
+        .map_or(source.len(), |i| start + i);
+    let end = span.end.clamp(start, line_end);
+
+    let number = (before.matches('\n').count() + first_line).to_string();
+    let gutter = " ".repeat(number.len());
+    // tabs are kept so the carets line up however wide they're shown
+    let indent = source[line_start..start]
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect::<String>();
+    let width = source.get(start..end).map_or(0, |s| s.chars().count());
+
+    let mut carets = "^".repeat(width.max(1));
+    if !label.is_empty() {
+        carets.push(' ');
+        carets.push_str(label);
+    }
+    let margin = paint(Style::Note, &format!("{} |", gutter));
+    return format!(
+        "{}\n{} {}\n{} {}{}",
+        margin,
+        paint(Style::Note, &format!("{} |", number)),
+        &source[line_start..line_end],
+        margin,
+        indent,
+        paint(Style::Error, &carets)
+    );
+}
@@ -1,9 +1,83 @@
+use std::ops::Range;
+
 use crate::ast::{Expression, Token};
 
+///each statement keeps the byte range it was parsed from, including its
+///keyword and ';'
 #[derive(Debug, Clone)]
 pub enum Statement {
-    Expression(Expression),
-    Print(Expression),
-    Dump,
-    Var(Token,Option<Expression>),
+    Expression(Expression, Range<usize>),
+    Print(Expression, Range<usize>),
+    Dump(Range<usize>),
+    Var(Token, Option<Expression>, Range<usize>),
+    ///`var (a, b) = tuple;` and `var [a, b] = list;` need exactly one
+    ///element per name, `var {a, b} = map;` reads the keys "a" and "b"
+    Destructure {
+        kind: DestructureKind,
+        ///the token opening the names, errors are reported at its line
+        open: Token,
+        names: Vec<Token>,
+        initializer: Expression,
+        span: Range<usize>,
+    },
+    ///`for (name in iterable) body`, runs over a snapshot of a list's
+    ///elements or a map's keys in insertion order, `name` is only visible
+    ///in the body
+    ForIn {
+        name: Token,
+        iterable: Expression,
+        body: Box<Statement>,
+        span: Range<usize>,
+    },
+    ///`assert condition, message;`, a falsey condition is a runtime error
+    ///quoting `source`, the message is only evaluated then
+    Assert {
+        keyword: Token,
+        condition: Expression,
+        source: String,
+        message: Option<Expression>,
+        span: Range<usize>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DestructureKind {
+    Tuple,
+    List,
+    Map,
+}
+
+impl DestructureKind {
+    pub fn name(&self) -> &'static str {
+        return match self {
+            DestructureKind::Tuple => "tuple",
+            DestructureKind::List => "list",
+            DestructureKind::Map => "map",
+        };
+    }
+}
+
+impl Statement {
+    ///line of the first token in the statement that carries one
+    pub fn line(&self) -> Option<usize> {
+        return match self {
+            Statement::Expression(expr, _) | Statement::Print(expr, _) => expr.line(),
+            Statement::Var(name, ..) | Statement::ForIn { name, .. } => Some(name.line),
+            Statement::Assert { keyword, .. } => Some(keyword.line),
+            Statement::Destructure { open, .. } => Some(open.line),
+            Statement::Dump(_) => None,
+        };
+    }
+
+    pub fn span(&self) -> Range<usize> {
+        return match self {
+            Statement::Expression(_, span)
+            | Statement::Print(_, span)
+            | Statement::Dump(span)
+            | Statement::Var(_, _, span)
+            | Statement::Destructure { span, .. }
+            | Statement::ForIn { span, .. }
+            | Statement::Assert { span, .. } => span.clone(),
+        };
+    }
 }
@@ -0,0 +1,246 @@
+use crate::{
+    cli::{parse_args, Command},
+    mutate::Mutant,
+};
+
+fn args(args: &[&str]) -> Vec<String> {
+    return args.iter().map(|arg| arg.to_string()).collect();
+}
+
+#[test]
+fn parse_subcommands() {
+    assert_eq!(
+        parse_args(&args(&["script.lox"])),
+        Ok(Command::Run {
+            path: "script.lox".to_string(),
+            stream: false,
+            mutant: None
+        })
+    );
+    assert_eq!(
+        parse_args(&args(&["run", "--stream", "script.lox"])),
+        Ok(Command::Run {
+            path: "script.lox".to_string(),
+            stream: true,
+            mutant: None
+        })
+    );
+    assert_eq!(
+        parse_args(&args(&["run", "script.lox", "--mutant", "plus-as-minus"])),
+        Ok(Command::Run {
+            path: "script.lox".to_string(),
+            stream: false,
+            mutant: Mutant::from_name("plus-as-minus")
+        })
+    );
+    assert_eq!(
+        parse_args(&args(&["run", "script.lox", "--mutant", "plus"])),
+        Err("Unknown mutant 'plus'".to_string())
+    );
+    assert_eq!(
+        parse_args(&args(&["-"])),
+        Ok(Command::Repl {
+            output: Default::default(),
+            restore: None
+        })
+    );
+    assert_eq!(
+        parse_args(&args(&["fmt", "--check", "a.lox", "b.lox"])),
+        Ok(Command::Fmt {
+            paths: args(&["a.lox", "b.lox"]),
+            check: true,
+            max_unformatted: 0,
+            changed_only: false
+        })
+    );
+    assert_eq!(
+        parse_args(&args(&["lint", "--changed-only", "--max-warnings", "4"])),
+        Ok(Command::Lint {
+            paths: vec![],
+            max_warnings: 4,
+            changed_only: true
+        })
+    );
+    assert_eq!(
+        parse_args(&args(&["bench", "-n", "3", "a.lox"])),
+        Ok(Command::Bench {
+            path: "a.lox".to_string(),
+            iterations: 3
+        })
+    );
+    assert_eq!(
+        parse_args(&args(&["test"])),
+        Ok(Command::Test {
+            dir: "tests".to_string()
+        })
+    );
+    assert_eq!(
+        parse_args(&args(&["check", "--help"])),
+        Ok(Command::Help {
+            topic: Some("check".to_string())
+        })
+    );
+}
+
+#[test]
+fn parse_usage_errors() {
+    assert!(parse_args(&[]).is_err());
+    assert!(parse_args(&args(&["run"])).is_err());
+    assert!(parse_args(&args(&["repl", "extra"])).is_err());
+    assert!(parse_args(&args(&["bench", "-n", "0", "a.lox"])).is_err());
+    assert!(parse_args(&args(&["--bogus"])).is_err());
+    assert!(parse_args(&args(&["lint"])).is_err());
+    assert!(parse_args(&args(&["fmt", "--changed-only", "a.lox"])).is_err());
+    assert!(parse_args(&args(&["fmt", "--chek", "a.lox"])).is_err());
+}
+
+#[test]
+fn split_config_flags() {
+    use crate::{
+        ast::Truthiness,
+        cli::split_config_flags,
+        color::ColorChoice,
+        config::{Dialect, Extension, WarningLevel},
+    };
+
+    let (rest, overrides) = split_config_flags(&args(&[
+        "--dialect=strict",
+        "run",
+        "-I",
+        "lib",
+        "a.lox",
+        "--warnings",
+        "deny",
+        "--messages=fr.toml",
+        "--extensions=match,dump",
+        "--strict-truthiness",
+        "--color=never",
+    ]))
+    .expect("Failed to split flags");
+    assert_eq!(rest, args(&["run", "a.lox"]));
+    assert_eq!(overrides.dialect, Some(Dialect::Strict));
+    assert_eq!(overrides.warnings, Some(WarningLevel::Deny));
+    assert_eq!(overrides.messages, Some("fr.toml".into()));
+    assert_eq!(overrides.truthiness, Some(Truthiness::Strict));
+    assert_eq!(overrides.color, ColorChoice::Never);
+    let extensions = overrides.extensions.expect("Extensions not set");
+    assert!(extensions.enabled(Extension::Dump));
+    assert!(!extensions.enabled(Extension::Lists));
+    assert_eq!(
+        overrides.include_paths,
+        vec![std::path::PathBuf::from("lib")]
+    );
+    assert!(split_config_flags(&args(&["run", "a.lox", "--step-limit"])).is_err());
+    assert!(split_config_flags(&args(&["--extensions", "lists,macros"])).is_err());
+    assert!(split_config_flags(&args(&["--color", "sometimes"])).is_err());
+    let (_, overrides) = split_config_flags(&args(&["run", "a.lox"])).unwrap();
+    assert_eq!(overrides.color, ColorChoice::Auto);
+}
+
+#[test]
+fn parse_mutate() {
+    assert_eq!(
+        parse_args(&args(&["mutate", "--min-score", "80", "suite"])),
+        Ok(Command::Mutate {
+            dir: "suite".to_string(),
+            min_score: 80
+        })
+    );
+    assert!(parse_args(&args(&["mutate", "--min-score", "101"])).is_err());
+}
+
+#[test]
+fn parse_repl_output() {
+    use crate::repl::OutputMode;
+
+    #[cfg(feature = "json")]
+    assert_eq!(
+        parse_args(&args(&["repl", "--output", "json"])),
+        Ok(Command::Repl {
+            output: OutputMode::Json,
+            restore: None
+        })
+    );
+    assert_eq!(
+        parse_args(&args(&["repl", "--output=quiet"])),
+        Ok(Command::Repl {
+            output: OutputMode::Quiet,
+            restore: None
+        })
+    );
+    assert!(parse_args(&args(&["repl", "--output", "xml"])).is_err());
+    assert!(parse_args(&args(&["repl", "--restore"])).is_err());
+
+    #[cfg(feature = "json")]
+    assert_eq!(
+        parse_args(&args(&["repl", "--restore", "s.json", "--output=quiet"])),
+        Ok(Command::Repl {
+            output: OutputMode::Quiet,
+            restore: Some("s.json".to_string())
+        })
+    );
+}
+
+#[test]
+fn parse_kernel() {
+    #[cfg(feature = "json")]
+    assert_eq!(parse_args(&args(&["kernel"])), Ok(Command::Kernel));
+    assert!(parse_args(&args(&["kernel", "--port", "5555"])).is_err());
+}
+
+#[test]
+fn parse_messages() {
+    assert_eq!(parse_args(&args(&["messages"])), Ok(Command::Messages));
+    assert!(parse_args(&args(&["messages", "fr"])).is_err());
+}
+
+#[test]
+fn parse_xref() {
+    use crate::xref::Query;
+
+    assert_eq!(
+        parse_args(&args(&["xref", "a.lox", "--find-refs", "count"])),
+        Ok(Command::Xref {
+            path: "a.lox".to_string(),
+            query: Query::References("count".to_string()),
+            json: false,
+        })
+    );
+    assert!(parse_args(&args(&["xref", "a.lox"])).is_err());
+    assert!(parse_args(&args(&[
+        "xref",
+        "a.lox",
+        "--find-def",
+        "a",
+        "--find-refs",
+        "a"
+    ]))
+    .is_err());
+}
+
+#[test]
+fn parse_hash() {
+    assert_eq!(
+        parse_args(&args(&["hash", "--ignore-names", "a.lox", "b.lox"])),
+        Ok(Command::Hash {
+            paths: vec!["a.lox".to_string(), "b.lox".to_string()],
+            ignore_names: true,
+            canonical: false,
+        })
+    );
+    assert!(parse_args(&args(&["hash"])).is_err());
+    assert!(parse_args(&args(&["hash", "--sha", "a.lox"])).is_err());
+}
+
+#[test]
+fn parse_fix() {
+    assert_eq!(
+        parse_args(&args(&["fix", "--apply", "a.lox"])),
+        Ok(Command::Fix {
+            paths: vec!["a.lox".to_string()],
+            apply: true,
+        })
+    );
+    assert!(parse_args(&args(&["fix", "--apply"])).is_err());
+    assert!(parse_args(&args(&["fix", "--all", "a.lox"])).is_err());
+}
@@ -0,0 +1,89 @@
+use crate::{
+    ast::Truthiness,
+    config::{ConfigOverrides, Dialect, Extension, LoxConfig, WarningLevel},
+    formatter::QuoteStyle,
+    interpreter::AssignPolicy,
+};
+
+#[test]
+fn parse_config() {
+    let source = r#"
+# project settings
+dialect = "strict"
+warnings = "deny"  # fail on warnings
+include_paths = ["lib", "vendor/#lox"]
+step_limit = 1_000_000
+messages = "lang/fr.toml"
+extensions = ["integers", "lists"]
+truthiness = "strict"
+
+[format]
+quotes = "double"
+"#;
+    let config = LoxConfig::parse(source).expect("Failed to parse config");
+    assert_eq!(config.dialect, Dialect::Strict);
+    assert_eq!(config.warnings, WarningLevel::Deny);
+    assert_eq!(
+        config.include_paths,
+        vec![
+            std::path::PathBuf::from("lib"),
+            std::path::PathBuf::from("vendor/#lox")
+        ]
+    );
+    assert_eq!(config.step_limit, Some(1_000_000));
+    assert_eq!(config.interpreter().fuel, Some(1_000_000));
+    assert_eq!(config.messages, Some("lang/fr.toml".into()));
+    assert_eq!(config.format.quotes, QuoteStyle::Double);
+    assert_eq!(config.interpreter().assign_policy, AssignPolicy::Strict);
+    assert_eq!(config.parser().warning_level, WarningLevel::Deny);
+    assert!(config.extensions.enabled(Extension::Lists));
+    assert!(!config.extensions.enabled(Extension::Maps));
+    assert!(!config.parser().extensions.enabled(Extension::ForIn));
+    assert!(LoxConfig::default().extensions.enabled(Extension::ForIn));
+    assert_eq!(config.interpreter().truthiness, Truthiness::Strict);
+}
+
+#[test]
+fn parse_config_errors() {
+    assert_eq!(
+        LoxConfig::parse("dialect = \"strict\"\nstep_limt = 5"),
+        Err((2, "Unknown key 'step_limt'".to_string()))
+    );
+    assert!(LoxConfig::parse("[lint]").is_err());
+    assert!(LoxConfig::parse("dialect = strict").is_err());
+    assert!(LoxConfig::parse("warnings = \"loud\"").is_err());
+    assert_eq!(
+        LoxConfig::parse("extensions = [\"lambdas\"]"),
+        Err((1, "Unknown extension 'lambdas'".to_string()))
+    );
+}
+
+#[test]
+fn merge_overrides() {
+    let mut config = LoxConfig::parse("include_paths = [\"lib\"]\nwarnings = \"allow\"")
+        .expect("Failed to parse config");
+    config.merge(&ConfigOverrides {
+        dialect: Some(Dialect::Strict),
+        include_paths: vec!["cli".into()],
+        ..Default::default()
+    });
+    assert_eq!(config.dialect, Dialect::Strict);
+    assert_eq!(config.warnings, WarningLevel::Allow);
+    assert_eq!(
+        config.include_paths,
+        vec![std::path::PathBuf::from("cli"), "lib".into()]
+    );
+}
+
+#[test]
+fn resolve_through_include_paths() {
+    let config = LoxConfig {
+        include_paths: vec!["./tests/golden".into()],
+        ..Default::default()
+    };
+    assert_eq!(
+        config.resolve("quote_styles.lox"),
+        Some("./tests/golden/quote_styles.lox".into())
+    );
+    assert_eq!(config.resolve("missing.lox"), None);
+}
@@ -0,0 +1,70 @@
+use std::{
+    sync::mpsc::{self, RecvTimeoutError},
+    time::Duration,
+};
+
+use crate::{
+    interpreter::{Interpreter, Limits, Output},
+    parser::Parser,
+    scanner::scan_to_vec,
+};
+
+///far more than any corpus file needs, a file that takes longer is stuck
+const TIME_LIMIT: Duration = Duration::from_secs(10);
+
+///scans, parses and runs `source` with limits on and output captured, the
+///statements that parsed are run even when others didn't
+fn exercise(source: &str) {
+    let _ = scan_to_vec(source);
+    let (statements, _) = Parser::new().load_silently(source.to_string());
+    let mut interpreter = Interpreter::new().with_limits(Limits {
+        max_string_length: Some(1 << 16),
+        max_elements: Some(1 << 16),
+        max_memory: Some(1 << 24),
+    });
+    interpreter.output = Output::Captured(vec![]);
+    interpreter.interpret_statements(&statements);
+}
+
+///every tests/corpus/*.lox has to get through `exercise` without panicking
+///and in bounded time, what it prints or reports doesn't matter, the input
+///of every fixed crash belongs here
+#[test]
+fn corpus_runs_without_panicking() {
+    let mut entries = std::fs::read_dir("./tests/corpus")
+        .expect("Failed to read corpus dir")
+        .map(|entry| entry.expect("Failed to read entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lox"))
+        .collect::<Vec<std::path::PathBuf>>();
+    entries.sort();
+    assert!(!entries.is_empty());
+
+    let mut failures = vec![];
+    for path in entries {
+        let source = std::fs::read_to_string(&path).expect("Failed to read source");
+        let (done, finished) = mpsc::channel();
+        // a panic drops `done` without sending, a hang is left running, the
+        // stack is as big as the main thread's so the nesting the parser
+        // allows has to fit like in `rlox run`, a stack overflow still
+        // aborts the whole test binary
+        std::thread::Builder::new()
+            .stack_size(8 << 20)
+            .spawn(move || {
+                exercise(&source);
+                let _ = done.send(());
+            })
+            .expect("Failed to spawn");
+        match finished.recv_timeout(TIME_LIMIT) {
+            Ok(()) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                failures.push(format!("{} panicked", path.display()))
+            }
+            Err(RecvTimeoutError::Timeout) => failures.push(format!(
+                "{} ran for more than {:?}",
+                path.display(),
+                TIME_LIMIT
+            )),
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
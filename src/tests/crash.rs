@@ -0,0 +1,35 @@
+use crate::crash::{self, CrashContext, Phase, Position, Script};
+
+#[test]
+fn crash_report() {
+    let context = CrashContext {
+        script: Script::Input("var a = 1;\nvar b = 2;\nprint a + b;\n".to_string()),
+        position: Position {
+            phase: Phase::Running,
+            line: 2,
+            token: Some(8),
+            statement: Some(1),
+        },
+    };
+    let report = crash::render_report(&context, "boom", "0: main");
+    assert!(report.contains("panic: boom\nphase: Running\nscript: <input>\n"));
+    assert!(report.contains("token index: 8\nstatement index: 1\nline: 2\n"));
+    assert!(report.contains(">    2 | var b = 2;\n"));
+    assert!(report.contains("     3 | print a + b;\n"));
+    assert!(report.ends_with("backtrace:\n0: main\n"));
+}
+
+#[test]
+fn crash_context_tracks_execution() {
+    use crate::lox::Lox;
+
+    crash::set_script(Script::Input("var a = 1;\nprint a;".to_string()));
+    let program = Lox::compile(&mut crate::parser::Parser::new(), "var a = 1;\nprint a;")
+        .expect("Failed to compile");
+    assert_eq!(crash::context().position.phase, Phase::Parsing);
+    program.run(&mut crate::interpreter::Interpreter::new());
+    let position = crash::context().position;
+    assert_eq!(position.phase, Phase::Running);
+    assert_eq!(position.statement, Some(1));
+    assert_eq!(position.line, 2);
+}
@@ -0,0 +1,22 @@
+use crate::diff::unified_diff;
+
+#[test]
+fn diff_hunks() {
+    let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+    let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+    assert_eq!(
+        unified_diff(old, new, "a/x.lox", "b/x.lox"),
+        "--- a/x.lox\n+++ b/x.lox\n\
+         @@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n\
+         @@ -10,3 +10,4 @@\n j\n k\n l\n+m\n"
+    );
+    assert_eq!(unified_diff(old, old, "a", "b"), "");
+}
+
+#[test]
+fn diff_missing_newline() {
+    assert_eq!(
+        unified_diff("print 1;", "print 1;\n", "a", "b"),
+        "--- a\n+++ b\n@@ -1 +1 @@\n-print 1;\n\\ No newline at end of file\n+print 1;\n"
+    );
+}
@@ -0,0 +1,19 @@
+use crate::{config::LoxConfig, fix::fix_source};
+
+#[test]
+fn fix_caret_exponent() {
+    let fixed = fix_source("var a = 2;\nprint a^3 + a ^ 2;\n", &LoxConfig::default())
+        .expect("Failed to fix");
+    assert_eq!(fixed.source, "var a = 2;\nprint a ** 3 + a ** 2;\n");
+    assert_eq!(fixed.applied, vec![("caret-exponent", 2)]);
+}
+
+#[test]
+fn fix_leaves_current_scripts_alone() {
+    let source = "var a=2;\nprint a**3;\n";
+    let fixed = fix_source(source, &LoxConfig::default()).expect("Failed to fix");
+    assert_eq!(fixed.source, source);
+    assert!(fixed.applied.is_empty());
+
+    assert!(fix_source("print a ^;\n", &LoxConfig::default()).is_err());
+}
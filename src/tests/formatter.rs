@@ -0,0 +1,64 @@
+use crate::formatter::{format_source, FormatOptions, QuoteStyle};
+
+#[test]
+fn format_spacing() {
+    let source = "var  x=-1;print x+2*(3-x);\n\n\n// done\nprint !true;  // trailing\n";
+    let formatted = format_source(source, &FormatOptions::default()).expect("Failed to format");
+    assert_eq!(
+        formatted,
+        "var x = -1;\nprint x + 2 * (3 - x);\n\n// done\nprint !true; // trailing\n"
+    );
+    assert_eq!(
+        format_source(&formatted, &FormatOptions::default()),
+        Ok(formatted)
+    );
+}
+
+#[test]
+fn format_lists() {
+    assert_eq!(
+        format_source(
+            "var xs=[ 1,2 ,[]];xs [0]+=xs[ 2 ] [0];",
+            &FormatOptions::default()
+        ),
+        Ok("var xs = [1, 2, []];\nxs[0] += xs[2][0];\n".to_string())
+    );
+    assert_eq!(
+        format_source("var m={ 'a' :1,2:{ } };", &FormatOptions::default()),
+        Ok("var m = { 'a': 1, 2: {} };\n".to_string())
+    );
+    assert_eq!(
+        format_source("for(x in[1]) print x;", &FormatOptions::default()),
+        Ok("for (x in [1]) print x;\n".to_string())
+    );
+}
+
+#[test]
+fn format_quotes() {
+    let options = FormatOptions {
+        quotes: QuoteStyle::Double,
+    };
+    assert_eq!(
+        format_source("print 'a' + 'say \"hi\"';", &options),
+        Ok("print \"a\" + 'say \"hi\"';\n".to_string())
+    );
+    assert!(format_source("print @;", &FormatOptions::default()).is_err());
+}
+
+#[test]
+fn format_keeps_shebang() {
+    let options = FormatOptions::default();
+    assert_eq!(
+        format_source("#!/usr/bin/env rlox\n\nprint  1;", &options),
+        Ok("#!/usr/bin/env rlox\n\nprint 1;\n".to_string())
+    );
+    assert_eq!(
+        format_source("#!/usr/bin/env rlox\nprint 1;", &options),
+        Ok("#!/usr/bin/env rlox\nprint 1;\n".to_string())
+    );
+    assert_eq!(
+        format_source("#!/usr/bin/env rlox", &options),
+        Ok("#!/usr/bin/env rlox\n".to_string())
+    );
+    assert!(format_source("print 1;\n#!/usr/bin/env rlox\n", &options).is_err());
+}
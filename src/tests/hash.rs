@@ -0,0 +1,68 @@
+use crate::{
+    hash::{canonical_form, fnv1a, hash_program},
+    parser::Parser,
+};
+
+fn hash(source: &str, ignore_names: bool) -> u64 {
+    let statements = Parser::new()
+        .load(source.to_string())
+        .expect("Failed to parse");
+    return hash_program(&statements, ignore_names);
+}
+
+#[test]
+fn fnv1a_known_values() {
+    assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+    assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+}
+
+#[test]
+fn hash_ignores_formatting() {
+    let original = hash("var a = 1;\nprint (a ^ 2);\n", false);
+    assert_eq!(original, hash("var a=1; print a**2;", false));
+    assert_eq!(original, hash("var a = 1;\n\n\nprint a ^ (2);", false));
+    assert_ne!(original, hash("var a = 1;\nprint a * 2;\n", false));
+    assert_ne!(original, hash("var b = 1;\nprint b ^ 2;\n", false));
+    assert_eq!(
+        hash("var a = 1;\nprint a ^ 2;\n", true),
+        hash("var b = 1;\nprint b ^ 2;\n", true)
+    );
+}
+
+#[test]
+fn canonical_form_numbers_names() {
+    let statements = Parser::new()
+        .load("var x = 'hi'; y = x;".to_string())
+        .expect("Failed to parse");
+    assert_eq!(
+        canonical_form(&statements, true),
+        "rlox-ast-2\n(var $0 str:2:hi)\n(expr (assign $1 $0))\n"
+    );
+}
+
+#[test]
+fn hash_long_chain() {
+    let source = format!("var x = 1;\nprint x{};\n", " + x".repeat(10_000));
+    let canonical = canonical_form(
+        &Parser::new().load(source.clone()).expect("Failed to parse"),
+        false,
+    );
+    assert!(canonical.ends_with(&format!(
+        "(print {}x{})\n",
+        "(+ ".repeat(10_000),
+        " x)".repeat(10_000)
+    )));
+    assert_eq!(hash(&source, true), hash(&source.replace('x', "y"), true));
+}
+
+#[test]
+fn canonical_form_literals() {
+    let statements = Parser::new()
+        .load("print match 1.5 { Float => -2, nil => !true, \"a)\" => 2 ^ 3 };".to_string())
+        .expect("Failed to parse");
+    assert_eq!(
+        canonical_form(&statements, false),
+        "rlox-ast-2\n(print (match float:3ff8000000000000 (arm Float (- int:2)) \
+         (arm nil (! true)) (arm str:2:a) (** int:2 int:3))))\n"
+    );
+}
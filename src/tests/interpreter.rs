@@ -1,4 +1,8 @@
-
+use crate::{
+    ast::{LoxError, ParserError, TokenLiteral, Truthiness},
+    interpreter::{AssignPolicy, Interpreter, Limits, Output, OutputEvent},
+    stmt::Statement,
+};
 
 #[test]
 fn print() {
@@ -7,7 +11,7 @@ fn print() {
     match parser.load_file("./tests/print.lox".to_string()) {
         Ok(stmts) => {
             dbg!(&stmts);
-            assert_eq!(stmts.len(), 3);
+            assert_eq!(stmts.len(), 4);
         }
         Err(e) => {
             dbg!(&e);
@@ -23,7 +27,7 @@ fn exponent() {
         Ok(stmts) => {
             assert_eq!(stmts.len(), 1);
             let stmt = stmts.first().unwrap();
-            if let Statement::Expression(expr) = stmt {
+            if let Statement::Expression(expr, _) = stmt {
                 let intr = expr.evaluate().expect("Failed to evaluate");
                 assert_eq!(TokenLiteral::Integer(3125), intr);
             }
@@ -42,7 +46,7 @@ fn mul() {
         Ok(stmts) => {
             assert_eq!(stmts.len(), 1);
             let stmt = stmts.first().unwrap();
-            if let Statement::Expression(expr) = stmt {
+            if let Statement::Expression(expr, _) = stmt {
                 let intr = expr.evaluate().expect("Failed to evaluate");
                 assert_eq!(TokenLiteral::Integer(25), intr);
             }
@@ -61,7 +65,7 @@ fn add() {
         Ok(stmts) => {
             assert_eq!(stmts.len(), 1);
             let stmt = stmts.first().unwrap();
-            if let Statement::Expression(expr) = stmt {
+            if let Statement::Expression(expr, _) = stmt {
                 let intr = expr.evaluate().expect("Failed to evaluate");
                 assert_eq!(TokenLiteral::Integer(10), intr);
             }
@@ -80,7 +84,7 @@ fn sub() {
         Ok(stmts) => {
             assert_eq!(stmts.len(), 1);
             let stmt = stmts.first().unwrap();
-            if let Statement::Expression(expr) = stmt {
+            if let Statement::Expression(expr, _) = stmt {
                 let intr = expr.evaluate().expect("Failed to evaluate");
                 assert_eq!(TokenLiteral::Integer(0), intr);
             }
@@ -99,7 +103,7 @@ fn str() {
         Ok(stmts) => {
             assert_eq!(stmts.len(), 1);
             let stmt = stmts.first().unwrap();
-            if let Statement::Expression(expr) = stmt {
+            if let Statement::Expression(expr, _) = stmt {
                 let intr = expr.evaluate().expect("Failed to evaluate");
                 assert_eq!(TokenLiteral::String("test".to_string()), intr);
             }
@@ -118,7 +122,7 @@ fn str_concat() {
         Ok(stmts) => {
             assert_eq!(stmts.len(), 1);
             let stmt = stmts.first().unwrap();
-            if let Statement::Expression(expr) = stmt {
+            if let Statement::Expression(expr, _) = stmt {
                 let intr = expr.evaluate().expect("Failed to evaluate");
                 assert_eq!(TokenLiteral::String("Hello World!".to_string()), intr);
             }
@@ -138,7 +142,7 @@ fn str_plus_num() {
         Ok(stmts) => {
             assert_eq!(stmts.len(), 1);
             let stmt = stmts.first().unwrap();
-            if let Statement::Expression(expr) = stmt {
+            if let Statement::Expression(expr, _) = stmt {
                 let intr = expr.evaluate().expect("Failed to evaluate");
                 assert_eq!(TokenLiteral::String("Hello5".to_string()), intr);
             }
@@ -152,7 +156,7 @@ fn str_plus_num() {
         Ok(stmts) => {
             assert_eq!(stmts.len(), 1);
             let stmt = stmts.first().unwrap();
-            if let Statement::Expression(expr) = stmt {
+            if let Statement::Expression(expr, _) = stmt {
                 let intr = expr.evaluate().expect("Failed to evaluate");
                 assert_eq!(TokenLiteral::String("1Hello5".to_string()), intr);
             }
@@ -178,3 +182,1110 @@ fn variables() {
         }
     }
 }
+
+#[test]
+fn match_expression() {
+    use crate::parser::Parser;
+    let mut parser = Parser::new();
+    let cases = [
+        ("match 2 { 1 => 'one', 2 => 'two', _ => 'many' };", "two"),
+        ("match 7 { 1 => 'one', 2 => 'two', _ => 'many' };", "many"),
+        ("match -1 { -1 => 'negative', _ => 'other' };", "negative"),
+        (
+            "match 'a' { Number => 'number', String => 'string' };",
+            "string",
+        ),
+        ("match 5 { n => 'got ' + n };", "got 5"),
+    ];
+    for (source, expected) in cases {
+        let stmts = parser.load(source.to_string()).expect("Failed to parse");
+        if let Some(Statement::Expression(expr, _)) = stmts.first() {
+            let intr = expr.evaluate().expect("Failed to evaluate");
+            assert_eq!(TokenLiteral::String(expected.to_string()), intr);
+        } else {
+            panic!("Expected an expression statement for {}", source);
+        }
+    }
+}
+
+#[test]
+fn match_binding_scope() {
+    use crate::parser::Parser;
+    let mut parser = Parser::new();
+    let mut interpreter = Interpreter::new();
+    let stmts = parser
+        .load("var n = 1; var m = match 10 { n => n * 2 };".to_string())
+        .expect("Failed to parse");
+    interpreter.interpret(stmts);
+    assert!(interpreter.scopes.is_empty());
+    let stmts = parser.load("n + m;".to_string()).expect("Failed to parse");
+    if let Some(Statement::Expression(expr, _)) = stmts.first() {
+        let intr = interpreter.evaluate(expr).expect("Failed to evaluate");
+        assert_eq!(TokenLiteral::Integer(21), intr);
+    }
+}
+
+#[test]
+fn with_scope() {
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.with_scope(|scope| {
+        scope.define("x", TokenLiteral::Integer(21));
+        let nested = scope.with_scope(|inner| {
+            inner.define("x", TokenLiteral::Integer(1));
+            inner.eval("x + 1")
+        });
+        assert_eq!(nested, Ok(TokenLiteral::Integer(2)));
+        assert_eq!(scope.get("x"), Some(TokenLiteral::Integer(21)));
+        assert_eq!(scope.get("y"), None);
+        scope.eval("x * 2")
+    });
+    assert_eq!(result, Ok(TokenLiteral::Integer(42)));
+    assert!(interpreter.scopes.is_empty());
+    assert!(interpreter.variables.is_empty());
+}
+
+#[test]
+fn eval_expr_str() {
+    use crate::parser::Parser;
+    let mut parser = Parser::new();
+    let mut interpreter = Interpreter::new();
+    let stmts = parser
+        .load("var a = 2; var b = 5;".to_string())
+        .expect("Failed to parse");
+    interpreter.interpret(stmts);
+    assert_eq!(
+        interpreter.eval_expr_str("a + b * 2"),
+        Ok(TokenLiteral::Integer(12))
+    );
+    assert!(interpreter.eval_expr_str("a + b;").is_err());
+}
+
+#[test]
+fn eval_str() {
+    let mut interpreter = Interpreter::new();
+    interpreter.output = Output::Captured(vec![]);
+    assert_eq!(
+        interpreter.eval_str("var a = 2; print a; a * 21;"),
+        Ok(TokenLiteral::Integer(42))
+    );
+    assert_eq!(interpreter.eval_str("a = 3;"), Ok(TokenLiteral::Integer(3)));
+    assert_eq!(interpreter.eval_str("var b = a;"), Ok(TokenLiteral::Empty));
+    assert_eq!(
+        interpreter.take_output(),
+        vec![OutputEvent::Print("2".to_string())]
+    );
+
+    let errors = interpreter.eval_str("print missing; a;").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, Some("R3001"));
+    assert_eq!(
+        interpreter.eval_str("a +;").unwrap_err()[0].code,
+        Some("E2001")
+    );
+    assert!(!interpreter.had_error);
+    assert!(interpreter.take_output().is_empty());
+
+    assert_eq!(
+        crate::eval("'x' + 'y';"),
+        Ok(TokenLiteral::String("xy".into()))
+    );
+}
+
+#[test]
+fn globals() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_global("width", 6);
+    interpreter.set_global("height", 7.5);
+    interpreter.set_global("label", "area");
+    interpreter.set_global("missing", None::<bool>);
+    interpreter
+        .eval_expr_str("width * height")
+        .map(|area| interpreter.set_global("area", area))
+        .expect("Failed to evaluate");
+
+    let area: f64 = interpreter
+        .get_global("area")
+        .expect("Missing global")
+        .try_into()
+        .expect("Failed to convert");
+    assert_eq!(area, 45.0);
+    assert_eq!(interpreter.get_global("missing"), Some(TokenLiteral::Empty));
+    assert_eq!(interpreter.get_global("nope"), None);
+    assert!(String::try_from(TokenLiteral::from(1)).is_err());
+
+    interpreter.set_global("width", 1);
+    assert_eq!(
+        interpreter.get_global("width"),
+        Some(TokenLiteral::Integer(1))
+    );
+}
+
+#[test]
+fn collection_conversions() {
+    use std::collections::HashMap;
+    let mut interpreter = Interpreter::new();
+    interpreter.set_global("sizes", vec![1, 2, 3]);
+    interpreter.set_global(
+        "names",
+        HashMap::from([("b", vec!["x"]), ("a", vec!["y", "z"])]),
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("names").unwrap().to_string(),
+        r#"{"a": ["y", "z"], "b": ["x"]}"#
+    );
+
+    let total = interpreter.eval_str("sizes[0] = 4; sizes;").unwrap();
+    assert_eq!(Vec::<i64>::try_from(total), Ok(vec![4, 2, 3]));
+    let pair = interpreter.eval_expr_str("(1.5, 2)").unwrap();
+    assert_eq!(Vec::<f64>::try_from(pair), Ok(vec![1.5, 2.0]));
+    let names: HashMap<String, Vec<String>> =
+        interpreter.get_global("names").unwrap().try_into().unwrap();
+    assert_eq!(names["a"], vec!["y".to_string(), "z".to_string()]);
+
+    let mixed = interpreter.eval_expr_str("[1, 'two']").unwrap();
+    assert!(Vec::<i64>::try_from(mixed).is_err());
+    let keyed = interpreter.eval_expr_str("{1: 2}").unwrap();
+    assert_eq!(
+        HashMap::<String, i64>::try_from(keyed),
+        Err(LoxError::InvalidConversion {
+            from: "integer",
+            to: "String"
+        })
+    );
+    assert!(Vec::<bool>::try_from(TokenLiteral::from(true)).is_err());
+
+    // an i64 is only an integer where it fits isize
+    assert_eq!(TokenLiteral::try_from(-7i64), Ok(TokenLiteral::Integer(-7)));
+    match isize::try_from(i64::MAX) {
+        Ok(max) => assert_eq!(
+            TokenLiteral::try_from(i64::MAX),
+            Ok(TokenLiteral::Integer(max))
+        ),
+        Err(_) => assert_eq!(
+            TokenLiteral::try_from(i64::MAX).is_ok(),
+            cfg!(feature = "bigint")
+        ),
+    }
+}
+
+#[test]
+fn remove_and_reset() {
+    use crate::parser::Parser;
+    let stmts = Parser::new()
+        .load("var a = 1; var a = 2; var b = 3; assert false;".to_string())
+        .expect("Failed to parse");
+    let mut interpreter = Interpreter::new().with_fuel(100);
+    interpreter.output = crate::interpreter::Output::Captured(vec![]);
+    interpreter.interpret(stmts);
+    assert!(interpreter.had_error);
+
+    // the latest declaration is the one that's read and written
+    assert_eq!(interpreter.eval_expr_str("a"), Ok(TokenLiteral::Integer(2)));
+    interpreter.set_global("a", 5);
+    assert_eq!(
+        interpreter.eval_expr_str("a + b"),
+        Ok(TokenLiteral::Integer(8))
+    );
+
+    assert_eq!(interpreter.remove("a"), Some(TokenLiteral::Integer(5)));
+    assert_eq!(interpreter.get_global("a"), None);
+    assert_eq!(interpreter.remove("a"), None);
+    assert_eq!(interpreter.get_global("b"), Some(TokenLiteral::Integer(3)));
+
+    interpreter.clear_globals();
+    assert_eq!(interpreter.get_global("b"), None);
+
+    interpreter.set_global("c", 4);
+    interpreter.reset();
+    assert_eq!(interpreter.get_global("c"), None);
+    assert!(!interpreter.had_error);
+    assert!(interpreter.fuel.is_some());
+    assert_eq!(interpreter.take_output().len(), 1);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_globals() {
+    let mut interpreter = Interpreter::new();
+    let input = serde_json::json!(21);
+    interpreter.set_global(
+        "n",
+        TokenLiteral::try_from(input).expect("Failed to convert"),
+    );
+    let result = interpreter
+        .eval_expr_str("n * 2")
+        .expect("Failed to evaluate");
+    assert_eq!(serde_json::Value::from(result), serde_json::json!(42));
+    let list = TokenLiteral::try_from(serde_json::json!([1, [2]])).expect("Failed to convert");
+    assert_eq!(list.to_string(), "[1, [2]]");
+    assert_eq!(serde_json::Value::from(list), serde_json::json!([1, [2]]));
+    let map = TokenLiteral::try_from(serde_json::json!({ "a": [1] })).expect("Failed to convert");
+    assert_eq!(map.to_string(), "{\"a\": [1]}");
+    assert_eq!(
+        serde_json::Value::from(map),
+        serde_json::json!({ "a": [1] })
+    );
+}
+
+#[test]
+fn string_length_limit() {
+    let mut interpreter = Interpreter::new().with_limits(Limits {
+        max_string_length: Some(8),
+        ..Limits::default()
+    });
+    interpreter.set_global("s", "abcd");
+    assert_eq!(
+        interpreter.eval_expr_str("s + s"),
+        Ok(TokenLiteral::String("abcdabcd".to_string()))
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("s + s + s"),
+        Err(ParserError::LimitExceeded {
+            limit: "string length",
+            max: 8
+        }
+        .into())
+    );
+    // a number's digits are only known once it's written out
+    assert_eq!(
+        interpreter.eval_expr_str("s + s + 1"),
+        Err(ParserError::LimitExceeded {
+            limit: "string length",
+            max: 8
+        }
+        .into())
+    );
+}
+
+#[test]
+fn element_limit() {
+    let mut interpreter = Interpreter::new().with_limits(Limits {
+        max_elements: Some(2),
+        ..Limits::default()
+    });
+    let exceeded = Err(ParserError::LimitExceeded {
+        limit: "element count",
+        max: 2,
+    }
+    .into());
+    assert!(interpreter.eval_expr_str("[1, 2]").is_ok());
+    assert_eq!(interpreter.eval_expr_str("[1, 2, 3]"), exceeded);
+    assert_eq!(interpreter.eval_expr_str("(1, 2, 3)"), exceeded);
+    assert_eq!(interpreter.eval_expr_str("{1: 1, 2: 2, 3: 3}"), exceeded);
+
+    interpreter.set_global("m", TokenLiteral::Map(Default::default()));
+    assert!(interpreter
+        .eval_str("m['a'] = 1; m['b'] = 2; m['a'] = 3;")
+        .is_ok());
+    let errors = interpreter.eval_str("m['c'] = 4;").unwrap_err();
+    assert!(errors[0].message.contains("element count is capped at 2"));
+    assert_eq!(
+        interpreter.eval_expr_str("m").unwrap().to_string(),
+        r#"{"a": 3, "b": 2}"#
+    );
+}
+
+#[test]
+fn memory_limit() {
+    use crate::{
+        interpreter::{Output, OutputEvent},
+        parser::Parser,
+    };
+    let stmts = Parser::new()
+        .load(
+            "var xs = [1, 2]; xs[0] = xs;
+var s = 'ab';
+for (x in [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]) s = s + s;
+print s;"
+                .to_string(),
+        )
+        .expect("Failed to parse");
+    let mut interpreter = Interpreter::new().with_limits(Limits {
+        max_memory: Some(1024),
+        ..Limits::default()
+    });
+    interpreter.output = Output::Captured(vec![]);
+    interpreter.interpret(stmts);
+    // the list that holds itself is counted once
+    assert!(interpreter.memory_usage() < 1024);
+    let output = interpreter.take_output();
+    assert!(
+        matches!(&output[0], OutputEvent::Error { message, .. } if message.contains("memory is capped at 1024"))
+    );
+    let s = interpreter.get_global("s").expect("Missing s").to_string();
+    assert!(s.len() < 1024);
+}
+
+#[test]
+fn assign_policy() {
+    use crate::parser::Parser;
+    let mut parser = Parser::new();
+    let stmts = parser
+        .load("var a = 1; a = a + 1; b = 10;".to_string())
+        .expect("Failed to parse");
+
+    let mut repl = Interpreter::new().with_assign_policy(AssignPolicy::ImplicitGlobal);
+    repl.interpret(stmts.clone());
+    assert_eq!(repl.get_global("a"), Some(TokenLiteral::Integer(2)));
+    assert_eq!(repl.get_global("b"), Some(TokenLiteral::Integer(10)));
+
+    let mut strict = Interpreter::new().with_assign_policy(AssignPolicy::Strict);
+    strict.interpret(stmts);
+    assert_eq!(strict.get_global("a"), Some(TokenLiteral::Integer(2)));
+    assert_eq!(strict.get_global("b"), None);
+    assert_eq!(
+        strict.eval_expr_str("c = 1"),
+        Err(ParserError::UndefinedVariable {
+            name: "c".to_string(),
+            suggestion: None,
+            line: 1,
+            column: 1,
+            span: 0..1,
+        }
+        .into())
+    );
+}
+
+#[test]
+fn assign_in_scope() {
+    let mut interpreter = Interpreter::new().with_assign_policy(AssignPolicy::ImplicitGlobal);
+    interpreter.set_global("total", 0);
+    interpreter.with_scope(|scope| {
+        scope.define("step", TokenLiteral::Integer(5));
+        scope
+            .eval("total = total + step")
+            .expect("Failed to assign");
+        scope.eval("created = step").expect("Failed to assign");
+    });
+    assert_eq!(
+        interpreter.get_global("total"),
+        Some(TokenLiteral::Integer(5))
+    );
+    assert_eq!(
+        interpreter.get_global("created"),
+        Some(TokenLiteral::Integer(5))
+    );
+}
+
+#[test]
+fn mutant_changes_operator() {
+    use crate::mutate::Mutant;
+
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        interpreter.eval_expr_str("1 < 1"),
+        Ok(TokenLiteral::Boolean(false))
+    );
+    interpreter.mutant = Mutant::from_name("less-as-less-equal");
+    assert_eq!(
+        interpreter.eval_expr_str("1 < 1"),
+        Ok(TokenLiteral::Boolean(true))
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("2 - 1"),
+        Ok(TokenLiteral::Integer(1))
+    );
+    assert!(Mutant::from_name("no-such-mutant").is_none());
+}
+
+#[test]
+fn compound_assignment() {
+    use crate::parser::Parser;
+    let mut parser = Parser::new();
+    let stmts = parser
+        .load("var a = 10; a += 5; a -= 3; a *= 2; a /= 4; var s = 'x'; s += 'y';".to_string())
+        .expect("Failed to parse");
+    let mut interpreter = Interpreter::new();
+    interpreter.interpret(stmts);
+    // division always produces a float
+    assert_eq!(interpreter.get_global("a"), Some(TokenLiteral::Float(6.0)));
+    assert_eq!(
+        interpreter.get_global("s"),
+        Some(TokenLiteral::String("xy".to_string()))
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("a += 1"),
+        Ok(TokenLiteral::Float(7.0))
+    );
+}
+
+#[test]
+fn lists() {
+    use crate::parser::Parser;
+    let mut parser = Parser::new();
+    let stmts = parser
+        .load("var xs = [1, 'two', [3]]; var ys = xs; ys[0] = 10; xs[2][0] += 1;".to_string())
+        .expect("Failed to parse");
+    let mut interpreter = Interpreter::new();
+    interpreter.interpret(stmts);
+    assert!(!interpreter.had_error);
+    assert_eq!(
+        interpreter.eval_expr_str("xs[0]"),
+        Ok(TokenLiteral::Integer(10))
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("xs").map(|xs| xs.to_string()),
+        Ok("[10, \"two\", [4]]".to_string())
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("xs == ys"),
+        Ok(TokenLiteral::Boolean(true))
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("[1] == [1]"),
+        Ok(TokenLiteral::Boolean(false))
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("match xs { List => 1, _ => 2 }"),
+        Ok(TokenLiteral::Integer(1))
+    );
+
+    assert_eq!(
+        interpreter.eval_expr_str("xs[3]"),
+        Err(ParserError::IndexOutOfBounds {
+            index: 3,
+            len: 3,
+            line: 1,
+            column: 5,
+            span: 4..5,
+        }
+        .into())
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("xs[-1] = 0"),
+        Err(ParserError::IndexOutOfBounds {
+            index: -1,
+            len: 3,
+            line: 1,
+            column: 6,
+            span: 5..6,
+        }
+        .into())
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("xs[0][0]"),
+        Err(ParserError::NotIndexable {
+            type_name: "integer",
+            line: 1,
+            column: 8,
+            span: 7..8,
+        }
+        .into())
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("xs[true]"),
+        Err(ParserError::IndexType {
+            type_name: "boolean",
+            line: 1,
+            column: 8,
+            span: 7..8,
+        }
+        .into())
+    );
+}
+
+#[test]
+fn maps() {
+    use crate::parser::Parser;
+    let mut parser = Parser::new();
+    let stmts = parser
+        .load("var m = {'b': 1, 2: [], nil: true}; var n = m; n['a'] = 3; m['b'] += 1;".to_string())
+        .expect("Failed to parse");
+    let mut interpreter = Interpreter::new();
+    interpreter.interpret(stmts);
+    assert!(!interpreter.had_error);
+    assert_eq!(
+        interpreter.eval_expr_str("m").map(|m| m.to_string()),
+        Ok("{\"b\": 2, 2: [], nil: true, \"a\": 3}".to_string())
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("{1: 'x', 1: 'y'}[1]"),
+        Ok(TokenLiteral::String("y".to_string()))
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("match {} { Map => 1, _ => 2 }"),
+        Ok(TokenLiteral::Integer(1))
+    );
+
+    assert_eq!(
+        interpreter.eval_expr_str("m['c']"),
+        Err(ParserError::MissingKey {
+            key: "\"c\"".to_string(),
+            line: 1,
+            column: 6,
+            span: 5..6,
+        }
+        .into())
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("m[1.5] = 0"),
+        Err(ParserError::UnhashableKey {
+            type_name: "float",
+            line: 1,
+            column: 6,
+            span: 5..6,
+        }
+        .into())
+    );
+}
+
+#[test]
+fn self_containing_values() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .eval_str("var xs = [1]; xs[0] = xs; var m = {'a': xs}; m['k'] = m; [m, (xs,)];")
+        .expect("Failed to evaluate");
+    assert_eq!(
+        value.to_string(),
+        r#"[{"a": [[...]], "k": {...}}, ([[...]],)]"#
+    );
+    assert_eq!(
+        format!("{:?}", value),
+        r#"List(SharedList([{"a": [[...]], "k": {...}}, ([[...]],)]))"#
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("[xs, xs]").unwrap().to_string(),
+        "[[[...]], [[...]]]"
+    );
+
+    #[cfg(feature = "json")]
+    assert_eq!(
+        serde_json::Value::from(value),
+        serde_json::json!([{"a": ["[...]"], "k": "{...}"}, [["[...]"]]])
+    );
+}
+
+#[test]
+fn for_in() {
+    use crate::{
+        interpreter::{Output, OutputEvent},
+        parser::Parser,
+    };
+    let mut parser = Parser::new();
+    let stmts = parser
+        .load(
+            "var xs = [1, 2]; var m = {'b': 0, 'a': 0}; var total = 0;
+for (x in xs) for (k in m) m[k] += x;
+for (x in xs) xs[0] = 10;
+for (k in m) print k;
+for (x in 3) print x;
+for (x in [1, 2, 3]) print total[x];"
+                .to_string(),
+        )
+        .expect("Failed to parse");
+    let mut interpreter = Interpreter::new();
+    interpreter.output = Output::Captured(vec![]);
+    interpreter.interpret(stmts);
+    assert_eq!(
+        interpreter.eval_expr_str("m").map(|m| m.to_string()),
+        Ok("{\"b\": 3, \"a\": 3}".to_string())
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("xs").map(|xs| xs.to_string()),
+        Ok("[10, 2]".to_string())
+    );
+    // the loop variable is gone after the loop
+    assert!(interpreter.scopes.is_empty());
+
+    let output = interpreter.take_output();
+    assert_eq!(output[0], OutputEvent::Print("b".to_string()));
+    assert_eq!(output[1], OutputEvent::Print("a".to_string()));
+    assert!(
+        matches!(&output[2], OutputEvent::Error { message, .. } if message.contains("Can only iterate over lists and maps, not integer."))
+    );
+    // the first failing iteration ends the loop
+    assert_eq!(output.len(), 4);
+}
+
+#[cfg(not(feature = "minimal"))]
+#[test]
+fn operators_assertions() {
+    use crate::{interpreter::Output, parser::Parser};
+    let mut parser = Parser::new();
+    let stmts = parser
+        .load_file("./tests/operators.lox".to_string())
+        .expect("Failed to load file");
+    assert!(!parser.had_error);
+    let mut interpreter = Interpreter::new();
+    interpreter.output = Output::Captured(vec![]);
+    interpreter.interpret(stmts);
+    assert_eq!(interpreter.take_output(), vec![]);
+}
+
+#[test]
+fn assert_statement() {
+    use crate::{
+        interpreter::{Output, OutputEvent},
+        parser::Parser,
+    };
+    let mut parser = Parser::new();
+    let stmts = parser
+        .load(
+            "var x = 2;
+assert x == 2, missing;
+assert x  *  3 == 5, 'x is ' + x;
+assert nil;
+for (y in [1, 0]) assert y;"
+                .to_string(),
+        )
+        .expect("Failed to parse");
+    let mut interpreter = Interpreter::new();
+    interpreter.output = Output::Captured(vec![]);
+    interpreter.interpret(stmts);
+    assert!(interpreter.had_error);
+    assert_eq!(
+        interpreter.take_output(),
+        vec![
+            OutputEvent::Error {
+                line: Some(3),
+                code: Some("R3013"),
+                message: "[line 3, column 1] Error[R3013]: Assertion failed: x  *  3 == 5: x is 2"
+                    .to_string()
+            },
+            OutputEvent::Error {
+                line: Some(4),
+                code: Some("R3013"),
+                message: "[line 4, column 1] Error[R3013]: Assertion failed: nil".to_string()
+            },
+            OutputEvent::Error {
+                line: Some(5),
+                code: Some("R3013"),
+                message: "[line 5, column 19] Error[R3013]: Assertion failed: y".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn tuples() {
+    use crate::{
+        interpreter::{Output, OutputEvent},
+        parser::Parser,
+    };
+    let mut parser = Parser::new();
+    let stmts = parser
+        .load(
+            "var pair = (1, 'two',);
+var (a, b) = pair;
+var single = ((1 + 2),);
+var (x, y) = (1, 2, 3);
+var (z) = 4;"
+                .to_string(),
+        )
+        .expect("Failed to parse");
+    let mut interpreter = Interpreter::new();
+    interpreter.output = Output::Captured(vec![]);
+    interpreter.interpret(stmts);
+    assert_eq!(interpreter.get_global("a"), Some(TokenLiteral::Integer(1)));
+    assert_eq!(
+        interpreter.get_global("b"),
+        Some(TokenLiteral::String("two".to_string()))
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("single").map(|t| t.to_string()),
+        Ok("(3,)".to_string())
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("pair").map(|t| t.to_string()),
+        Ok("(1, \"two\")".to_string())
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("pair == (1, 'two')"),
+        Ok(TokenLiteral::Boolean(true))
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("match pair { Tuple => 1, _ => 0 }"),
+        Ok(TokenLiteral::Integer(1))
+    );
+
+    let output = interpreter.take_output();
+    assert_eq!(output.len(), 2);
+    assert!(
+        matches!(&output[0], OutputEvent::Error { message, .. } if message.contains("Expected a tuple of 2 values, got 3."))
+    );
+    assert!(
+        matches!(&output[1], OutputEvent::Error { message, .. } if message.contains("Can't destructure integer with a tuple pattern."))
+    );
+}
+
+#[test]
+fn destructuring() {
+    use crate::{
+        interpreter::{Output, OutputEvent},
+        parser::Parser,
+    };
+    let mut parser = Parser::new();
+    let stmts = parser
+        .load(
+            "var [a, b] = [1, 2];
+var {x, y} = {'y': 4, 'x': 3, 'z': 5};
+var [c] = [1, 2];
+var {w} = {'x': 1};
+var [d, e] = (1, 2);"
+                .to_string(),
+        )
+        .expect("Failed to parse");
+    let mut interpreter = Interpreter::new();
+    interpreter.output = Output::Captured(vec![]);
+    interpreter.interpret(stmts);
+    for (name, value) in [("a", 1), ("b", 2), ("x", 3), ("y", 4)] {
+        assert_eq!(
+            interpreter.get_global(name),
+            Some(TokenLiteral::Integer(value))
+        );
+    }
+    assert_eq!(interpreter.get_global("c"), None);
+
+    let output = interpreter
+        .take_output()
+        .into_iter()
+        .map(|event| match event {
+            OutputEvent::Error { message, .. } => message,
+            OutputEvent::Print(text) => text,
+        })
+        .collect::<Vec<String>>();
+    assert_eq!(output.len(), 3);
+    assert!(
+        output[0].contains("[line 3, column 5] Error[R3012]: Expected a list of 1 values, got 2.")
+    );
+    assert!(output[1].contains("[line 4, column 6] Error[R3008]: Key \"w\" isn't in the map."));
+    assert!(output[2].contains("Can't destructure tuple with a list pattern."));
+}
+
+#[test]
+fn nil_coalescing() {
+    let mut interpreter = Interpreter::new();
+    let cases = [
+        ("nil ?? 1", TokenLiteral::Integer(1)),
+        ("false ?? 1", TokenLiteral::Boolean(false)),
+        ("nil ?? nil ?? 'c'", TokenLiteral::String("c".to_string())),
+        ("1 == 2 ?? 3", TokenLiteral::Boolean(false)),
+        // the right side is never evaluated
+        ("0 ?? [][5]", TokenLiteral::Integer(0)),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(
+            interpreter.eval_expr_str(source),
+            Ok(expected),
+            "{}",
+            source
+        );
+    }
+    interpreter.set_global("x", TokenLiteral::Empty);
+    interpreter
+        .eval_expr_str("x = nil ?? 2")
+        .expect("Failed to assign");
+    assert_eq!(interpreter.get_global("x"), Some(TokenLiteral::Integer(2)));
+}
+
+#[test]
+fn undefined_variable() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        interpreter.eval_expr_str("1 + missing"),
+        Err(ParserError::UndefinedVariable {
+            name: "missing".to_string(),
+            suggestion: None,
+            line: 1,
+            column: 5,
+            span: 4..11,
+        }
+        .into())
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("missing = 1"),
+        Err(ParserError::UndefinedVariable {
+            name: "missing".to_string(),
+            suggestion: None,
+            line: 1,
+            column: 1,
+            span: 0..7,
+        }
+        .into())
+    );
+    assert_eq!(interpreter.get_global("missing"), None);
+
+    interpreter.set_global("length", 1);
+    interpreter.set_global("x", 2);
+    let suggested = |interpreter: &mut Interpreter, source: &str| match interpreter
+        .eval_expr_str(source)
+    {
+        Err(LoxError::ParseError(ParserError::UndefinedVariable { suggestion, .. })) => suggestion,
+        other => panic!("expected an undefined variable, got {:?}", other),
+    };
+    assert_eq!(
+        suggested(&mut interpreter, "lenght"),
+        Some("length".to_string())
+    );
+    assert_eq!(
+        suggested(&mut interpreter, "lngth + 1"),
+        Some("length".to_string())
+    );
+    assert_eq!(suggested(&mut interpreter, "xs"), Some("x".to_string()));
+    assert_eq!(suggested(&mut interpreter, "y"), None);
+    assert_eq!(suggested(&mut interpreter, "width"), None);
+    assert_eq!(
+        interpreter.eval_expr_str("lenght").unwrap_err().to_string(),
+        "Parser error: [line 1, column 1] Error[R3001]: Undefined variable 'lenght', did you mean 'length'?"
+    );
+}
+
+#[test]
+fn runtime_error_context() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        interpreter.eval_expr_str("1 +\n2 * 'a'"),
+        Err(ParserError::RuntimeError {
+            message: "Operand of '*' must be a number, not string.".to_string(),
+            code: Some("R3003"),
+            line: 2,
+            column: 3,
+            span: 6..7,
+            token: "*".to_string(),
+        }
+        .into())
+    );
+    let err = interpreter.eval_expr_str("nil + 1").expect_err("Added nil");
+    assert_eq!(
+        err.to_string(),
+        "Parser error: [line 1, column 5] Error[R3002]: Can't apply '+' to nil and integer."
+    );
+}
+
+#[test]
+fn type_mismatch() {
+    let mut interpreter = Interpreter::new();
+    for (source, message) in [
+        ("true * 3", "Can't apply '*' to boolean and integer."),
+        ("nil - 1", "Can't apply '-' to nil and integer."),
+        ("'a' / 2", "Can't apply '/' to string and integer."),
+        ("nil < 1", "Can't apply '<' to nil and integer."),
+        ("1 >= 'a'", "Can't apply '>=' to integer and string."),
+    ] {
+        assert_eq!(
+            interpreter.eval_expr_str(source),
+            Err(ParserError::RuntimeError {
+                message: message.to_string(),
+                code: Some("R3002"),
+                line: 1,
+                column: source.find(' ').unwrap() + 2,
+                span: {
+                    let start = source.find(' ').unwrap() + 1;
+                    start..start + source.split(' ').nth(1).unwrap().len()
+                },
+                token: source.split(' ').nth(1).unwrap().to_string(),
+            }
+            .into()),
+            "{}",
+            source
+        );
+    }
+    assert_eq!(
+        interpreter.eval_expr_str("-1.5"),
+        Ok(TokenLiteral::Float(-1.5))
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("1.5 < 2"),
+        Ok(TokenLiteral::Boolean(true))
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("2 ** -1"),
+        Ok(TokenLiteral::Float(0.5))
+    );
+}
+
+#[test]
+fn strict_truthiness() {
+    let mut rlox = Interpreter::new();
+    assert_eq!(rlox.eval_expr_str("!0"), Ok(TokenLiteral::Boolean(true)));
+    assert_eq!(rlox.eval_expr_str("!0.0"), Ok(TokenLiteral::Boolean(true)));
+
+    let mut strict = Interpreter::new().with_truthiness(Truthiness::Strict);
+    assert_eq!(strict.eval_expr_str("!0"), Ok(TokenLiteral::Boolean(false)));
+    assert_eq!(
+        strict.eval_expr_str("!0.0"),
+        Ok(TokenLiteral::Boolean(false))
+    );
+    assert_eq!(
+        strict.eval_expr_str("!nil"),
+        Ok(TokenLiteral::Boolean(true))
+    );
+    assert_eq!(
+        strict.eval_expr_str("!false"),
+        Ok(TokenLiteral::Boolean(true))
+    );
+}
+
+#[cfg(not(feature = "bigint"))]
+#[test]
+fn integer_overflow() {
+    let mut interpreter = Interpreter::new();
+    for (source, operator) in [
+        (format!("{} + 1", isize::MAX), "+"),
+        (format!("-{} - 2", isize::MAX), "-"),
+        ("2 ** 100".to_string(), "**"),
+    ] {
+        assert_eq!(
+            interpreter.eval_expr_str(&source),
+            Err(ParserError::RuntimeError {
+                message: format!("Integer overflow in '{}'.", operator),
+                code: Some("R3004"),
+                line: 1,
+                column: source.find(&format!(" {} ", operator)).unwrap() + 2,
+                span: {
+                    let start = source.find(&format!(" {} ", operator)).unwrap() + 1;
+                    start..start + operator.len()
+                },
+                token: operator.to_string(),
+            }
+            .into())
+        );
+    }
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn bigint_promotion() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        interpreter
+            .eval_expr_str("2 ** 100")
+            .expect("Failed to evaluate")
+            .to_string(),
+        "1267650600228229401496703205376"
+    );
+    assert_eq!(
+        interpreter.eval_expr_str(&format!("{} + 1 - 1", isize::MAX)),
+        Ok(TokenLiteral::Integer(isize::MAX))
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("99999999999999999999 > 1.5"),
+        Ok(TokenLiteral::Boolean(true))
+    );
+}
+
+#[test]
+fn fuel() {
+    use crate::{
+        interpreter::{Output, OutputEvent},
+        parser::Parser,
+    };
+    let stmts = Parser::new()
+        .load("print 1; for (x in [2, 3]) print x; print 4;".to_string())
+        .expect("Failed to parse");
+    // every statement and expression burns a step, the loop with its
+    // list and elements takes four
+    let mut interpreter = Interpreter::new().with_fuel(8);
+    interpreter.output = Output::Captured(vec![]);
+    interpreter.interpret(stmts);
+    assert_eq!(
+        interpreter.take_output(),
+        vec![
+            OutputEvent::Print("1".to_string()),
+            OutputEvent::Print("2".to_string()),
+            OutputEvent::Error {
+                line: Some(1),
+                code: Some("R3015"),
+                message: "Parser error: Step budget exhausted, the script was stopped.".to_string()
+            },
+        ]
+    );
+    assert_eq!(
+        interpreter.eval_expr_str("1"),
+        Err(ParserError::FuelExhausted.into())
+    );
+}
+
+#[test]
+fn writer_output() {
+    use crate::parser::Parser;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            return Ok(buf.len());
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            return Ok(());
+        }
+    }
+
+    let stmts = Parser::new()
+        .load("var xs = [1, 2];\nfor (x in xs) print x * 10;\nprint 'done';".to_string())
+        .expect("Failed to parse");
+    let buffer = Buffer::default();
+    let mut interpreter = Interpreter::new().with_writer(buffer.clone());
+    interpreter.interpret(stmts);
+    let output = String::from_utf8(buffer.0.lock().unwrap().clone()).expect("Not UTF-8");
+    assert_eq!(output, "10\n20\ndone\n");
+    assert_eq!(interpreter.take_output(), vec![]);
+}
+
+#[test]
+fn reader_input() {
+    use crate::interpreter::Input;
+
+    let interpreter = Interpreter::new().with_reader(std::io::Cursor::new("yes\r\n\nno"));
+    let mut lines = vec![];
+    while let Some(line) = interpreter.input.read_line().expect("Failed to read") {
+        lines.push(line);
+    }
+    assert_eq!(lines, vec!["yes", "", "no"]);
+    // clones share the reader, so it stays at the end
+    assert_eq!(interpreter.clone().input.read_line().ok(), Some(None));
+    assert_eq!(Interpreter::new().input, Input::Stdin);
+}
+
+#[test]
+fn interpret_stream() {
+    use crate::{
+        interpreter::{Output, OutputEvent},
+        parser::Parser,
+    };
+
+    let mut parser = Parser::new();
+    parser.silent = true;
+    parser
+        .scan_str("print 1;\nvar a = 2;\nprint a;\nprint (;\nprint 4;\nprint 5 +;\n")
+        .expect("Failed to scan");
+    let mut interpreter = Interpreter::new();
+    interpreter.output = Output::Captured(vec![]);
+    interpreter.interpret_stream(parser.parse_iter());
+    // nothing runs after the first syntax error, the second is still found
+    assert_eq!(
+        interpreter.take_output(),
+        vec![
+            OutputEvent::Print("1".to_string()),
+            OutputEvent::Print("2".to_string())
+        ]
+    );
+    assert_eq!(parser.diagnostics.len(), 2);
+    assert!(parser.statements.is_empty());
+
+    let mut parser = Parser::new();
+    parser.silent = true;
+    assert!(matches!(
+        parser.scan_str("print @;"),
+        Err(LoxError::ScanError(_))
+    ));
+}
+
+#[test]
+fn long_chain() {
+    // a flat chain isn't nesting, however long it is every pass loops over
+    // it instead of recursing
+    let source = format!("1{};", " + 3 - 1".repeat(5_000));
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        interpreter.eval_str(&source),
+        Ok(TokenLiteral::Integer(10_001))
+    );
+
+    let source = format!("nil{} ?? 7;", " ?? nil".repeat(10_000));
+    assert_eq!(interpreter.eval_str(&source), Ok(TokenLiteral::Integer(7)));
+}
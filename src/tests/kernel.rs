@@ -0,0 +1,78 @@
+use serde_json::{json, Value};
+
+use crate::{config::LoxConfig, kernel::Kernel};
+
+fn of_type<'a>(replies: &'a [Value], msg_type: &str) -> Vec<&'a Value> {
+    return replies
+        .iter()
+        .filter(|reply| reply["msg_type"] == msg_type)
+        .collect();
+}
+
+#[test]
+fn kernel_executes_cells() {
+    let mut kernel = Kernel::new(&LoxConfig::default());
+    let request = json!({
+        "msg_type": "execute_request",
+        "msg_id": "cell-1",
+        "content": { "code": "var a = 2;\nprint a;\na * 3" },
+    });
+    let (replies, shutdown) = kernel.handle(&request.to_string());
+    assert!(!shutdown);
+    assert!(replies.iter().all(|reply| reply["parent_id"] == "cell-1"));
+    assert_eq!(
+        replies.first().unwrap()["content"]["execution_state"],
+        "busy"
+    );
+    assert_eq!(
+        replies.last().unwrap()["content"]["execution_state"],
+        "idle"
+    );
+
+    let streams = of_type(&replies, "stream");
+    assert_eq!(streams.len(), 1);
+    assert_eq!(
+        streams[0]["content"],
+        json!({ "name": "stdout", "text": "2\n" })
+    );
+    let results = of_type(&replies, "execute_result");
+    assert_eq!(results[0]["content"]["data"]["text/plain"], "6");
+    assert_eq!(results[0]["content"]["execution_count"], 1);
+    assert_eq!(
+        of_type(&replies, "execute_reply")[0]["content"]["status"],
+        "ok"
+    );
+
+    let request = json!({
+        "msg_type": "execute_request",
+        "msg_id": "cell-2",
+        "content": { "code": "print @;" },
+    });
+    let (replies, _) = kernel.handle(&request.to_string());
+    assert!(!of_type(&replies, "error").is_empty());
+    let reply = &of_type(&replies, "execute_reply")[0]["content"];
+    assert_eq!(reply["status"], "error");
+    assert_eq!(reply["execution_count"], 2);
+}
+
+#[test]
+fn kernel_protocol_messages() {
+    let mut kernel = Kernel::new(&LoxConfig::default());
+    let (replies, _) = kernel.handle(r#"{"msg_type":"kernel_info_request","msg_id":"1"}"#);
+    let info = of_type(&replies, "kernel_info_reply");
+    assert_eq!(
+        info[0]["content"]["language_info"]["file_extension"],
+        ".lox"
+    );
+
+    let (replies, shutdown) = kernel.handle("not json");
+    assert!(!shutdown);
+    assert_eq!(
+        of_type(&replies, "error")[0]["content"]["ename"],
+        "ProtocolError"
+    );
+
+    let (replies, shutdown) = kernel.handle(r#"{"msg_type":"shutdown_request","msg_id":"2"}"#);
+    assert!(shutdown);
+    assert_eq!(of_type(&replies, "shutdown_reply").len(), 1);
+}
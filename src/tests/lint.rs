@@ -0,0 +1,24 @@
+use crate::{config::LoxConfig, lint::lint_source};
+
+#[test]
+fn lint_variables() {
+    let source = "var a = 1;\nvar b = 2;\nvar c = match a { b => b, };\nvar a = c;\n";
+    let findings = lint_source(source, &LoxConfig::default()).expect("Failed to lint");
+    let found = findings
+        .iter()
+        .map(|finding| (finding.rule, finding.line))
+        .collect::<Vec<(&str, usize)>>();
+    assert_eq!(
+        found,
+        vec![("unused-variable", 2), ("redeclared-variable", 4)]
+    );
+}
+
+#[test]
+fn lint_parser_warnings() {
+    let findings = lint_source("print match true { true => 1, };", &LoxConfig::default())
+        .expect("Failed to lint");
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule, "parser-warning");
+    assert!(lint_source("print ;", &LoxConfig::default()).is_err());
+}
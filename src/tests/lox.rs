@@ -0,0 +1,67 @@
+use crate::{ast::TokenLiteral, interpreter::Interpreter, lox::Lox, parser::Parser};
+
+#[test]
+fn compile_once_run_many() {
+    let program = Lox::compile(&mut Parser::new(), "var x = 6 * 7;").expect("Failed to compile");
+
+    let mut first = Interpreter::new();
+    program.run(&mut first);
+    assert_eq!(first.eval_expr_str("x"), Ok(TokenLiteral::Integer(42)));
+
+    let shared = program.clone();
+    let handle = std::thread::spawn(move || {
+        let mut second = Interpreter::new();
+        shared.run(&mut second);
+        second.eval_expr_str("x + 1")
+    });
+    assert_eq!(
+        handle.join().expect("Thread panicked"),
+        Ok(TokenLiteral::Integer(43))
+    );
+}
+
+#[test]
+fn compile_rejects_syntax_errors() {
+    use crate::{config::Extensions, sink::Collector, sink::SharedSink};
+
+    let collector = Collector::default();
+    let mut parser = Parser::new();
+    parser.sink = SharedSink::new(collector.clone());
+    let errors = Lox::compile(&mut parser, "var = 5;\nprint (1;").expect_err("Expected errors");
+    assert_eq!(errors.len(), 2);
+    assert_eq!(collector.take().len(), 2);
+
+    parser.extensions = Extensions::only(&[]).unwrap();
+    let errors = Lox::compile(&mut parser, "print [1];").expect_err("Expected errors");
+    assert_eq!(errors[0].code, Some("E2027"));
+}
+
+#[test]
+fn parse_program_is_pure() {
+    use crate::lox::parse_program;
+
+    let program = parse_program("var a = 1;\nprint a;").expect("Failed to parse");
+    assert_eq!(program.statements().len(), 2);
+    // nothing from the first call leaks into the second
+    let errors = parse_program("print 1;\nprint @;").expect_err("Expected errors");
+    assert_eq!(errors[0].line, 2);
+}
+
+#[test]
+fn session_runs_each_input_once() {
+    use crate::{config::LoxConfig, interpreter::Output, lox::Session};
+
+    let mut session = Session::new(&LoxConfig::default());
+    session.silent = true;
+    session.interpreter.output = Output::Captured(vec![]);
+    let (result, _) = session.run("var n = 1;\n".to_string());
+    assert!(result.is_ok());
+    let (result, _) = session.run("n += 1;\n".to_string());
+    assert!(result.is_ok());
+    let (value, _) = session.evaluate("n\n".to_string());
+    assert_eq!(value, Ok(TokenLiteral::Integer(2)));
+
+    let (_, parsed) = session.run("print @;\n".to_string());
+    assert_eq!(parsed.diagnostics[0].line, 4);
+    assert_eq!(session.line, 5);
+}
@@ -0,0 +1,93 @@
+use crate::{
+    ast::ParserError,
+    messages::{Catalog, CATALOG, CODES},
+    parser::Parser,
+};
+
+#[test]
+fn catalog_overrides() {
+    let catalog = Catalog::parse(
+        "# français\nundefined-variable = \"Variable '{name}' non définie.\"\nindex-type = \"{missing} {type}\"\n",
+    )
+    .expect("Failed to parse catalog");
+    assert_eq!(
+        catalog.render("undefined-variable", &[("name", &"x")]),
+        "Variable 'x' non définie."
+    );
+    // unknown placeholders are kept, codes without an override use the
+    // built in text
+    assert_eq!(
+        catalog.render("index-type", &[("type", &"float")]),
+        "{missing} float"
+    );
+    assert_eq!(
+        catalog.render("missing-key", &[("key", &"\"a\"")]),
+        "Key \"a\" isn't in the map."
+    );
+    assert_eq!(
+        Catalog::parse("\nundefined-varible = \"x\""),
+        Err((2, "Unknown message code 'undefined-varible'".to_string()))
+    );
+    assert!(Catalog::parse("undefined-variable = x").is_err());
+}
+
+#[test]
+fn builtin_messages() {
+    let mut codes = CATALOG.iter().map(|(code, _)| *code).collect::<Vec<_>>();
+    codes.sort();
+    codes.dedup();
+    assert_eq!(codes.len(), CATALOG.len());
+
+    // the default catalog round trips through its own file format
+    let catalog = Catalog::parse(&crate::messages::render_catalog()).expect("Failed to parse");
+    assert_eq!(catalog.overrides.len(), CATALOG.len());
+    assert_eq!(
+        ParserError::UndefinedVariable {
+            name: "x".to_string(),
+            suggestion: None,
+            line: 3,
+            column: 0,
+            span: 0..0,
+        }
+        .to_string(),
+        "[line 3] Error[R3001]: Undefined variable 'x'."
+    );
+    assert_eq!(
+        ParserError::UndefinedVariable {
+            name: "x".to_string(),
+            suggestion: None,
+            line: 3,
+            column: 7,
+            span: 0..0,
+        }
+        .to_string(),
+        "[line 3, column 7] Error[R3001]: Undefined variable 'x'."
+    );
+}
+
+#[test]
+fn stable_codes() {
+    let mut codes = CODES.iter().map(|(_, code)| *code).collect::<Vec<_>>();
+    codes.sort();
+    codes.dedup();
+    assert_eq!(codes.len(), CODES.len());
+    for (key, _) in CODES {
+        assert!(CATALOG.iter().any(|(known, _)| known == key), "{}", key);
+    }
+
+    let diagnostics = Parser::new().syntax_errors("print 'a\n1 = 2;\n".to_string());
+    let codes = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.code)
+        .collect::<Vec<_>>();
+    // the unterminated string runs to the end of the script, the parser
+    // doesn't repeat that
+    assert_eq!(codes, vec![Some("E1001")]);
+    let diagnostics = Parser::new().syntax_errors("print 1 /* x".to_string());
+    assert_eq!(diagnostics.len(), 1);
+    let diagnostics = Parser::new().syntax_errors("print (1\n".to_string());
+    assert_eq!(diagnostics[0].code, Some("E2011"));
+    let diagnostics = Parser::new().syntax_errors("var a;\n1 = 2;\n".to_string());
+    assert_eq!(diagnostics[0].code, Some("E2003"));
+    assert!(diagnostics[0].message.starts_with("Error[E2003] at '='"));
+}
@@ -1,3 +1,22 @@
+pub mod cli;
+pub mod config;
+pub mod corpus;
+pub mod crash;
+pub mod diff;
+pub mod fix;
+pub mod formatter;
+pub mod hash;
 pub mod interpreter;
+#[cfg(feature = "json")]
+pub mod kernel;
+pub mod lint;
+pub mod lox;
+pub mod messages;
 pub mod parser;
+pub mod repl;
+pub mod resolver;
 pub mod scanner;
+#[cfg(feature = "json")]
+pub mod snapshot;
+pub mod snippet;
+pub mod xref;
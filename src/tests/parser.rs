@@ -1,8 +1,517 @@
+#[cfg(not(feature = "minimal"))]
 #[test]
 fn parse() {
     use crate::parser::Parser;
 
     let mut parser = Parser::new();
-    parser.load_file("./tests/parser.lox".to_string()).expect("Failed to load file");
+    parser
+        .load_file("./tests/parser.lox".to_string())
+        .expect("Failed to load file");
     parser.parse().expect("Failed to parse");
 }
+
+#[test]
+fn match_exhaustiveness() {
+    use crate::parser::Parser;
+
+    let mut parser = Parser::new();
+    parser
+        .load("match true { true => 1, false => 0 };".to_string())
+        .expect("Failed to parse");
+    assert!(parser.warnings.is_empty());
+
+    parser
+        .load("match nil { true => 1, nil => 0 };".to_string())
+        .expect("Failed to parse");
+    assert_eq!(parser.warnings.len(), 1);
+    assert!(parser.warnings[0].message.contains("missing false"));
+
+    parser
+        .load("match 1 { 1 => 1 };".to_string())
+        .expect("Failed to parse");
+    assert_eq!(parser.warnings.len(), 1);
+}
+
+#[test]
+fn invalid_assignment_target() {
+    use crate::parser::Parser;
+
+    let mut parser = Parser::new();
+    parser
+        .load("var a = 1; a + 1 = 2;".to_string())
+        .expect("Failed to parse");
+    assert!(parser.had_error);
+
+    let mut parser = Parser::new();
+    parser
+        .load("var a = 1; (a) += 2;".to_string())
+        .expect("Failed to parse");
+    assert!(parser.had_error);
+}
+
+#[test]
+fn reserved_word_as_identifier() {
+    use crate::ast::{LoxError, ParserError};
+    use crate::parser::Parser;
+
+    for source in [
+        "var class = 1;",
+        "var (a, class) = (1, 2);",
+        "for (while in []) 1;",
+    ] {
+        let mut parser = Parser::new();
+        let errors = parser.syntax_errors(source.to_string());
+        assert_eq!(errors[0].code, Some("E2026"), "{}", source);
+    }
+    // only names being declared get the explanation, a keyword where an
+    // expression or pattern belongs is just unexpected
+    for source in ["class = 1;", "print var;", "match 1 { class => 2 };"] {
+        let mut parser = Parser::new();
+        let errors = parser.syntax_errors(source.to_string());
+        assert!(!errors.is_empty(), "{}", source);
+        assert_ne!(errors[0].code, Some("E2026"), "{}", source);
+    }
+
+    let mut parser = Parser::new();
+    parser.scanner.load("while");
+    match parser.primary() {
+        Err(LoxError::ParseError(ParserError::Generic(msg))) => {
+            assert_eq!(msg, "Expression Expected")
+        }
+        other => panic!("Expected expression error, got {:?}", other),
+    }
+}
+
+#[test]
+fn line_continuity() {
+    use crate::parser::Parser;
+
+    let mut parser = Parser::new();
+    parser
+        .load("var a = 1;\n".to_string())
+        .expect("Failed to parse");
+    assert_eq!(parser.line, 1);
+    parser
+        .load_expression("a +\n 2\n".to_string())
+        .expect("Failed to parse");
+    assert_eq!(parser.line, 3);
+    parser.load("\n".to_string()).expect("Failed to parse");
+    parser
+        .load("var b = a;\n".to_string())
+        .expect("Failed to parse");
+    assert_eq!(parser.line, 5);
+    assert_eq!(parser.peek().line, 6);
+}
+
+#[cfg(not(feature = "minimal"))]
+#[test]
+fn load_file_keeps_io_error() {
+    use crate::ast::LoxError;
+    use crate::parser::Parser;
+    use std::error::Error;
+
+    let mut parser = Parser::new();
+    let err = parser
+        .load_file("./tests/does_not_exist.lox".to_string())
+        .expect_err("Loading a missing file should fail");
+    assert!(matches!(err, LoxError::Io(_)));
+    let source = err
+        .source()
+        .and_then(|source| source.downcast_ref::<std::io::Error>())
+        .expect("Missing io::Error source");
+    assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+
+    let boxed: Box<dyn Error> = Box::new(err);
+    assert!(boxed.to_string().starts_with("IO error"));
+}
+
+#[test]
+fn validate_reports_byte_ranges() {
+    use crate::parser::validate;
+
+    assert!(validate("var a = 1;\nprint a;\n").is_empty());
+
+    let source = "var ñ = 1;\nprint @;\n";
+    let errors = validate(source);
+    assert_eq!(&source[errors[0].0.clone()], "@");
+
+    let source = "var a = 1;\nprint a +;\n";
+    let errors = validate(source);
+    assert_eq!(&source[errors[0].0.clone()], ";");
+    assert!(errors[0].1.contains("at ';'"));
+
+    // the borrowing check finds what a full load does
+    let source = "var = 0xZZ;\nprint match 1 { x => };\nprint 0b2 + \"a;\n";
+    let loaded = crate::parser::Parser::new()
+        .syntax_errors(source.to_string())
+        .into_iter()
+        .map(|diagnostic| (diagnostic.span, diagnostic.message))
+        .collect::<Vec<_>>();
+    assert_eq!(loaded.len(), 5);
+    assert_eq!(validate(source), loaded);
+}
+
+#[test]
+fn columns() {
+    use crate::{parser::Parser, scanner::scan_to_vec};
+
+    // columns count chars, not bytes, and restart after every newline
+    let (tokens, _) = scan_to_vec("var ñü = 'é';\n  ñü;");
+    let columns = tokens
+        .iter()
+        .map(|token| (token.line, token.column))
+        .collect::<Vec<(usize, usize)>>();
+    assert_eq!(
+        columns,
+        vec![
+            (1, 1),
+            (1, 5),
+            (1, 8),
+            (1, 10),
+            (1, 13),
+            (2, 3),
+            (2, 5),
+            (2, 6)
+        ]
+    );
+
+    let diagnostics = Parser::new().syntax_errors("var a = 1;\nprint a +;\n".to_string());
+    assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 10));
+    assert!(diagnostics[0]
+        .to_string()
+        .starts_with("[line 2, column 10] "));
+}
+
+#[test]
+fn every_syntax_error() {
+    use crate::parser::Parser;
+
+    let source = "print (1;\nprint 2;\nprint 3 3;\nprint 4;\nvar = 1;\nvar a = ;\nprint 5;\n";
+    let (statements, diagnostics) = Parser::new().load_silently(source.to_string());
+    let lines = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.line)
+        .collect::<Vec<usize>>();
+    assert_eq!(lines, vec![1, 3, 5, 6]);
+    assert_eq!(
+        diagnostics[3].message,
+        "Error[E2001] at ';': Expression Expected"
+    );
+    // each error only skips its own statement
+    assert_eq!(statements.len(), 3);
+}
+
+#[test]
+fn parse_str_starts_over() {
+    use crate::{config::WarningLevel, parser::Parser};
+
+    let mut parser = Parser::new();
+    parser.warning_level = WarningLevel::Deny;
+    let errors = parser.parse_str("print 1;\nprint (2;\nvar @;").unwrap_err();
+    assert_eq!(
+        errors.iter().map(|error| error.code).collect::<Vec<_>>(),
+        vec![Some("E1002"), Some("E2011"), Some("E2008")]
+    );
+    // nothing from the failed parse leaks into the next one
+    let statements = parser.parse_str("print 3;\nprint 4;").unwrap();
+    assert_eq!(statements.len(), 2);
+    assert_eq!(statements[1].span(), 9..17);
+    assert_eq!(parser.statements.len(), 2);
+    assert!(parser.diagnostics.is_empty() && !parser.had_error);
+    assert_eq!(parser.warning_level, WarningLevel::Deny);
+}
+
+#[test]
+fn comments_are_skipped() {
+    use crate::{parser::Parser, stmt::Statement};
+
+    let source =
+        "// leading\nvar a = /* one */ 1; // trailing\nprint a /* two\n */ + /**/ a;\n/* last */";
+    let mut parser = Parser::new();
+    let statements = parser.parse_str(source).unwrap();
+    assert_eq!(statements.len(), 2);
+    // spans end at the last real token, not a comment after it
+    assert_eq!(&source[statements[0].span()], "var a = /* one */ 1;");
+    assert!(matches!(statements[1], Statement::Print(..)));
+    // the comments stay in the token stream for tools
+    let comments = parser
+        .scanner
+        .tokens
+        .iter()
+        .filter(|token| token.is_trivia());
+    assert_eq!(comments.count(), 6);
+}
+
+#[test]
+fn diagnostics_sink() {
+    use crate::{
+        interpreter::Interpreter,
+        parser::Parser,
+        sink::{Collector, Severity, SharedSink},
+    };
+
+    let collector = Collector::default();
+    let mut parser = Parser::new();
+    parser.sink = SharedSink::new(collector.clone());
+    let source = "print @;\nprint 1 +;\nmatch nil { true => 1 };\nprint nope;\n";
+    assert!(parser.load(source.to_string()).is_err());
+    let reported = collector
+        .take()
+        .into_iter()
+        .map(|(severity, diagnostic)| (severity, diagnostic.line, diagnostic.code))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        reported,
+        vec![
+            (Severity::Error, 1, Some("E1002")),
+            (Severity::Error, 1, Some("E2001")),
+            (Severity::Error, 2, Some("E2001")),
+            (Severity::Warning, 3, Some("W2001")),
+        ]
+    );
+
+    let mut interpreter = Interpreter::new();
+    interpreter.sink = SharedSink::new(collector.clone());
+    interpreter.interpret(parser.statements.clone());
+    let reported = collector.take();
+    assert_eq!(reported.len(), 1);
+    assert_eq!(reported[0].1.code, Some("R3001"));
+    // the runtime error points at the variable, not the whole statement
+    assert_eq!((reported[0].1.line, reported[0].1.column), (4, 7));
+    assert_eq!(reported[0].1.span, 51..55);
+}
+
+#[test]
+fn scan_errors_fail_load() {
+    use crate::{ast::LoxError, parser::Parser};
+
+    let mut parser = Parser::new();
+    parser.silent = true;
+    let result = parser.load("print 1;\nprint @ 2;\nprint 3 +;\n".to_string());
+    let Err(LoxError::ScanError(errors)) = &result else {
+        panic!("Expected scan errors, got {:?}", result);
+    };
+    assert_eq!(errors.len(), 1);
+    assert_eq!((errors[0].line, errors[0].column), (2, 7));
+    assert_eq!(errors[0].message, "Unexpected character: @");
+    assert_eq!(result.unwrap_err().code(), Some("E1002"));
+    // the rest of the script is still parsed and checked
+    assert_eq!(parser.diagnostics.len(), 1);
+    assert_eq!(parser.statements.len(), 2);
+
+    let mut parser = Parser::new();
+    parser.silent = true;
+    assert!(matches!(
+        parser.load_expression("1 + #".to_string()),
+        Err(LoxError::ScanError(_))
+    ));
+}
+
+#[test]
+fn spans() {
+    use crate::{parser::Parser, stmt::Statement};
+
+    let source =
+        "print (1 + x) * y[0];\nvar a = match a { _ => [1, (2,)] };\nfor (i in a) a += i;\n";
+    let statements = Parser::new().load(source.to_string()).unwrap();
+    let spans = statements
+        .iter()
+        .map(|statement| &source[statement.span()])
+        .collect::<Vec<&str>>();
+    assert_eq!(
+        spans,
+        vec![
+            "print (1 + x) * y[0];",
+            "var a = match a { _ => [1, (2,)] };",
+            "for (i in a) a += i;"
+        ]
+    );
+
+    let Statement::Print(expr, _) = &statements[0] else {
+        panic!("expected a print statement");
+    };
+    assert_eq!(&source[expr.span()], "(1 + x) * y[0]");
+    let Statement::ForIn { body, .. } = &statements[2] else {
+        panic!("expected a for loop");
+    };
+    let Statement::Expression(expr, _) = body.as_ref() else {
+        panic!("expected an expression statement");
+    };
+    assert_eq!(&source[expr.span()], "a += i");
+}
+
+#[test]
+fn list_syntax_errors() {
+    use crate::parser::validate;
+
+    assert!(validate("var xs = [1, 2,];\nxs[0] = xs[1];\n").is_empty());
+    assert!(!validate("var xs = [1, 2;\n").is_empty());
+    assert!(!validate("print xs[0;\n").is_empty());
+    assert!(!validate("[1][0] + 1 = 2;\n").is_empty());
+    assert!(validate("var m = {'a': [1], 2: {},};\nm['a'] = m[2];\n").is_empty());
+    assert!(!validate("var m = {'a' 1};\n").is_empty());
+}
+
+#[test]
+fn for_in_syntax_errors() {
+    use crate::parser::validate;
+
+    assert!(validate("for (x in [1]) for (y in {}) print x + y;\nvar in = 1;\n").is_empty());
+    assert!(!validate("for x in [1] print x;\n").is_empty());
+    assert!(!validate("for (x of [1]) print x;\n").is_empty());
+    assert!(!validate("for (x in [1]) var y = x;\n").is_empty());
+}
+
+#[test]
+fn disabled_extensions() {
+    use crate::{ast::TokenLiteral, config::Extensions, parser::Parser, stmt::Statement};
+
+    let mut parser = Parser::new();
+    parser.extensions = Extensions::only(&["lists"]).expect("Unknown extension");
+    let errors = parser.syntax_errors(
+        "var a = [1, 2 ** 3];\na[0] += 1;\nvar m = {};\nfor (x in a) print x;\ndump;\nprint 'hi';\n"
+            .to_string(),
+    );
+    let messages = errors
+        .iter()
+        .map(|error| error.message.as_str())
+        .collect::<Vec<&str>>();
+    assert_eq!(errors.len(), 6, "{:?}", messages);
+    for (error, name) in errors.iter().zip([
+        "exponent",
+        "compound-assignment",
+        "maps",
+        "for-in",
+        "dump",
+        "single-quotes",
+    ]) {
+        assert!(
+            error.message.contains(&format!("'{}'", name)),
+            "{:?}",
+            error
+        );
+    }
+    assert_eq!(errors[2].line, 3);
+    let errors = parser.syntax_errors("print 0x10 + 16;\n".to_string());
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].message.contains("'radix-literals'"));
+
+    // indexing only needs one of lists and maps
+    parser.extensions = Extensions::only(&["maps"]).expect("Unknown extension");
+    assert!(parser
+        .syntax_errors("var m = {1: 2};\nprint m[1];\n".to_string())
+        .is_empty());
+
+    parser.extensions = Extensions::only(&[]).expect("Unknown extension");
+    let statements = parser
+        .load("print 3;".to_string())
+        .expect("Failed to parse");
+    assert!(matches!(
+        &statements[0],
+        Statement::Print(crate::ast::Expression::Literal(TokenLiteral::Float(n), _), _) if *n == 3.0
+    ));
+}
+
+#[test]
+fn assert_syntax_errors() {
+    use crate::parser::validate;
+
+    assert!(validate("assert 1 == 1;\nassert true, 'message';\n").is_empty());
+    assert!(!validate("assert;\n").is_empty());
+    assert!(!validate("assert true 'message';\n").is_empty());
+    assert!(!validate("var assert = 1;\n").is_empty());
+}
+
+#[test]
+fn tuple_syntax_errors() {
+    use crate::parser::validate;
+
+    assert!(validate("var (a, b) = (1, (2,),);\nprint (a);\n").is_empty());
+    assert!(!validate("var t = (1, 2;\n").is_empty());
+    assert!(!validate("var (a, b);\n").is_empty());
+    assert!(!validate("var (a, 1) = (1, 2);\n").is_empty());
+    assert!(!validate("var () = (1, 2);\n").is_empty());
+    assert!(validate("var [a, b] = [1, 2];\nvar {c, d} = {};\n").is_empty());
+    assert!(!validate("var [a, b) = [1, 2];\n").is_empty());
+    assert!(!validate("var {a: b} = {};\n").is_empty());
+}
+
+#[test]
+fn nesting_limit() {
+    use crate::parser::{Parser, MAX_DEPTH};
+
+    // the nesting the parser allows is sized for the main thread's stack,
+    // a debug build needs more than a test thread's for it
+    let check = || {
+        let parens = |n: usize| format!("print {}1{};\n", "(".repeat(n), ")".repeat(n));
+        let mut parser = Parser::new();
+        assert!(parser.parse_str(&parens(MAX_DEPTH - 1)).is_ok());
+        assert!(parser.parse_str("print ((((-[1]))));\n").is_ok());
+        // the operators of a chain aren't nesting
+        let chain = format!("print 1{};\n", " + 1".repeat(MAX_DEPTH * 4));
+        assert!(parser.parse_str(&chain).is_ok());
+
+        let source = format!("{}print 2;\n", parens(MAX_DEPTH + 1));
+        let (statements, diagnostics) = parser.load_silently(source);
+        assert_eq!(statements.len(), 1);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, Some("E2028"));
+    };
+    std::thread::Builder::new()
+        .stack_size(8 << 20)
+        .spawn(check)
+        .expect("Failed to spawn")
+        .join()
+        .expect("Nesting check panicked");
+}
+
+#[test]
+fn brace_at_statement_start() {
+    use crate::parser::Parser;
+
+    // `{` starting a statement is kept for blocks, even where it would
+    // parse as a map literal
+    let mut parser = Parser::new();
+    let (statements, diagnostics) =
+        parser.load_silently("{ print \"x\"; }\n{\"a\": 1};\nprint 2;\n".to_string());
+    assert_eq!(statements.len(), 1);
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.code))
+            .collect::<Vec<_>>(),
+        vec![(1, Some("E2029")), (2, Some("E2029"))]
+    );
+
+    // the map literal is still an expression anywhere else
+    assert!(parser.parse_str("({\"a\": 1})[\"a\"];\n").is_ok());
+    assert!(parser.parse_str("print {\"a\": 1}[\"a\"];\n").is_ok());
+}
+
+#[test]
+fn malformed_numbers_report_once() {
+    use crate::{
+        parser::Parser,
+        sink::{Collector, SharedSink},
+    };
+
+    let too_large = format!("print {}0;\n", "9".repeat(40));
+    let mut sources = vec!["print 0xZZ;\n", "print 0b102;\n", "print 0x;\n"];
+    // a huge literal is fine with the bigint feature
+    if !cfg!(feature = "bigint") {
+        sources.push(&too_large);
+    }
+    for source in sources {
+        let collector = Collector::default();
+        let mut parser = Parser::new();
+        parser.sink = SharedSink::new(collector.clone());
+        assert!(parser.load(source.to_string()).is_err());
+        let codes = collector
+            .take()
+            .into_iter()
+            .map(|(_, diagnostic)| diagnostic.code)
+            .collect::<Vec<_>>();
+        assert_eq!(codes.len(), 1, "{}: {:?}", source, codes);
+        assert!(matches!(codes[0], Some("E1003" | "E1004")), "{}", source);
+    }
+}
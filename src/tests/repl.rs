@@ -0,0 +1,95 @@
+use crate::{
+    config::LoxConfig,
+    repl::{OutputMode, Repl},
+};
+
+#[cfg(feature = "json")]
+#[test]
+fn repl_captures_events() {
+    use crate::{ast::TokenLiteral, repl::Event};
+
+    let mut repl = Repl::new(&LoxConfig::default(), OutputMode::Json);
+    let (events, result) = repl.eval("var a = 2;\n".to_string());
+    assert!(result.is_ok());
+    assert!(events.is_empty());
+
+    let (events, _) = repl.eval("print a + 1;\n".to_string());
+    assert_eq!(events, vec![Event::Print("3".to_string())]);
+
+    let (events, _) = repl.eval("a * 3\n".to_string());
+    assert_eq!(events, vec![Event::Result(TokenLiteral::Integer(6))]);
+
+    let (events, _) = repl.eval("print @;\n".to_string());
+    assert!(matches!(
+        events.first(),
+        Some(Event::Diagnostic {
+            severity: "error",
+            line: Some(4),
+            ..
+        })
+    ));
+
+    // the parser's own report is the only one
+    let (events, _) = repl.eval("a +\n".to_string());
+    assert_eq!(events.len(), 1);
+    let (events, _) = repl.eval("missing\n".to_string());
+    match events.as_slice() {
+        [Event::Diagnostic {
+            code: Some("R3001"),
+            message,
+            ..
+        }] => assert!(message.starts_with("[line 6"), "{}", message),
+        other => panic!("Expected one runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn repl_output_modes() {
+    #[cfg(feature = "json")]
+    assert_eq!(
+        Repl::new(&LoxConfig::default(), OutputMode::Json).eval_line("'hi'\n".to_string()),
+        Ok(vec![
+            r#"{"display":"hi","type":"result","value":"hi","value_type":"string"}"#.to_string()
+        ])
+    );
+
+    let mut repl = Repl::new(&LoxConfig::default(), OutputMode::Plain);
+    assert_eq!(
+        repl.eval_line("1 + 1\n".to_string()),
+        Ok(vec!["2".to_string()])
+    );
+
+    let mut repl = Repl::new(&LoxConfig::default(), OutputMode::Quiet);
+    assert_eq!(repl.eval_line("1 + 1\n".to_string()), Ok(vec![]));
+}
+
+#[test]
+fn repl_snippets_count_session_lines() {
+    use crate::{
+        ast::Diagnostic,
+        sink::{DiagnosticSink, Severity, SharedSink},
+    };
+    use std::sync::{Arc, Mutex};
+
+    struct Rendered(Arc<Mutex<Vec<String>>>);
+    impl DiagnosticSink for Rendered {
+        fn report(&mut self, _: Severity, _: &Diagnostic, rendered: &str) {
+            self.0.lock().unwrap().push(rendered.to_string());
+        }
+    }
+
+    let rendered = Arc::new(Mutex::new(vec![]));
+    let mut repl = Repl::new(&LoxConfig::default(), OutputMode::Quiet);
+    repl.session.interpreter.sink = SharedSink::new(Rendered(rendered.clone()));
+    let _ = repl.eval("var a = 1;\n".to_string());
+    let _ = repl.eval("\n".to_string());
+    let _ = repl.eval("print a +;\n".to_string());
+    let _ = repl.eval("print 'a;\n".to_string());
+    assert_eq!(
+        rendered.lock().unwrap().as_slice(),
+        [
+            "[line 3, column 10] Error[E2001] at ';': Expression Expected\n  |\n3 | print a +;\n  |          ^ Expression Expected",
+            "[line 4, column 7] Error[E1001]: Unterminated string\n  |\n4 | print 'a;\n  |       ^^^ Unterminated string"
+        ]
+    );
+}
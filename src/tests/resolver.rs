@@ -0,0 +1,65 @@
+use crate::{
+    ast::{Expression, Local, TokenLiteral},
+    interpreter::Interpreter,
+    parser::Parser,
+    stmt::Statement,
+};
+
+///where every variable read in `expr` was found bound, in source order
+fn depths(expr: &Expression, out: &mut Vec<(String, Option<Local>)>) {
+    match expr {
+        Expression::Variable { name, local } => out.push((name.lexeme.clone(), *local)),
+        Expression::Binary { left, right, .. } => {
+            depths(left, out);
+            depths(right, out);
+        }
+        Expression::Match { subject, arms, .. } => {
+            depths(subject, out);
+            for arm in arms {
+                depths(&arm.body, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[test]
+fn resolve_depths() {
+    let mut parser = Parser::new();
+    let stmts = parser
+        .load("for (x in [1]) for (y in [2]) print match x { z => x + y + z + g };".to_string())
+        .expect("Failed to parse");
+    let Statement::ForIn { body, .. } = &stmts[0] else {
+        panic!("Expected a for loop");
+    };
+    let Statement::ForIn { body, .. } = body.as_ref() else {
+        panic!("Expected a nested for loop");
+    };
+    let Statement::Print(expr, _) = body.as_ref() else {
+        panic!("Expected a print statement");
+    };
+    let mut found = vec![];
+    depths(expr, &mut found);
+    let at = |depth| Some(Local { depth, slot: 0 });
+    assert_eq!(
+        found,
+        vec![
+            ("x".to_string(), at(1)),
+            ("x".to_string(), at(2)),
+            ("y".to_string(), at(1)),
+            ("z".to_string(), at(0)),
+            ("g".to_string(), None),
+        ]
+    );
+}
+
+#[test]
+fn resolved_names_skip_outer_scopes() {
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.with_scope(|scope| {
+        // the embedder's x is only visible where nothing in the script binds x
+        scope.define("x", TokenLiteral::Integer(10));
+        scope.eval("match 1 { x => x + 1 } + match 5 { y => x + y }")
+    });
+    assert_eq!(result, Ok(TokenLiteral::Integer(17)));
+}
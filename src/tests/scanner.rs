@@ -1,19 +1,152 @@
 #[test]
 fn scan_tokens() {
-    use crate::scanner::Scanner;
+    use crate::{ast::TokenType, scanner::Scanner};
     let mut scanner = Scanner::default();
-    assert_eq!(scanner.tokens.len(), 0); //These all should be default
+    assert_eq!(scanner.tokens.len(), 1); //Only the EOF token
     assert_eq!(scanner.source.len(), 0); //These all should be default
     assert_eq!(scanner.start, 0); //These all should be default
     assert_eq!(scanner.current, 0); //These all should be default
     assert_eq!(scanner.line, 1); //These all should be default
-    scanner.load(
-        std::fs::read_to_string("./tests/scanner.lox")
-            .expect("Faild to load test.lox")
-            .chars()
-            .collect(),
-    );
+    scanner.load(&std::fs::read_to_string("./tests/scanner.lox").expect("Faild to load test.lox"));
     dbg!(&scanner.tokens);
     //Assuming we parsed the file successfully we should have tokens
-    assert_ne!(0, scanner.tokens.len());
+    assert_ne!(1, scanner.tokens.len());
+    assert_eq!(scanner.tokens.last().unwrap().token_type, TokenType::EOF);
+}
+
+#[test]
+fn scan_utf8() {
+    use crate::ast::{TokenLiteral, TokenType};
+    use crate::scanner::Scanner;
+    let mut scanner = Scanner::default();
+    scanner.load("var ñame = 'héllo wörld'; // ünïcode\nñame;");
+    let lexemes = scanner
+        .tokens
+        .iter()
+        .map(|t| t.lexeme.as_str())
+        .collect::<Vec<&str>>();
+    assert_eq!(
+        lexemes,
+        [
+            "var",
+            "ñame",
+            "=",
+            "'héllo wörld'",
+            ";",
+            "// ünïcode",
+            "ñame",
+            ";",
+            ""
+        ]
+    );
+    assert_eq!(scanner.tokens[1].token_type, TokenType::IDENTIFIER);
+    assert_eq!(
+        scanner.tokens[3].literal,
+        TokenLiteral::String("héllo wörld".to_string())
+    );
+    assert_eq!(scanner.tokens[6].line, 2);
+}
+
+#[test]
+#[ignore = "benchmark, run with `cargo test --release -- --ignored scan_large_script`"]
+fn scan_large_script() {
+    use crate::scanner::{Scanner, Source};
+    use std::time::Instant;
+
+    const SIZE: usize = 50 * 1024 * 1024;
+    let line = "var value = (12.5 * 3) + 'some text' - 4 ** 2; // trailing comment\n";
+    let mut source = String::with_capacity(SIZE + line.len());
+    while source.len() < SIZE {
+        source.push_str(line);
+    }
+    let path = std::env::temp_dir().join("rlox_scan_large_script.lox");
+    std::fs::write(&path, &source).expect("Failed to write benchmark script");
+    drop(source);
+
+    let start = Instant::now();
+    let source = Source::read(&path.to_string_lossy()).expect("Failed to read benchmark script");
+    let loaded = start.elapsed();
+    let mut scanner = Scanner::default();
+    scanner.load_source(source);
+    let scanned = start.elapsed();
+    std::fs::remove_file(&path).ok();
+
+    println!(
+        "loaded {} MB in {:?}, scanned {} tokens in {:?} ({:.1} MB/s)",
+        SIZE / 1024 / 1024,
+        loaded,
+        scanner.tokens.len(),
+        scanned - loaded,
+        (SIZE / 1024 / 1024) as f64 / (scanned - loaded).as_secs_f64()
+    );
+    assert!(!scanner.had_error);
+}
+
+///renders scanner output one token or diagnostic per line for golden files
+fn render_scan(source: &str) -> String {
+    use crate::scanner::scan_to_vec;
+    let (tokens, diagnostics) = scan_to_vec(source);
+    let mut out = String::new();
+    for token in tokens {
+        out.push_str(&format!(
+            "{}:{} {:?} {:?} {:?}\n",
+            token.line, token.column, token.token_type, token.lexeme, token.literal
+        ));
+    }
+    for diagnostic in diagnostics {
+        out.push_str(&format!("error {}\n", diagnostic));
+    }
+    return out;
+}
+
+///the expected tokens for `path`, with the bigint feature huge literals
+///scan instead of failing so those files have a `.bigint.tokens` of their own
+fn golden_path(path: &std::path::Path) -> std::path::PathBuf {
+    let bigint = path.with_extension("bigint.tokens");
+    if cfg!(feature = "bigint") && bigint.exists() {
+        return bigint;
+    }
+    return path.with_extension("tokens");
+}
+
+///compares every tests/golden/*.lox against its .tokens file, run with
+///UPDATE_GOLDEN=1 to rewrite them after an intended change
+#[test]
+fn golden_tokens() {
+    let mut checked = 0;
+    let mut entries = std::fs::read_dir("./tests/golden")
+        .expect("Failed to read golden dir")
+        .map(|entry| entry.expect("Failed to read entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lox"))
+        .collect::<Vec<std::path::PathBuf>>();
+    entries.sort();
+    for path in entries {
+        let source = std::fs::read_to_string(&path).expect("Failed to read source");
+        let actual = render_scan(&source);
+        let golden = golden_path(&path);
+        if std::env::var("UPDATE_GOLDEN").is_ok() {
+            std::fs::write(&golden, &actual).expect("Failed to write golden file");
+        }
+        let expected = std::fs::read_to_string(&golden).expect("Missing golden file");
+        assert_eq!(expected, actual, "{} changed", path.display());
+        checked += 1;
+    }
+    assert_ne!(checked, 0);
+}
+
+#[test]
+fn scan_to_vec_is_independent() {
+    use crate::scanner::scan_to_vec;
+    let first = scan_to_vec("a @ b;");
+    let second = scan_to_vec("a @ b;");
+    assert_eq!(first, second);
+    assert_eq!(first.1.len(), 1);
+}
+
+#[cfg(feature = "minimal")]
+#[test]
+fn minimal_builds_cannot_read_files() {
+    use crate::scanner::Source;
+    let err = Source::read("./tests/scanner.lox").expect_err("Minimal builds read a file");
+    assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
 }
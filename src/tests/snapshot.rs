@@ -0,0 +1,105 @@
+use crate::{
+    ast::TokenLiteral,
+    config::LoxConfig,
+    interpreter::Interpreter,
+    parser::Parser,
+    repl::{Event, OutputMode, Repl},
+    snapshot::{restore, restore_file, save},
+};
+
+fn run(source: &str) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.interpret(Parser::new().load(source.to_string()).unwrap());
+    assert!(!interpreter.had_error);
+    return interpreter;
+}
+
+#[test]
+fn session_round_trip() {
+    let interpreter = run(
+        "var a = 1; var a = 2; var f = 2.0; var inf = 1.0 / 0.0; var s = 'x';
+        var t = (1, 'two', nil); var m = {1: [true, 0.5], 'k': (2,)};",
+    );
+    let saved = save(&interpreter).unwrap();
+
+    let mut restored = Interpreter::new();
+    assert_eq!(restore(&mut restored, &saved), Ok(6));
+    assert_eq!(
+        restored
+            .globals()
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<&str>>(),
+        vec!["a", "f", "inf", "s", "t", "m"]
+    );
+    for (name, value) in interpreter.globals() {
+        let restored = restored.get_global(&name).unwrap();
+        assert_eq!(restored.type_name(), value.type_name());
+        assert_eq!(restored.to_string(), value.to_string());
+    }
+    assert_eq!(restored.get_global("a"), Some(TokenLiteral::Integer(2)));
+
+    #[cfg(feature = "bigint")]
+    {
+        let interpreter = run("var big = -9223372036854775807 * 4;");
+        let mut restored = Interpreter::new();
+        restore(&mut restored, &save(&interpreter).unwrap()).unwrap();
+        assert_eq!(restored.get_global("big"), interpreter.get_global("big"));
+    }
+}
+
+#[test]
+fn session_errors() {
+    let interpreter = run("var l = [1]; l[0] = l;");
+    assert_eq!(
+        save(&interpreter),
+        Err("'l' contains itself and can't be saved.".to_string())
+    );
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_global("kept", 1);
+    let bad = r#"{"version": 1, "globals": [
+        {"name": "a", "value": 1},
+        {"name": "b", "value": {"map": [[[1], 2]]}}
+    ]}"#;
+    assert_eq!(
+        restore(&mut interpreter, bad),
+        Err("Invalid saved value for 'b'.".to_string())
+    );
+    assert_eq!(interpreter.get_global("a"), None);
+    assert!(restore(&mut interpreter, r#"{"version": 2, "globals": []}"#).is_err());
+    assert!(restore(&mut interpreter, "[").is_err());
+    assert!(restore_file(&mut interpreter, "missing.json".as_ref()).is_err());
+    assert_eq!(
+        interpreter.get_global("kept"),
+        Some(TokenLiteral::Integer(1))
+    );
+}
+
+#[test]
+fn repl_save_command() {
+    let path = std::env::temp_dir().join("rlox_repl_save_command.json");
+    let mut repl = Repl::new(&LoxConfig::default(), OutputMode::Json);
+    assert!(repl.eval("var total = [1, 2];\n".to_string()).1.is_ok());
+    let (events, result) = repl.eval(format!(":save {}\n", path.display()));
+    assert!(result.is_ok());
+    assert!(events.is_empty());
+    let (events, _) = repl.eval(":load x\n".to_string());
+    assert!(matches!(
+        events.as_slice(),
+        [Event::Diagnostic { line: None, .. }]
+    ));
+    let (events, _) = repl.eval("print @;\n".to_string());
+    assert!(matches!(
+        events.first(),
+        Some(Event::Diagnostic { line: Some(4), .. })
+    ));
+
+    let mut interpreter = Interpreter::new();
+    assert_eq!(restore_file(&mut interpreter, &path), Ok(1));
+    assert_eq!(
+        interpreter.eval_expr_str("total[1]").unwrap(),
+        TokenLiteral::Integer(2)
+    );
+    std::fs::remove_file(path).unwrap();
+}
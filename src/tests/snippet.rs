@@ -0,0 +1,41 @@
+use crate::snippet::{render, render_from};
+
+#[test]
+fn snippet_carets() {
+    let source = "var a = 1;\n\tprint a +;\n";
+    assert_eq!(
+        render(source, 21..22, "Expression Expected"),
+        "  |\n2 | \tprint a +;\n  | \t         ^ Expression Expected"
+    );
+    // only the first line of a span is shown
+    assert_eq!(
+        render(source, 4..15, ""),
+        "  |\n1 | var a = 1;\n  |     ^^^^^^"
+    );
+    assert_eq!(
+        render("print 'é'", 6..10, ""),
+        "  |\n1 | print 'é'\n  |       ^^^"
+    );
+    assert_eq!(
+        render("print", 5..5, "at end"),
+        "  |\n1 | print\n  |      ^ at end"
+    );
+    assert_eq!(
+        render_from(source, 9, 21..22, ""),
+        "   |\n10 | \tprint a +;\n   | \t         ^"
+    );
+}
+
+#[test]
+fn styles() {
+    use crate::color::Style;
+
+    assert_eq!(
+        Style::Error.apply("Error[E2001]", true),
+        "\x1b[1;31mError[E2001]\x1b[0m"
+    );
+    assert_eq!(Style::Warning.apply("w", true), "\x1b[1;33mw\x1b[0m");
+    assert_eq!(Style::Note.apply("1 |", true), "\x1b[36m1 |\x1b[0m");
+    assert_eq!(Style::Error.apply("plain", false), "plain");
+    assert_eq!(Style::Note.apply("", true), "");
+}
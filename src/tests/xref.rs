@@ -0,0 +1,116 @@
+use crate::{
+    parser::Parser,
+    xref::{index, query, Query, Scope, Site, SiteKind},
+};
+
+fn sites(source: &str, q: Query) -> Vec<(Scope, Site)> {
+    let statements = Parser::new()
+        .load(source.to_string())
+        .expect("Failed to parse");
+    return query(&index(&statements), &q);
+}
+
+#[test]
+fn xref_finds_definitions_and_references() {
+    let source = "var a = 1;\nvar b = a;\na = b + 2;\nvar a = 3;\n";
+    assert_eq!(
+        sites(source, Query::Definition("a".to_string())),
+        vec![
+            (
+                Scope::Global,
+                Site {
+                    kind: SiteKind::Definition,
+                    line: 1
+                }
+            ),
+            (
+                Scope::Global,
+                Site {
+                    kind: SiteKind::Definition,
+                    line: 4
+                }
+            ),
+        ]
+    );
+    assert_eq!(
+        sites(source, Query::References("a".to_string())),
+        vec![
+            (
+                Scope::Global,
+                Site {
+                    kind: SiteKind::Read,
+                    line: 2
+                }
+            ),
+            (
+                Scope::Global,
+                Site {
+                    kind: SiteKind::Write,
+                    line: 3
+                }
+            ),
+        ]
+    );
+    assert!(sites(source, Query::References("c".to_string())).is_empty());
+}
+
+#[test]
+fn xref_match_bindings_shadow_globals() {
+    let source = "var x = 1;\nprint match 2 {\n  x => x * 2\n};\nprint x;\n";
+    let references = sites(source, Query::References("x".to_string()));
+    assert_eq!(
+        references,
+        vec![
+            (
+                Scope::MatchArm(3),
+                Site {
+                    kind: SiteKind::Read,
+                    line: 3
+                }
+            ),
+            (
+                Scope::Global,
+                Site {
+                    kind: SiteKind::Read,
+                    line: 5
+                }
+            ),
+        ]
+    );
+    let source = "var x = [1];\nfor (x in x)\n  print x;\n";
+    assert_eq!(
+        sites(source, Query::References("x".to_string())),
+        vec![
+            (
+                Scope::Global,
+                Site {
+                    kind: SiteKind::Read,
+                    line: 2
+                }
+            ),
+            (
+                Scope::ForLoop(2),
+                Site {
+                    kind: SiteKind::Read,
+                    line: 3
+                }
+            ),
+        ]
+    );
+
+    // an outer binding is found past the inner ones by its depth
+    let source = "for (x in [1])
+  for (y in [2])
+    print x + y;
+";
+    assert_eq!(
+        sites(source, Query::References("x".to_string())),
+        vec![(
+            Scope::ForLoop(1),
+            Site {
+                kind: SiteKind::Read,
+                line: 3
+            }
+        )]
+    );
+}
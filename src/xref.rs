@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::{Expression, Local, Pattern, Token},
+    stmt::Statement,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    Definition(String),
+    References(String),
+}
+
+impl Query {
+    pub fn name(&self) -> &str {
+        return match self {
+            Query::Definition(name) | Query::References(name) => name,
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope {
+    Global,
+    ///bound by the match arm on this line, only visible inside the arm
+    MatchArm(usize),
+    ///the variable of the for loop on this line, only visible in its body
+    ForLoop(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SiteKind {
+    Definition,
+    Read,
+    Write,
+}
+
+impl SiteKind {
+    pub fn name(&self) -> &'static str {
+        return match self {
+            SiteKind::Definition => "definition",
+            SiteKind::Read => "read",
+            SiteKind::Write => "write",
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Site {
+    pub kind: SiteKind,
+    pub line: usize,
+}
+
+///one variable and every place that defines or uses it, a global that's
+///declared more than once is still one binding
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binding {
+    pub name: String,
+    pub scope: Scope,
+    pub definitions: Vec<Site>,
+    pub references: Vec<Site>,
+}
+
+impl Binding {
+    fn new(name: &str, scope: Scope) -> Binding {
+        return Binding {
+            name: name.to_string(),
+            scope,
+            definitions: vec![],
+            references: vec![],
+        };
+    }
+}
+
+///finds the binding of every variable in `statements` by the scopes
+///`resolver::resolve` found them in, as it does for what the parser
+///returns, globals come first in the order they're first seen
+pub fn index(statements: &[Statement]) -> Vec<Binding> {
+    let mut index = Index::default();
+    for statement in statements {
+        index.statement(statement);
+    }
+    let mut bindings = index.globals;
+    bindings.append(&mut index.locals);
+    return bindings;
+}
+
+///the sites answering `query`, sorted by line
+pub fn query(bindings: &[Binding], query: &Query) -> Vec<(Scope, Site)> {
+    let mut sites = vec![];
+    for binding in bindings.iter().filter(|b| b.name == query.name()) {
+        let found = match query {
+            Query::Definition(_) => &binding.definitions,
+            Query::References(_) => &binding.references,
+        };
+        sites.extend(found.iter().map(|site| (binding.scope, site.clone())));
+    }
+    sites.sort_by_key(|(_, site)| site.line);
+    return sites;
+}
+
+#[derive(Default)]
+struct Index {
+    globals: Vec<Binding>,
+    global_names: HashMap<String, usize>,
+    locals: Vec<Binding>,
+    ///indices into `locals` of the arm and loop bindings in scope, in the
+    ///order the resolver counts depths in
+    scopes: Vec<usize>,
+}
+
+impl Index {
+    fn global(&mut self, name: &str) -> &mut Binding {
+        let i = match self.global_names.get(name) {
+            Some(i) => *i,
+            None => {
+                self.globals.push(Binding::new(name, Scope::Global));
+                self.global_names
+                    .insert(name.to_string(), self.globals.len() - 1);
+                self.globals.len() - 1
+            }
+        };
+        return &mut self.globals[i];
+    }
+
+    ///the binding a use of `name` the resolver found at `local` refers to,
+    ///every local scope binds one name
+    fn resolve(&mut self, name: &Token, local: Option<Local>) -> &mut Binding {
+        return match local {
+            Some(local) => &mut self.locals[self.scopes[self.scopes.len() - 1 - local.depth]],
+            None => self.global(&name.lexeme),
+        };
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Expression(expr, _) | Statement::Print(expr, _) => self.expression(expr),
+            Statement::Var(name, initializer, _) => {
+                if let Some(initializer) = initializer {
+                    self.expression(initializer);
+                }
+                self.global(&name.lexeme).definitions.push(Site {
+                    kind: SiteKind::Definition,
+                    line: name.line,
+                });
+            }
+            Statement::Destructure {
+                names, initializer, ..
+            } => {
+                self.expression(initializer);
+                for name in names {
+                    self.global(&name.lexeme).definitions.push(Site {
+                        kind: SiteKind::Definition,
+                        line: name.line,
+                    });
+                }
+            }
+            Statement::ForIn {
+                name,
+                iterable,
+                body,
+                ..
+            } => {
+                self.expression(iterable);
+                self.scoped(name, Scope::ForLoop(name.line));
+                self.statement(body);
+                self.scopes.pop();
+            }
+            Statement::Assert {
+                condition, message, ..
+            } => {
+                self.expression(condition);
+                if let Some(message) = message {
+                    self.expression(message);
+                }
+            }
+            Statement::Dump(_) => {}
+        }
+    }
+
+    ///pushes a local binding defined by `name`, the caller pops it
+    fn scoped(&mut self, name: &Token, scope: Scope) {
+        let mut binding = Binding::new(&name.lexeme, scope);
+        binding.definitions.push(Site {
+            kind: SiteKind::Definition,
+            line: name.line,
+        });
+        self.locals.push(binding);
+        self.scopes.push(self.locals.len() - 1);
+    }
+
+    ///walks `expr` with a stack of its own, a long chain like `a + b + c`
+    ///nests as deep as it has operators
+    fn expression(&mut self, expr: &Expression) {
+        let mut tasks = vec![Task::Visit(expr)];
+        while let Some(task) = tasks.pop() {
+            let expr = match task {
+                Task::Visit(expr) => expr,
+                Task::Write(name, local) => {
+                    self.resolve(name, local).references.push(Site {
+                        kind: SiteKind::Write,
+                        line: name.line,
+                    });
+                    continue;
+                }
+                Task::Bind(name) => {
+                    self.scoped(name, Scope::MatchArm(name.line));
+                    continue;
+                }
+                Task::Unbind => {
+                    self.scopes.pop();
+                    continue;
+                }
+            };
+            match expr {
+                Expression::Binary { left, right, .. }
+                | Expression::Logical { left, right, .. } => {
+                    tasks.extend([Task::Visit(right), Task::Visit(left)]);
+                }
+                Expression::Unary { right, .. } => tasks.push(Task::Visit(right)),
+                Expression::Grouping(inner, _) => tasks.push(Task::Visit(inner)),
+                Expression::Variable { name, local } => {
+                    self.resolve(name, *local).references.push(Site {
+                        kind: SiteKind::Read,
+                        line: name.line,
+                    })
+                }
+                Expression::Assign { name, value, local } => {
+                    tasks.extend([Task::Write(name, *local), Task::Visit(value)]);
+                }
+                Expression::Match { subject, arms, .. } => {
+                    for arm in arms.iter().rev() {
+                        if let Pattern::Binding(name) = &arm.pattern {
+                            tasks.extend([Task::Unbind, Task::Visit(&arm.body), Task::Bind(name)]);
+                        } else {
+                            tasks.push(Task::Visit(&arm.body));
+                        }
+                    }
+                    tasks.push(Task::Visit(subject));
+                }
+                Expression::ListLiteral { elements, .. }
+                | Expression::TupleLiteral { elements, .. } => {
+                    tasks.extend(elements.iter().rev().map(Task::Visit));
+                }
+                Expression::MapLiteral { entries, .. } => {
+                    for (key, value) in entries.iter().rev() {
+                        tasks.extend([Task::Visit(value), Task::Visit(key)]);
+                    }
+                }
+                // storing into a list reads the variable holding it
+                Expression::Index { object, index, .. } => {
+                    tasks.extend([Task::Visit(index), Task::Visit(object)]);
+                }
+                Expression::IndexAssign {
+                    object,
+                    index,
+                    value,
+                    ..
+                } => {
+                    tasks.extend([Task::Visit(value), Task::Visit(index), Task::Visit(object)]);
+                }
+                Expression::Literal(_, _) | Expression::Empty => {}
+            }
+        }
+    }
+}
+
+enum Task<'a> {
+    Visit(&'a Expression),
+    ///the assignment to `name` after its value
+    Write(&'a Token, Option<Local>),
+    ///a match arm binding `name` starts
+    Bind(&'a Token),
+    Unbind,
+}
+
+///one line per site, `path:line: kind of 'name'`
+pub fn render_text(path: &str, query: &Query, sites: &[(Scope, Site)]) -> String {
+    let mut out = String::new();
+    for (scope, site) in sites {
+        out.push_str(&format!(
+            "{}:{}: {} of '{}'",
+            path,
+            site.line,
+            site.kind.name(),
+            query.name()
+        ));
+        match scope {
+            Scope::Global => {}
+            Scope::MatchArm(line) => {
+                out.push_str(&format!(" (bound by the match arm on line {})", line))
+            }
+            Scope::ForLoop(line) => {
+                out.push_str(&format!(" (bound by the for loop on line {})", line))
+            }
+        }
+        out.push('\n');
+    }
+    return out;
+}
+
+#[cfg(feature = "json")]
+pub fn render_json(path: &str, query: &Query, sites: &[(Scope, Site)]) -> String {
+    let sites = sites
+        .iter()
+        .map(|(scope, site)| {
+            let scope = match scope {
+                Scope::Global => serde_json::json!({ "kind": "global" }),
+                Scope::MatchArm(line) => serde_json::json!({ "kind": "match-arm", "line": line }),
+                Scope::ForLoop(line) => serde_json::json!({ "kind": "for-loop", "line": line }),
+            };
+            serde_json::json!({ "line": site.line, "kind": site.kind.name(), "scope": scope })
+        })
+        .collect::<Vec<serde_json::Value>>();
+    let json = serde_json::json!({
+        "file": path,
+        "name": query.name(),
+        "query": match query {
+            Query::Definition(_) => "definition",
+            Query::References(_) => "references",
+        },
+        "sites": sites,
+    });
+    return json.to_string();
+}
@@ -0,0 +1,104 @@
+//! Runs every tests/difftest/*.lox through rlox and a reference Lox
+//! implementation and reports where their output differs.
+//!
+//! RLOX_REFERENCE is the reference command, split on whitespace so
+//! `java -jar jlox.jar` works, the script path is appended to it.
+//! RLOX_DIFFTEST_ARGS holds extra flags for rlox, like `--dialect strict`.
+//!
+//!     RLOX_REFERENCE=clox cargo test --features difftest --test difftest
+
+#![allow(clippy::needless_return)]
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+struct Run {
+    stdout: String,
+    success: bool,
+}
+
+fn run(program: &str, args: &[String], script: &Path) -> Run {
+    let output = Command::new(program)
+        .args(args)
+        .arg(script)
+        .output()
+        .unwrap_or_else(|e| panic!("Failed to run {}: {}", program, e));
+    return Run {
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        success: output.status.success(),
+    };
+}
+
+///describes the first line where the outputs differ
+fn compare(rlox: &Run, reference: &Run) -> Option<String> {
+    if rlox.success != reference.success {
+        return Some(format!(
+            "rlox {} but the reference {}",
+            if rlox.success { "succeeded" } else { "failed" },
+            if reference.success {
+                "succeeded"
+            } else {
+                "failed"
+            }
+        ));
+    }
+    let ours = rlox.stdout.lines().collect::<Vec<&str>>();
+    let theirs = reference.stdout.lines().collect::<Vec<&str>>();
+    for i in 0..ours.len().max(theirs.len()) {
+        let (a, b) = (ours.get(i), theirs.get(i));
+        if a != b {
+            return Some(format!(
+                "line {}: rlox printed {:?}, the reference printed {:?}",
+                i + 1,
+                a.copied().unwrap_or("<nothing>"),
+                b.copied().unwrap_or("<nothing>")
+            ));
+        }
+    }
+    return None;
+}
+
+#[test]
+fn difftest() {
+    let reference = match std::env::var("RLOX_REFERENCE") {
+        Ok(reference) if !reference.trim().is_empty() => reference,
+        _ => {
+            eprintln!("RLOX_REFERENCE is not set, skipping the differential tests");
+            return;
+        }
+    };
+    let mut reference = reference.split_whitespace().map(str::to_string);
+    let reference_program = reference.next().expect("Empty RLOX_REFERENCE");
+    let reference_args = reference.collect::<Vec<String>>();
+    let mut rlox_args = std::env::var("RLOX_DIFFTEST_ARGS")
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect::<Vec<String>>();
+    rlox_args.push("run".to_string());
+
+    let mut scripts = std::fs::read_dir("tests/difftest")
+        .expect("Failed to read tests/difftest")
+        .map(|entry| entry.expect("Failed to read entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lox"))
+        .collect::<Vec<PathBuf>>();
+    scripts.sort();
+
+    let mut divergences = vec![];
+    for script in &scripts {
+        let ours = run(env!("CARGO_BIN_EXE_rlox"), &rlox_args, script);
+        let theirs = run(&reference_program, &reference_args, script);
+        if let Some(divergence) = compare(&ours, &theirs) {
+            divergences.push(format!("{}: {}", script.display(), divergence));
+        }
+    }
+    assert!(
+        divergences.is_empty(),
+        "{} of {} programs diverge from the reference:\n{}",
+        divergences.len(),
+        scripts.len(),
+        divergences.join("\n")
+    );
+}